@@ -0,0 +1,1176 @@
+//! Local user accounts with bookmarks and private study notes.
+//!
+//! Accounts are stored as JSON in `DATA_DIR/accounts.json` (default
+//! `userdata`), with passwords hashed using Argon2. Signing in sets an
+//! HTTP-only `session` cookie, `Secure` over HTTPS; sessions live in memory
+//! and lapse with the cookie after 30 days, and a restart signs everyone out.
+//! Bookmarks and notes are keyed to a book, chapter and the index of a section
+//! within that chapter, and are shown inline on the index page while signed in.
+
+use actix_web::cookie::{time::Duration, Cookie, SameSite};
+use actix_web::http::StatusCode;
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use argon2::password_hash::{PasswordHash, PasswordHasher, PasswordVerifier, SaltString};
+use argon2::Argon2;
+use rand::rngs::OsRng;
+use rand::RngCore;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::HashMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::i18n::Locale;
+use crate::overlay::CustomHeading;
+use crate::{canon, layout, AppState, Book};
+
+const SESSION_COOKIE: &str = "session";
+const SESSION_DAYS: i64 = 30;
+const MIN_PASSWORD_LENGTH: usize = 8;
+const MAX_NOTE_LENGTH: usize = 10_000;
+
+/// A section of a chapter, identified by its position among the chapter's headings.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Location {
+    pub book: String,
+    pub chapter: u32,
+    /// Zero-based index of the section within the chapter.
+    pub section: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Bookmark {
+    pub id: u64,
+    #[serde(flatten)]
+    pub location: Location,
+    /// The heading at the time the bookmark was made.
+    pub heading: String,
+    pub created: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct Note {
+    pub id: u64,
+    #[serde(flatten)]
+    pub location: Location,
+    pub heading: String,
+    pub text: String,
+    pub created: u64,
+    pub updated: u64,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct User {
+    username: String,
+    password_hash: String,
+    #[serde(default)]
+    bookmarks: Vec<Bookmark>,
+    #[serde(default)]
+    notes: Vec<Note>,
+    /// Personal heading overlay (see `overlay.rs`).
+    #[serde(default)]
+    pub headings: Vec<CustomHeading>,
+}
+
+#[derive(Default, Serialize, Deserialize)]
+struct Accounts {
+    next_id: u64,
+    users: Vec<User>,
+    /// Counts changes, so a save never overwrites a newer one.
+    #[serde(skip)]
+    revision: u64,
+}
+
+impl Accounts {
+    /// The next revision of the store, serialized for saving.
+    fn snapshot(&mut self) -> (u64, io::Result<String>) {
+        self.revision += 1;
+        let json = serde_json::to_string_pretty(self).map_err(io::Error::other);
+        (self.revision, json)
+    }
+}
+
+#[derive(Debug)]
+pub enum AccountError {
+    InvalidUsername,
+    WeakPassword,
+    UsernameTaken,
+    BadCredentials,
+    Storage(io::Error),
+}
+
+impl AccountError {
+    pub fn response(&self) -> HttpResponse {
+        let (status, message) = match self {
+            AccountError::InvalidUsername => (
+                StatusCode::BAD_REQUEST,
+                "Usernames are 3 to 32 letters, digits, '-' or '_'".to_string(),
+            ),
+            AccountError::WeakPassword => (
+                StatusCode::BAD_REQUEST,
+                format!("Passwords need at least {} characters", MIN_PASSWORD_LENGTH),
+            ),
+            AccountError::UsernameTaken => {
+                (StatusCode::CONFLICT, "That username is taken".to_string())
+            }
+            AccountError::BadCredentials => (
+                StatusCode::UNAUTHORIZED,
+                "Incorrect username or password".to_string(),
+            ),
+            AccountError::Storage(err) => {
+                log::warn!("Could not save accounts: {}", err);
+                (
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Could not save changes".to_string(),
+                )
+            }
+        };
+        HttpResponse::build(status).json(json!({ "error": message }))
+    }
+}
+
+/// A signed-in browser: who it is and when the session lapses, in seconds
+/// since the Unix epoch, like its cookie.
+struct Session {
+    username: String,
+    expires: u64,
+}
+
+pub struct AccountStore {
+    path: PathBuf,
+    accounts: Mutex<Accounts>,
+    /// The revision last written to `path`. Saves take this lock rather than
+    /// `accounts`, so requests are not held up by the disk.
+    saved: Mutex<u64>,
+    /// Session token to its user.
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl AccountStore {
+    /// Opens `accounts.json` in the data directory, starting empty when it does
+    /// not exist yet. An unreadable file is an error rather than something to
+    /// overwrite.
+    pub fn load(dir: &Path) -> io::Result<Self> {
+        let path = dir.join("accounts.json");
+
+        let accounts = match std::fs::read_to_string(&path) {
+            Ok(source) => serde_json::from_str(&source).map_err(|err| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("{}: {}", path.display(), err),
+                )
+            })?,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Accounts::default(),
+            Err(err) => return Err(err),
+        };
+
+        Ok(AccountStore {
+            path,
+            accounts: Mutex::new(accounts),
+            saved: Mutex::new(0),
+            sessions: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Writes a snapshot of the store unless a later one was written first.
+    /// This blocks on the disk, so it runs off the async workers.
+    fn save(&self, revision: u64, json: io::Result<String>) -> io::Result<()> {
+        let json = json?;
+        let mut saved = self.saved.lock().unwrap();
+        if *saved >= revision {
+            return Ok(());
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        let temp = self.path.with_extension("json.tmp");
+        std::fs::write(&temp, json)?;
+        std::fs::rename(&temp, &self.path)?;
+        *saved = revision;
+        Ok(())
+    }
+
+    pub fn register(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> std::result::Result<(), AccountError> {
+        let valid_username = (3..=32).contains(&username.len())
+            && username
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+        if !valid_username {
+            return Err(AccountError::InvalidUsername);
+        }
+        if password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(AccountError::WeakPassword);
+        }
+
+        let salt = SaltString::generate(&mut OsRng);
+        let password_hash = Argon2::default()
+            .hash_password(password.as_bytes(), &salt)
+            .map_err(|err| AccountError::Storage(io::Error::other(err.to_string())))?
+            .to_string();
+
+        let (revision, json) = {
+            let mut accounts = self.accounts.lock().unwrap();
+            if accounts
+                .users
+                .iter()
+                .any(|user| user.username.eq_ignore_ascii_case(username))
+            {
+                return Err(AccountError::UsernameTaken);
+            }
+            accounts.users.push(User {
+                username: username.to_string(),
+                password_hash,
+                bookmarks: Vec::new(),
+                notes: Vec::new(),
+                headings: Vec::new(),
+            });
+            accounts.snapshot()
+        };
+        // Registration already runs on the blocking pool, so it saves in place.
+        if let Err(err) = self.save(revision, json) {
+            let mut accounts = self.accounts.lock().unwrap();
+            accounts.users.retain(|user| user.username != username);
+            return Err(AccountError::Storage(err));
+        }
+        Ok(())
+    }
+
+    /// Checks a password, returning the stored spelling of the username.
+    pub fn verify(
+        &self,
+        username: &str,
+        password: &str,
+    ) -> std::result::Result<String, AccountError> {
+        // Hashing is slow, so check the password without holding the lock.
+        let (stored_name, stored_hash) = {
+            let accounts = self.accounts.lock().unwrap();
+            let user = accounts
+                .users
+                .iter()
+                .find(|user| user.username.eq_ignore_ascii_case(username))
+                .ok_or(AccountError::BadCredentials)?;
+            (user.username.clone(), user.password_hash.clone())
+        };
+        let hash = PasswordHash::new(&stored_hash).map_err(|_| AccountError::BadCredentials)?;
+        Argon2::default()
+            .verify_password(password.as_bytes(), &hash)
+            .map_err(|_| AccountError::BadCredentials)?;
+        Ok(stored_name)
+    }
+
+    /// Starts a session lasting as long as its cookie, dropping any that
+    /// have lapsed.
+    pub fn start_session(&self, username: &str) -> String {
+        let mut bytes = [0u8; 32];
+        OsRng.fill_bytes(&mut bytes);
+        let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        let now = now();
+        let mut sessions = self.sessions.lock().unwrap();
+        sessions.retain(|_, session| session.expires > now);
+        sessions.insert(
+            token.clone(),
+            Session {
+                username: username.to_string(),
+                expires: now + SESSION_DAYS as u64 * 24 * 60 * 60,
+            },
+        );
+        token
+    }
+
+    pub fn end_session(&self, token: &str) {
+        self.sessions.lock().unwrap().remove(token);
+    }
+
+    /// The signed-in username for a request, if its session cookie is valid.
+    pub fn current_user(&self, req: &HttpRequest) -> Option<String> {
+        let cookie = req.cookie(SESSION_COOKIE)?;
+        let mut sessions = self.sessions.lock().unwrap();
+        let session = sessions.get(cookie.value())?;
+        if session.expires <= now() {
+            sessions.remove(cookie.value());
+            return None;
+        }
+        Some(session.username.clone())
+    }
+
+    /// Runs `change` against a user's record and saves the store on the
+    /// blocking pool when the record changed, undoing the change when it
+    /// cannot be saved.
+    pub async fn update<T>(
+        self: &Arc<Self>,
+        username: &str,
+        change: impl FnOnce(&mut User, &mut u64) -> T,
+    ) -> std::result::Result<T, AccountError> {
+        let (result, before, after, revision, json) = {
+            let mut accounts = self.accounts.lock().unwrap();
+            let index = accounts
+                .users
+                .iter()
+                .position(|user| user.username == username)
+                .ok_or(AccountError::BadCredentials)?;
+            let before = (accounts.users[index].clone(), accounts.next_id);
+            let Accounts { next_id, users, .. } = &mut *accounts;
+            let result = change(&mut users[index], next_id);
+            let after = (accounts.users[index].clone(), accounts.next_id);
+            if after == before {
+                return Ok(result);
+            }
+            let (revision, json) = accounts.snapshot();
+            (result, before, after, revision, json)
+        };
+
+        let store = Arc::clone(self);
+        let saved = web::block(move || store.save(revision, json))
+            .await
+            .map_err(io::Error::other)
+            .and_then(|saved| saved);
+        if let Err(err) = saved {
+            // Undo only what nothing has changed again since.
+            let mut accounts = self.accounts.lock().unwrap();
+            if let Some(user) = accounts.users.iter_mut().find(|user| **user == after.0) {
+                *user = before.0;
+            }
+            if accounts.next_id == after.1 {
+                accounts.next_id = before.1;
+            }
+            return Err(AccountError::Storage(err));
+        }
+        Ok(result)
+    }
+
+    /// A snapshot of a user's bookmarks, notes and headings for rendering.
+    pub fn annotations(&self, username: &str) -> Option<Annotations> {
+        let accounts = self.accounts.lock().unwrap();
+        let user = accounts
+            .users
+            .iter()
+            .find(|user| user.username == username)?;
+        Some(Annotations {
+            username: user.username.clone(),
+            bookmarks: user.bookmarks.clone(),
+            notes: user.notes.clone(),
+            headings: user.headings.clone(),
+        })
+    }
+}
+
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+/// Resolves a location against the loaded books, normalizing the book name
+/// and returning the heading it points at.
+fn resolve(books: &[Book], location: &Location) -> Option<(Location, String)> {
+    let name = canon::lookup(&location.book).map_or(location.book.as_str(), |info| info.name);
+    let heading = books
+        .iter()
+        .find(|book| book.name == name)?
+        .chapters
+        .iter()
+        .find(|c| c.chapter_number == location.chapter)?
+        .sections
+        .get(location.section)?
+        .heading
+        .clone();
+    Some((
+        Location {
+            book: name.to_string(),
+            chapter: location.chapter,
+            section: location.section,
+        },
+        heading,
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// Page rendering
+// ---------------------------------------------------------------------------
+
+/// The signed-in user's bookmarks, notes and headings, layered onto the index page.
+pub struct Annotations {
+    pub username: String,
+    bookmarks: Vec<Bookmark>,
+    notes: Vec<Note>,
+    pub headings: Vec<CustomHeading>,
+}
+
+impl Annotations {
+    /// Bookmark toggle and notes shown beside one section heading.
+    pub fn section_html(&self, book: &str, chapter: u32, section: usize) -> String {
+        let location = Location {
+            book: book.to_string(),
+            chapter,
+            section,
+        };
+        let bookmark = self.bookmarks.iter().find(|b| b.location == location);
+        let (bookmark_id, pressed, icon) = match bookmark {
+            Some(bookmark) => (bookmark.id.to_string(), "true", "★"),
+            None => (String::new(), "false", "☆"),
+        };
+
+        let mut notes = String::new();
+        for note in self.notes.iter().filter(|n| n.location == location) {
+            notes.push_str(&format!(
+                r#"
+                                <div class="note" data-note-id="{}">
+                                    <p class="note-text">{}</p>
+                                    <button class="note-action" onclick="editNote(this)">Edit</button>
+                                    <button class="note-action" onclick="deleteNote(this)">Delete</button>
+                                </div>"#,
+                note.id,
+                layout::escape(&note.text)
+            ));
+        }
+
+        format!(
+            r#"
+                            <button class="bookmark-toggle" onclick="toggleBookmark(this)" data-bookmark-id="{}" aria-pressed="{}" aria-label="Bookmark this section">{}</button>
+                            <div class="annotations" data-book="{}" data-chapter="{}" data-section="{}">{}
+                                <button class="note-action" onclick="showNoteForm(this)">+ Add note</button>
+                                <form class="note-form" onsubmit="return saveNote(this)" hidden>
+                                    <textarea name="text" rows="3" maxlength="{}" aria-label="Note" required></textarea>
+                                    <button type="submit" class="note-action">Save</button>
+                                </form>
+                            </div>"#,
+            bookmark_id, pressed, icon, book, chapter, section, notes, MAX_NOTE_LENGTH
+        )
+    }
+}
+
+/// Links shown in the index header: sign-in, or the user's name and sign-out.
+pub fn nav_html(account: Option<&Annotations>, locale: &Locale) -> String {
+    match account {
+        Some(account) => format!(
+            r#"
+                <span class="signed-in">{}</span>
+                <a href="/login" onclick="return signOut()">{}</a>"#,
+            locale.format(
+                "nav.signed_in_as",
+                &[("name", &layout::escape(&account.username))]
+            ),
+            locale.text("nav.sign_out")
+        ),
+        None => format!(
+            r#"
+                <a href="/login">{}</a>"#,
+            locale.text("nav.sign_in")
+        ),
+    }
+}
+
+pub const STYLES: &str = r#"
+        .section-heading {
+            flex-wrap: wrap;
+        }
+
+        .heading-text {
+            flex: 1;
+        }
+
+        .signed-in {
+            color: rgba(255,255,255,0.9);
+        }
+
+        .bookmark-toggle {
+            border: none;
+            background: none;
+            color: #764ba2;
+            font-size: 1.1rem;
+            line-height: 1.4;
+            cursor: pointer;
+        }
+
+        .annotations {
+            flex-basis: 100%;
+            padding-inline-start: 1.3rem;
+            display: flex;
+            flex-direction: column;
+            align-items: flex-start;
+            gap: 0.35rem;
+        }
+
+        .note {
+            width: 100%;
+            background: #f7fafc;
+            border-inline-start: 3px solid #764ba2;
+            border-radius: 4px;
+            padding: 0.4rem 0.6rem;
+        }
+
+        .note-text {
+            font-size: 0.85rem;
+            color: #2d3748;
+            white-space: pre-wrap;
+        }
+
+        .note-action {
+            border: none;
+            background: none;
+            color: #667eea;
+            font-family: 'Inter', sans-serif;
+            font-size: 0.75rem;
+            cursor: pointer;
+            padding: 0;
+            margin-inline-end: 0.5rem;
+        }
+
+        .note-action:hover {
+            text-decoration: underline;
+        }
+
+        .note-form {
+            width: 100%;
+        }
+
+        .note-form[hidden] {
+            display: none;
+        }
+
+        .note-form textarea {
+            width: 100%;
+            padding: 0.5rem;
+            border: 1px solid #e2e8f0;
+            border-radius: 6px;
+            font-family: 'Inter', sans-serif;
+            font-size: 0.85rem;
+        }
+"#;
+
+pub const SCRIPT: &str = r#"
+        function sendJson(method, url, body) {
+            return fetch(url, {
+                method: method,
+                headers: { 'Content-Type': 'application/json' },
+                body: body === undefined ? undefined : JSON.stringify(body)
+            }).then(response => response.ok ? response : response.json().then(error => Promise.reject(error)));
+        }
+
+        function reportError(error) {
+            alert(error && error.error ? error.error : 'Something went wrong');
+        }
+
+        function sectionOf(element) {
+            const annotations = element.closest('.section-heading').querySelector('.annotations');
+            return {
+                book: annotations.dataset.book,
+                chapter: Number(annotations.dataset.chapter),
+                section: Number(annotations.dataset.section)
+            };
+        }
+
+        function toggleBookmark(button) {
+            const id = button.dataset.bookmarkId;
+            const request = id
+                ? sendJson('DELETE', '/api/bookmarks/' + id)
+                : sendJson('POST', '/api/bookmarks', sectionOf(button)).then(response => response.json());
+            request.then(bookmark => {
+                button.dataset.bookmarkId = id ? '' : bookmark.id;
+                button.setAttribute('aria-pressed', id ? 'false' : 'true');
+                button.textContent = id ? '☆' : '★';
+            }).catch(reportError);
+        }
+
+        function showNoteForm(button, note) {
+            const form = button.closest('.annotations').querySelector('.note-form');
+            form.dataset.noteId = note ? note.dataset.noteId : '';
+            form.elements.text.value = note ? note.querySelector('.note-text').textContent : '';
+            form.hidden = false;
+            form.elements.text.focus();
+        }
+
+        function editNote(button) {
+            showNoteForm(button, button.closest('.note'));
+        }
+
+        function saveNote(form) {
+            const id = form.dataset.noteId;
+            const text = form.elements.text.value;
+            const request = id
+                ? sendJson('PUT', '/api/notes/' + id, { text: text })
+                : sendJson('POST', '/api/notes', Object.assign(sectionOf(form), { text: text }));
+            request.then(() => location.reload()).catch(reportError);
+            return false;
+        }
+
+        function deleteNote(button) {
+            if (!confirm('Delete this note?')) {
+                return;
+            }
+            sendJson('DELETE', '/api/notes/' + button.closest('.note').dataset.noteId)
+                .then(() => button.closest('.note').remove())
+                .catch(reportError);
+        }
+
+        function signOut() {
+            sendJson('POST', '/api/account/logout').then(() => location.reload()).catch(reportError);
+            return false;
+        }
+"#;
+
+pub async fn login_page() -> Result<HttpResponse> {
+    let body = r#"
+        <div class="card account">
+            <h2>Sign in</h2>
+            <p class="intro">Sign in to bookmark headings and keep private study notes.</p>
+            <form id="accountForm" onsubmit="return submitAccount(event)">
+                <label>Username <input name="username" autocomplete="username" required></label>
+                <label>Password <input name="password" type="password" autocomplete="current-password" required></label>
+                <p class="form-error" id="formError" role="alert"></p>
+                <div class="form-actions">
+                    <button type="submit" value="login">Sign in</button>
+                    <button type="submit" value="register" class="secondary">Create account</button>
+                </div>
+            </form>
+        </div>"#;
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(layout::page("Sign in", LOGIN_STYLES, body, LOGIN_SCRIPT)))
+}
+
+const LOGIN_STYLES: &str = r#"
+        .account {
+            max-width: 420px;
+            margin: 0 auto 1.5rem;
+        }
+
+        .intro {
+            color: #4a5568;
+            margin-bottom: 1.25rem;
+        }
+
+        .account label {
+            display: block;
+            font-weight: 500;
+            margin-bottom: 1rem;
+        }
+
+        .account input {
+            display: block;
+            width: 100%;
+            margin-top: 0.35rem;
+            padding: 0.75rem;
+            border: 2px solid #e2e8f0;
+            border-radius: 10px;
+            font-family: 'Inter', sans-serif;
+            font-size: 1rem;
+        }
+
+        .account input:focus {
+            outline: none;
+            border-color: #667eea;
+        }
+
+        .form-error {
+            color: #c53030;
+            min-height: 1.25rem;
+            margin-bottom: 0.5rem;
+        }
+
+        .form-actions {
+            display: flex;
+            gap: 0.75rem;
+        }
+
+        .form-actions button {
+            padding: 0.75rem 1.5rem;
+            border: 2px solid transparent;
+            border-radius: 10px;
+            background: linear-gradient(135deg, #667eea 0%, #764ba2 100%);
+            color: white;
+            font-family: 'Inter', sans-serif;
+            font-size: 0.95rem;
+            font-weight: 500;
+            cursor: pointer;
+        }
+
+        .form-actions button.secondary {
+            background: white;
+            color: #667eea;
+            border-color: #e2e8f0;
+        }
+"#;
+
+const LOGIN_SCRIPT: &str = r#"    <script>
+        function submitAccount(event) {
+            const form = event.target;
+            const action = event.submitter ? event.submitter.value : 'login';
+            fetch('/api/account/' + action, {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({
+                    username: form.elements.username.value,
+                    password: form.elements.password.value
+                })
+            }).then(response => {
+                if (response.ok) {
+                    location.href = '/';
+                } else {
+                    response.json().then(error => {
+                        document.getElementById('formError').textContent = error.error;
+                    });
+                }
+            });
+            return false;
+        }
+    </script>
+"#;
+
+// ---------------------------------------------------------------------------
+// Handlers
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize, ToSchema)]
+pub struct Credentials {
+    username: String,
+    password: String,
+}
+
+/// Sets the session cookie, marked `Secure` when the server speaks HTTPS.
+fn session_response(req: &HttpRequest, mut response: HttpResponse, token: String) -> HttpResponse {
+    let cookie = Cookie::build(SESSION_COOKIE, token)
+        .path("/")
+        .http_only(true)
+        .secure(req.app_config().secure())
+        .same_site(SameSite::Lax)
+        .max_age(Duration::days(SESSION_DAYS))
+        .finish();
+    // A freshly built cookie always forms a valid header value.
+    let _ = response.add_cookie(&cookie);
+    response
+}
+
+pub fn sign_in_required() -> HttpResponse {
+    HttpResponse::Unauthorized().json(json!({ "error": "Sign in required" }))
+}
+
+/// Creates an account and signs in.
+#[utoipa::path(
+    post,
+    path = "/api/account/register",
+    tag = "account",
+    request_body = Credentials,
+    responses(
+        (status = 201, description = "Account created and signed in; sets the `session` cookie", body = crate::openapi::Username),
+        (status = 400, description = "Username or password rejected", body = crate::openapi::ErrorBody),
+        (status = 409, description = "Username taken", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_register(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    credentials: web::Json<Credentials>,
+) -> Result<HttpResponse> {
+    let Credentials { username, password } = credentials.into_inner();
+    let username = username.trim().to_string();
+    // Hashing is slow, so it runs on the blocking pool rather than the worker.
+    let accounts = data.accounts.clone();
+    let name = username.clone();
+    if let Err(err) = web::block(move || accounts.register(&name, &password)).await? {
+        return Ok(err.response());
+    }
+    let token = data.accounts.start_session(&username);
+    Ok(session_response(
+        &req,
+        HttpResponse::Created().json(json!({ "username": username })),
+        token,
+    ))
+}
+
+/// Signs in.
+#[utoipa::path(
+    post,
+    path = "/api/account/login",
+    tag = "account",
+    request_body = Credentials,
+    responses(
+        (status = 200, description = "Signed in; sets the `session` cookie", body = crate::openapi::Username),
+        (status = 401, description = "Wrong username or password", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_login(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    credentials: web::Json<Credentials>,
+) -> Result<HttpResponse> {
+    let Credentials { username, password } = credentials.into_inner();
+    let accounts = data.accounts.clone();
+    match web::block(move || accounts.verify(username.trim(), &password)).await? {
+        Ok(username) => {
+            let token = data.accounts.start_session(&username);
+            Ok(session_response(
+                &req,
+                HttpResponse::Ok().json(json!({ "username": username })),
+                token,
+            ))
+        }
+        Err(err) => Ok(err.response()),
+    }
+}
+
+/// Signs out and clears the session cookie.
+#[utoipa::path(
+    post,
+    path = "/api/account/logout",
+    tag = "account",
+    responses((status = 204, description = "Signed out"))
+)]
+pub async fn api_logout(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    if let Some(cookie) = req.cookie(SESSION_COOKIE) {
+        data.accounts.end_session(cookie.value());
+    }
+    let mut response = HttpResponse::NoContent().finish();
+    let mut expired = Cookie::build(SESSION_COOKIE, "")
+        .path("/")
+        .secure(req.app_config().secure())
+        .finish();
+    expired.make_removal();
+    let _ = response.add_cookie(&expired);
+    Ok(response)
+}
+
+/// The signed-in user.
+#[utoipa::path(
+    get,
+    path = "/api/account",
+    tag = "account",
+    security(("session" = [])),
+    responses((status = 200, description = "The signed-in user", body = crate::openapi::Username), (status = 401, description = "Not signed in", body = crate::openapi::ErrorBody))
+)]
+pub async fn api_account(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    match data.accounts.current_user(&req) {
+        Some(username) => Ok(HttpResponse::Ok().json(json!({ "username": username }))),
+        None => Ok(sign_in_required()),
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AnnotationFilter {
+    /// Only this book's annotations.
+    book: Option<String>,
+    /// Only this chapter's annotations.
+    chapter: Option<u32>,
+}
+
+impl AnnotationFilter {
+    fn matches(&self, location: &Location) -> bool {
+        let book = self
+            .book
+            .as_deref()
+            .map(|book| canon::lookup(book).map_or(book, |info| info.name));
+        book.is_none_or(|book| location.book == book)
+            && self
+                .chapter
+                .is_none_or(|chapter| location.chapter == chapter)
+    }
+}
+
+/// The signed-in user's bookmarks, optionally for one book or chapter.
+#[utoipa::path(
+    get,
+    path = "/api/bookmarks",
+    tag = "account",
+    security(("session" = [])),
+    params(AnnotationFilter),
+    responses((status = 200, description = "Bookmarks", body = crate::openapi::BookmarkList), (status = 401, description = "Not signed in", body = crate::openapi::ErrorBody))
+)]
+pub async fn api_bookmarks(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    filter: web::Query<AnnotationFilter>,
+) -> Result<HttpResponse> {
+    let Some(account) = data
+        .accounts
+        .current_user(&req)
+        .and_then(|username| data.accounts.annotations(&username))
+    else {
+        return Ok(sign_in_required());
+    };
+    let bookmarks: Vec<&Bookmark> = account
+        .bookmarks
+        .iter()
+        .filter(|b| filter.matches(&b.location))
+        .collect();
+    Ok(HttpResponse::Ok().json(json!({ "total": bookmarks.len(), "bookmarks": bookmarks })))
+}
+
+/// Bookmarks a section.
+#[utoipa::path(
+    post,
+    path = "/api/bookmarks",
+    tag = "account",
+    security(("session" = [])),
+    request_body = Location,
+    responses(
+        (status = 201, description = "The new bookmark, or the existing one for that section", body = Bookmark),
+        (status = 400, description = "No such section", body = crate::openapi::ErrorBody),
+        (status = 401, description = "Not signed in", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_add_bookmark(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    location: web::Json<Location>,
+) -> Result<HttpResponse> {
+    let Some(username) = data.accounts.current_user(&req) else {
+        return Ok(sign_in_required());
+    };
+    let Some((location, heading)) = resolve(&data.books, &location) else {
+        return Ok(HttpResponse::BadRequest().json(json!({ "error": "No such section" })));
+    };
+
+    let result = data
+        .accounts
+        .update(&username, |user, next_id| {
+            if let Some(existing) = user.bookmarks.iter().find(|b| b.location == location) {
+                return existing.clone();
+            }
+            *next_id += 1;
+            let bookmark = Bookmark {
+                id: *next_id,
+                location,
+                heading,
+                created: now(),
+            };
+            user.bookmarks.push(bookmark.clone());
+            bookmark
+        })
+        .await;
+    match result {
+        Ok(bookmark) => Ok(HttpResponse::Created().json(bookmark)),
+        Err(err) => Ok(err.response()),
+    }
+}
+
+/// Deletes a bookmark.
+#[utoipa::path(
+    delete,
+    path = "/api/bookmarks/{id}",
+    tag = "account",
+    security(("session" = [])),
+    params(("id" = u64, Path, description = "Bookmark id")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, description = "No such bookmark", body = crate::openapi::ErrorBody),
+        (status = 401, description = "Not signed in", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_delete_bookmark(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> Result<HttpResponse> {
+    let Some(username) = data.accounts.current_user(&req) else {
+        return Ok(sign_in_required());
+    };
+    let id = id.into_inner();
+    let result = data
+        .accounts
+        .update(&username, |user, _| {
+            let before = user.bookmarks.len();
+            user.bookmarks.retain(|b| b.id != id);
+            before != user.bookmarks.len()
+        })
+        .await;
+    match result {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Ok(HttpResponse::NotFound().json(json!({ "error": "No such bookmark" }))),
+        Err(err) => Ok(err.response()),
+    }
+}
+
+/// The signed-in user's notes, optionally for one book or chapter.
+#[utoipa::path(
+    get,
+    path = "/api/notes",
+    tag = "account",
+    security(("session" = [])),
+    params(AnnotationFilter),
+    responses((status = 200, description = "Notes", body = crate::openapi::NoteList), (status = 401, description = "Not signed in", body = crate::openapi::ErrorBody))
+)]
+pub async fn api_notes(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    filter: web::Query<AnnotationFilter>,
+) -> Result<HttpResponse> {
+    let Some(account) = data
+        .accounts
+        .current_user(&req)
+        .and_then(|username| data.accounts.annotations(&username))
+    else {
+        return Ok(sign_in_required());
+    };
+    let notes: Vec<&Note> = account
+        .notes
+        .iter()
+        .filter(|n| filter.matches(&n.location))
+        .collect();
+    Ok(HttpResponse::Ok().json(json!({ "total": notes.len(), "notes": notes })))
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct NewNote {
+    #[serde(flatten)]
+    location: Location,
+    text: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct NoteText {
+    text: String,
+}
+
+fn check_note(text: &str) -> std::result::Result<String, HttpResponse> {
+    let text = text.trim();
+    if text.is_empty() || text.chars().count() > MAX_NOTE_LENGTH {
+        return Err(HttpResponse::BadRequest().json(json!({
+            "error": format!("Notes are 1 to {} characters", MAX_NOTE_LENGTH)
+        })));
+    }
+    Ok(text.to_string())
+}
+
+/// Adds a private note to a section.
+#[utoipa::path(
+    post,
+    path = "/api/notes",
+    tag = "account",
+    security(("session" = [])),
+    request_body = NewNote,
+    responses(
+        (status = 201, description = "The new note", body = Note),
+        (status = 400, description = "No such section, or bad note length", body = crate::openapi::ErrorBody),
+        (status = 401, description = "Not signed in", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_add_note(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    note: web::Json<NewNote>,
+) -> Result<HttpResponse> {
+    let Some(username) = data.accounts.current_user(&req) else {
+        return Ok(sign_in_required());
+    };
+    let text = match check_note(&note.text) {
+        Ok(text) => text,
+        Err(response) => return Ok(response),
+    };
+    let Some((location, heading)) = resolve(&data.books, &note.location) else {
+        return Ok(HttpResponse::BadRequest().json(json!({ "error": "No such section" })));
+    };
+
+    let result = data
+        .accounts
+        .update(&username, |user, next_id| {
+            *next_id += 1;
+            let created = now();
+            let note = Note {
+                id: *next_id,
+                location,
+                heading,
+                text,
+                created,
+                updated: created,
+            };
+            user.notes.push(note.clone());
+            note
+        })
+        .await;
+    match result {
+        Ok(note) => Ok(HttpResponse::Created().json(note)),
+        Err(err) => Ok(err.response()),
+    }
+}
+
+/// Changes a note's text.
+#[utoipa::path(
+    put,
+    path = "/api/notes/{id}",
+    tag = "account",
+    security(("session" = [])),
+    params(("id" = u64, Path, description = "Note id")),
+    request_body = NoteText,
+    responses(
+        (status = 200, description = "The updated note", body = Note),
+        (status = 400, description = "Bad note length", body = crate::openapi::ErrorBody),
+        (status = 404, description = "No such note", body = crate::openapi::ErrorBody),
+        (status = 401, description = "Not signed in", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_update_note(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    id: web::Path<u64>,
+    body: web::Json<NoteText>,
+) -> Result<HttpResponse> {
+    let Some(username) = data.accounts.current_user(&req) else {
+        return Ok(sign_in_required());
+    };
+    let text = match check_note(&body.text) {
+        Ok(text) => text,
+        Err(response) => return Ok(response),
+    };
+    let id = id.into_inner();
+
+    let result = data
+        .accounts
+        .update(&username, |user, _| {
+            let note = user.notes.iter_mut().find(|n| n.id == id)?;
+            note.text = text;
+            note.updated = now();
+            Some(note.clone())
+        })
+        .await;
+    match result {
+        Ok(Some(note)) => Ok(HttpResponse::Ok().json(note)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(json!({ "error": "No such note" }))),
+        Err(err) => Ok(err.response()),
+    }
+}
+
+/// Deletes a note.
+#[utoipa::path(
+    delete,
+    path = "/api/notes/{id}",
+    tag = "account",
+    security(("session" = [])),
+    params(("id" = u64, Path, description = "Note id")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, description = "No such note", body = crate::openapi::ErrorBody),
+        (status = 401, description = "Not signed in", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_delete_note(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> Result<HttpResponse> {
+    let Some(username) = data.accounts.current_user(&req) else {
+        return Ok(sign_in_required());
+    };
+    let id = id.into_inner();
+    let result = data
+        .accounts
+        .update(&username, |user, _| {
+            let before = user.notes.len();
+            user.notes.retain(|n| n.id != id);
+            before != user.notes.len()
+        })
+        .await;
+    match result {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Ok(HttpResponse::NotFound().json(json!({ "error": "No such note" }))),
+        Err(err) => Ok(err.response()),
+    }
+}
@@ -0,0 +1,169 @@
+//! HTTP caching: content-hash `ETag`s, `Last-Modified` from when the data was
+//! loaded, `Cache-Control`, `304 Not Modified` for conditional requests, and a
+//! cache of rendered pages so `/` is generated once per language and heading set.
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use actix_web::http::header::{self, HeaderValue, HttpDate};
+use actix_web::web::Bytes;
+use actix_web::{HttpRequest, HttpResponse};
+
+use crate::translations::HeadingSet;
+use crate::Book;
+
+/// `Cache-Control` for data that only changes with a new deployment.
+pub const API_CACHE_CONTROL: &str = "public, max-age=3600";
+/// API responses depend on the heading-set and session cookies as well as
+/// the format asked for.
+pub const API_VARY: &str = "Accept, Accept-Encoding, Cookie";
+/// Pages vary with cookies and sign-in, so browsers revalidate every time.
+pub const PAGE_CACHE_CONTROL: &str = "public, no-cache";
+pub const PRIVATE_CACHE_CONTROL: &str = "private, no-cache";
+
+/// The headings as loaded at startup: a hash over every heading set and the
+/// time it was loaded.
+pub struct DataVersion {
+    pub hash: String,
+    pub loaded: SystemTime,
+}
+
+impl DataVersion {
+    pub fn compute(books: &[Book], sets: &[HeadingSet]) -> Self {
+        let mut hasher = sha1_smol::Sha1::new();
+        // Serializing the API's own types cannot fail.
+        hasher.update(&serde_json::to_vec(books).expect("serializable books"));
+        for set in sets {
+            hasher.update(set.info.id.as_bytes());
+            hasher.update(&serde_json::to_vec(&set.books).expect("serializable books"));
+        }
+        // HTTP dates have whole seconds; dropping the rest keeps comparisons exact.
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        DataVersion {
+            hash: hasher.digest().to_string()[..16].to_string(),
+            loaded: UNIX_EPOCH + Duration::from_secs(now),
+        }
+    }
+}
+
+/// A strong entity tag from a hash of `body`.
+pub fn etag(body: &[u8]) -> String {
+    format!(
+        "\"{}\"",
+        &sha1_smol::Sha1::from(body).digest().to_string()[..16]
+    )
+}
+
+/// A weak entity tag, for bodies the compression middleware may re-encode.
+pub fn weak_etag(body: &[u8]) -> String {
+    format!("W/{}", etag(body))
+}
+
+/// Whether the client's copy is current: `If-None-Match` when sent (compared
+/// weakly), otherwise `If-Modified-Since`.
+fn not_modified(req: &HttpRequest, etag: &str, last_modified: Option<SystemTime>) -> bool {
+    let headers = req.headers();
+    if let Some(tags) = headers.get(header::IF_NONE_MATCH) {
+        let opaque = |tag: &str| tag.trim().trim_start_matches("W/").to_string();
+        return tags.to_str().is_ok_and(|tags| {
+            tags.trim() == "*" || tags.split(',').any(|tag| opaque(tag) == opaque(etag))
+        });
+    }
+    headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<HttpDate>().ok())
+        .is_some_and(|since| last_modified.is_some_and(|last| last <= SystemTime::from(since)))
+}
+
+/// Adds validators and `Cache-Control` to a successful response, or replaces
+/// it with `304 Not Modified` when the client already has it.
+pub fn validated(
+    req: &HttpRequest,
+    mut response: HttpResponse,
+    etag: &str,
+    last_modified: Option<SystemTime>,
+    cache_control: &'static str,
+) -> HttpResponse {
+    if !response.status().is_success() {
+        return response;
+    }
+    if not_modified(req, etag, last_modified) {
+        let mut unchanged = HttpResponse::NotModified();
+        // A 304 repeats the headers a cache would otherwise update.
+        for name in [header::VARY, header::CONTENT_ENCODING] {
+            if let Some(value) = response.headers().get(&name) {
+                unchanged.insert_header((name, value.clone()));
+            }
+        }
+        response = unchanged.finish();
+    }
+    let headers = response.headers_mut();
+    if let Ok(value) = HeaderValue::from_str(etag) {
+        headers.insert(header::ETAG, value);
+    }
+    if let Some(last_modified) = last_modified {
+        if let Ok(value) = HeaderValue::from_str(&HttpDate::from(last_modified).to_string()) {
+            headers.insert(header::LAST_MODIFIED, value);
+        }
+    }
+    headers.insert(
+        header::CACHE_CONTROL,
+        HeaderValue::from_static(cache_control),
+    );
+    response
+}
+
+/// A rendered page and its entity tag.
+pub struct Page {
+    pub html: Bytes,
+    pub etag: String,
+}
+
+/// Rendered pages keyed by data version, language and heading set. The keys
+/// are negotiated values rather than raw query strings, so the cache holds at
+/// most one page per language and set.
+#[derive(Default)]
+pub struct PageCache {
+    pages: Mutex<HashMap<(String, String, String), Arc<Page>>>,
+}
+
+impl PageCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The cached page for `key`, rendering it with `render` the first time.
+    pub fn get_or_render(
+        &self,
+        version: &DataVersion,
+        language: &str,
+        set: &str,
+        render: impl FnOnce() -> String,
+    ) -> Arc<Page> {
+        let key = (version.hash.clone(), language.to_string(), set.to_string());
+        if let Some(page) = self.pages.lock().unwrap().get(&key) {
+            return page.clone();
+        }
+        // Rendered outside the lock; a concurrent first request renders the same page.
+        let page = Arc::new(page(render()));
+        self.pages
+            .lock()
+            .unwrap()
+            .entry(key)
+            .or_insert(page)
+            .clone()
+    }
+}
+
+/// Wraps rendered HTML with its weak entity tag.
+pub fn page(html: String) -> Page {
+    Page {
+        etag: weak_etag(html.as_bytes()),
+        html: html.into(),
+    }
+}
@@ -0,0 +1,264 @@
+//! Reference metadata for the 66 books: OSIS identifiers, common abbreviations
+//! and literary genres, and the canon profiles that order them.
+
+use async_graphql::Enum;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::{i18n, search, Book};
+
+#[derive(Debug)]
+pub struct BookInfo {
+    pub name: &'static str,
+    pub osis: &'static str,
+    pub abbreviations: &'static [&'static str],
+}
+
+const fn book(
+    name: &'static str,
+    osis: &'static str,
+    abbreviations: &'static [&'static str],
+) -> BookInfo {
+    BookInfo {
+        name,
+        osis,
+        abbreviations,
+    }
+}
+
+/// The traditional grouping of the books by literary genre.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Enum, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Genre {
+    Law,
+    History,
+    Wisdom,
+    MajorProphets,
+    MinorProphets,
+    Gospels,
+    PaulineEpistles,
+    GeneralEpistles,
+    Apocalyptic,
+}
+
+pub const BOOKS: &[BookInfo] = &[
+    // Old Testament
+    book("Genesis", "Gen", &["Gen", "Ge", "Gn"]),
+    book("Exodus", "Exod", &["Exod", "Ex", "Exo"]),
+    book("Leviticus", "Lev", &["Lev", "Lv"]),
+    book("Numbers", "Num", &["Num", "Nm", "Nb"]),
+    book("Deuteronomy", "Deut", &["Deut", "Dt"]),
+    book("Joshua", "Josh", &["Josh", "Jos"]),
+    book("Judges", "Judg", &["Judg", "Jdg"]),
+    book("Ruth", "Ruth", &["Ru", "Rth"]),
+    book("1 Samuel", "1Sam", &["1 Sam", "1 Sa"]),
+    book("2 Samuel", "2Sam", &["2 Sam", "2 Sa"]),
+    book("1 Kings", "1Kgs", &["1 Kgs", "1 Ki"]),
+    book("2 Kings", "2Kgs", &["2 Kgs", "2 Ki"]),
+    book("1 Chronicles", "1Chr", &["1 Chr", "1 Ch"]),
+    book("2 Chronicles", "2Chr", &["2 Chr", "2 Ch"]),
+    book("Ezra", "Ezra", &["Ezr"]),
+    book("Nehemiah", "Neh", &["Neh", "Ne"]),
+    book("Esther", "Esth", &["Esth", "Est"]),
+    book("Job", "Job", &["Jb"]),
+    book("Psalms", "Ps", &["Ps", "Psa", "Pss", "Psalm"]),
+    book("Proverbs", "Prov", &["Prov", "Pr", "Prv"]),
+    book("Ecclesiastes", "Eccl", &["Eccl", "Ecc", "Qoh"]),
+    book(
+        "Song of Solomon",
+        "Song",
+        &["Song", "Song of Songs", "SS", "Cant"],
+    ),
+    book("Isaiah", "Isa", &["Isa", "Is"]),
+    book("Jeremiah", "Jer", &["Jer", "Je"]),
+    book("Lamentations", "Lam", &["Lam", "La"]),
+    book("Ezekiel", "Ezek", &["Ezek", "Eze", "Ezk"]),
+    book("Daniel", "Dan", &["Dan", "Dn"]),
+    book("Hosea", "Hos", &["Hos", "Ho"]),
+    book("Joel", "Joel", &["Jl"]),
+    book("Amos", "Amos", &["Am"]),
+    book("Obadiah", "Obad", &["Obad", "Ob"]),
+    book("Jonah", "Jonah", &["Jon"]),
+    book("Micah", "Mic", &["Mic", "Mi"]),
+    book("Nahum", "Nah", &["Nah", "Na"]),
+    book("Habakkuk", "Hab", &["Hab", "Hb"]),
+    book("Zephaniah", "Zeph", &["Zeph", "Zep"]),
+    book("Haggai", "Hag", &["Hag", "Hg"]),
+    book("Zechariah", "Zech", &["Zech", "Zec"]),
+    book("Malachi", "Mal", &["Mal", "Ml"]),
+    // New Testament
+    book("Matthew", "Matt", &["Matt", "Mt"]),
+    book("Mark", "Mark", &["Mk", "Mrk"]),
+    book("Luke", "Luke", &["Lk", "Luk"]),
+    book("John", "John", &["Jn", "Jhn"]),
+    book("Acts", "Acts", &["Ac"]),
+    book("Romans", "Rom", &["Rom", "Ro"]),
+    book("1 Corinthians", "1Cor", &["1 Cor", "1 Co"]),
+    book("2 Corinthians", "2Cor", &["2 Cor", "2 Co"]),
+    book("Galatians", "Gal", &["Gal", "Ga"]),
+    book("Ephesians", "Eph", &["Eph"]),
+    book("Philippians", "Phil", &["Phil", "Php"]),
+    book("Colossians", "Col", &["Col"]),
+    book("1 Thessalonians", "1Thess", &["1 Thess", "1 Th"]),
+    book("2 Thessalonians", "2Thess", &["2 Thess", "2 Th"]),
+    book("1 Timothy", "1Tim", &["1 Tim", "1 Ti"]),
+    book("2 Timothy", "2Tim", &["2 Tim", "2 Ti"]),
+    book("Titus", "Titus", &["Tit"]),
+    book("Philemon", "Phlm", &["Phlm", "Phm"]),
+    book("Hebrews", "Heb", &["Heb"]),
+    book("James", "Jas", &["Jas", "Jm"]),
+    book("1 Peter", "1Pet", &["1 Pet", "1 Pt"]),
+    book("2 Peter", "2Pet", &["2 Pet", "2 Pt"]),
+    book("1 John", "1John", &["1 Jn", "1 Jhn"]),
+    book("2 John", "2John", &["2 Jn", "2 Jhn"]),
+    book("3 John", "3John", &["3 Jn", "3 Jhn"]),
+    book("Jude", "Jude", &["Jud"]),
+    book("Revelation", "Rev", &["Rev", "Re"]),
+];
+
+impl Genre {
+    /// The genre's name for display, e.g. "Pauline epistles".
+    pub fn label(self) -> &'static str {
+        match self {
+            Genre::Law => "Law",
+            Genre::History => "History",
+            Genre::Wisdom => "Wisdom",
+            Genre::MajorProphets => "Major prophets",
+            Genre::MinorProphets => "Minor prophets",
+            Genre::Gospels => "Gospels",
+            Genre::PaulineEpistles => "Pauline epistles",
+            Genre::GeneralEpistles => "General epistles",
+            Genre::Apocalyptic => "Apocalyptic",
+        }
+    }
+}
+
+impl BookInfo {
+    /// The book's genre, from its place in the canon.
+    pub fn genre(&self) -> Genre {
+        let position = BOOKS
+            .iter()
+            .position(|info| info.name == self.name)
+            .unwrap_or(0);
+        match position {
+            0..=4 => Genre::Law,
+            5..=16 => Genre::History,
+            17..=21 => Genre::Wisdom,
+            22..=26 => Genre::MajorProphets,
+            27..=38 => Genre::MinorProphets,
+            39..=42 => Genre::Gospels,
+            43 => Genre::History,
+            44..=56 => Genre::PaulineEpistles,
+            57..=64 => Genre::GeneralEpistles,
+            _ => Genre::Apocalyptic,
+        }
+    }
+}
+
+/// The Hebrew Bible's books in Tanakh order: Torah, Nevi'im, Ketuvim.
+const TANAKH: [&str; 39] = [
+    "Genesis",
+    "Exodus",
+    "Leviticus",
+    "Numbers",
+    "Deuteronomy",
+    "Joshua",
+    "Judges",
+    "1 Samuel",
+    "2 Samuel",
+    "1 Kings",
+    "2 Kings",
+    "Isaiah",
+    "Jeremiah",
+    "Ezekiel",
+    "Hosea",
+    "Joel",
+    "Amos",
+    "Obadiah",
+    "Jonah",
+    "Micah",
+    "Nahum",
+    "Habakkuk",
+    "Zephaniah",
+    "Haggai",
+    "Zechariah",
+    "Malachi",
+    "Psalms",
+    "Proverbs",
+    "Job",
+    "Song of Solomon",
+    "Ruth",
+    "Lamentations",
+    "Ecclesiastes",
+    "Esther",
+    "Daniel",
+    "Ezra",
+    "Nehemiah",
+    "1 Chronicles",
+    "2 Chronicles",
+];
+
+/// Which books the server shows, and in what order.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Profile {
+    /// All 66 books in the order of English Protestant Bibles.
+    #[default]
+    Protestant,
+    /// The 39 books of the Hebrew Bible in Tanakh order.
+    Tanakh,
+}
+
+impl Profile {
+    pub const NAMES: &'static str = "protestant or tanakh";
+
+    pub fn parse(name: &str) -> Option<Profile> {
+        match name.to_lowercase().as_str() {
+            "protestant" => Some(Profile::Protestant),
+            "tanakh" => Some(Profile::Tanakh),
+            _ => None,
+        }
+    }
+
+    /// Puts `books` in the profile's order, leaving out books it does not
+    /// include. Books unknown to `BOOKS` follow the rest under `Protestant`.
+    pub fn apply(self, mut books: Vec<Book>) -> Vec<Book> {
+        let order: Vec<&str> = match self {
+            Profile::Protestant => BOOKS.iter().map(|info| info.name).collect(),
+            Profile::Tanakh => TANAKH.to_vec(),
+        };
+        let position = |book: &Book| order.iter().position(|name| *name == book.name);
+        if self == Profile::Tanakh {
+            books.retain(|book| position(book).is_some());
+        }
+        books.sort_by_key(|book| position(book).unwrap_or(order.len()));
+        books
+    }
+}
+
+/// Looks up a book's metadata by its canonical name.
+pub fn info(name: &str) -> Option<&'static BookInfo> {
+    BOOKS.iter().find(|info| info.name == name)
+}
+
+/// Resolves a book from its name, OSIS identifier or one of its abbreviations,
+/// ignoring case, spaces and periods ("1 jn.", "1John" and "1 John" all match).
+/// Localized names and abbreviations ("Éxodo", "1 R") resolve too.
+pub fn lookup(text: &str) -> Option<&'static BookInfo> {
+    let key = normalize(text);
+    BOOKS
+        .iter()
+        .find(|info| {
+            normalize(info.name) == key
+                || normalize(info.osis) == key
+                || info.abbreviations.iter().any(|a| normalize(a) == key)
+        })
+        .or_else(|| i18n::localized_book(&key).and_then(info))
+}
+
+pub fn normalize(text: &str) -> String {
+    search::fold(text)
+        .chars()
+        .filter(|c| !c.is_whitespace() && *c != '.')
+        .collect()
+}
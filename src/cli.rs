@@ -0,0 +1,525 @@
+//! Command-line subcommands. Without arguments the binary starts the web server.
+
+use std::io::{self, IsTerminal, Write};
+
+use serde::Serialize;
+use terminal_size::{terminal_size, Width};
+
+use crate::canon;
+use crate::config::{self, Config};
+use crate::export::{Format, Selection};
+use crate::flashcards::{Deck, DeckFormat};
+use crate::reference::Passage;
+use crate::search::{Analyzer, SearchIndex};
+use crate::{get_bible_data, i18n, v1, verses, Book, Testament, PLACEHOLDER_HEADING};
+
+/// Width assumed when output is not a terminal and `COLUMNS` is unset.
+const DEFAULT_WIDTH: usize = 80;
+const MIN_WIDTH: usize = 40;
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+const BOLD: &str = "1";
+const DIM: &str = "2";
+const YELLOW: &str = "33";
+const CYAN: &str = "36";
+
+const USAGE: &str = "\
+Usage: bible-headlines [COMMAND | SERVER OPTIONS]
+
+Without a command, starts the web server. Server options, which override
+the environment and the config file (see --print-config):
+  --config FILE       TOML config file (default: bible-headlines.toml)
+  --bind ADDRESS      Address to listen on (BIND_ADDRESS, default 0.0.0.0)
+  --port PORT         Port to listen on (PORT, default 8080)
+  --data-dir DIR      Where accounts are saved and data files are read
+                      (DATA_DIR, default userdata)
+  --translation NAME  Verse translation, with the verses feature
+                      (VERSE_TRANSLATION)
+  --canon PROFILE     protestant or tanakh (CANON, default protestant)
+  --tls-cert FILE     PEM certificate chain; serves HTTPS with --tls-key
+                      (TLS_CERT)
+  --tls-key FILE      PEM private key (TLS_KEY)
+  --log-level LEVEL   off, error, warn, info, debug or trace
+                      (LOG_LEVEL, default warn)
+  --workers N         Worker threads (WORKERS, default one per core)
+  --print-config      Print the effective configuration as TOML and exit
+
+Commands:
+  show <REFERENCE...> [--json]
+        Print the headings of the selected books and chapters, e.g. Gen 1-3
+        or John 3 Acts 2; separate references with ';' when they would
+        otherwise run together.
+  search <WORD...> [--limit 20] [--json]
+        Print the headings matching every word, best first.
+  books [--testament old|new] [--json]
+        List the books with their OSIS ids, chapter counts and genres.
+  tui   Browse the books, chapters and headings in the terminal, with a
+        live search box and a testament toggle.
+  export <markdown|opml|text|epub|pdf> [--columns 2] [REFERENCE...]
+        Print an outline of the headings. References such as \"Genesis\" or
+        \"Gen 1-3\" select books and chapters; none selects the whole Bible.
+        --columns 2 lays a PDF handout out in two columns.
+  flashcards <apkg|csv> [REFERENCE...]
+        Print a \"Which chapter is ...?\" flashcard deck for the selected
+        books (the whole Bible when none are given).
+  import-verses <NAME> <FILE>
+        Convert an eBible.org verse-per-line (VPL) file into the verse file
+        NAME.tsv in the data directory's verses folder (or VERSES_DIR), e.g.
+        \"import-verses kjv eng-kjv2006_vpl.txt\".
+  help  Show this message
+
+Commands read the books in the canon the server would use, from the config
+file (CONFIG_FILE) or CANON. show, search and books color their output on a
+terminal (set NO_COLOR to turn that off) and fit it to the terminal's width.
+--json prints the same JSON as /api/v1 instead.
+";
+
+/// Runs the subcommand named by `args` (the arguments after the program name).
+pub fn run(args: &[String]) -> io::Result<()> {
+    match args[0].as_str() {
+        "show" => show(&args[1..]),
+        "search" => search(&args[1..]),
+        "books" => books(&args[1..]),
+        "tui" => crate::tui::run(load_books()),
+        "export" => export(&args[1..]),
+        "flashcards" => flashcards(&args[1..]),
+        "import-verses" => import_verses(&args[1..]),
+        "help" | "--help" | "-h" => {
+            print!("{}", USAGE);
+            Ok(())
+        }
+        other => usage_error(&format!("Unknown command '{}'", other)),
+    }
+}
+
+pub fn usage_error(message: &str) -> io::Result<()> {
+    eprintln!("{}\n\n{}", message, USAGE);
+    std::process::exit(2);
+}
+
+/// The server's configuration from the config file and environment. Like
+/// the server, commands log at its level to stderr and read the message
+/// catalogs from its data directory.
+fn load_config() -> Config {
+    let config = match Config::load(&[]) {
+        Ok(config) => config,
+        Err(err) => {
+            eprintln!("{}", err);
+            std::process::exit(2);
+        }
+    };
+    if let Ok(level) = config.log_level() {
+        let _ = env_logger::Builder::new().filter_level(level).try_init();
+    }
+    i18n::load_catalogs(&config.data_path(&config::LOCALES));
+    config
+}
+
+/// The books as the server loads them: ordered and filtered by the canon
+/// profile from the config file and environment.
+fn load_books() -> Vec<Book> {
+    load_config().canon.apply(get_bible_data())
+}
+
+fn export(args: &[String]) -> io::Result<()> {
+    let Some(mut format) = args.first().and_then(|name| Format::parse(name)) else {
+        return usage_error("export needs a format: markdown, opml, text, epub or pdf");
+    };
+
+    let mut references = Vec::new();
+    let mut rest = args[1..].iter();
+    while let Some(arg) = rest.next() {
+        if arg != "--columns" {
+            references.push(arg.as_str());
+            continue;
+        }
+        match (format, rest.next().map(|n| n.parse())) {
+            (Format::Pdf { .. }, Some(Ok(columns @ 1..=2))) => format = Format::Pdf { columns },
+            (Format::Pdf { .. }, _) => return usage_error("--columns must be 1 or 2"),
+            _ => return usage_error("--columns only applies to pdf"),
+        }
+    }
+
+    let books = load_books();
+    let selection = match Selection::parse(&books, &join_references(&references)) {
+        Ok(selection) => selection,
+        Err(err) => return usage_error(&err),
+    };
+    io::stdout().write_all(&selection.render(format))
+}
+
+fn flashcards(args: &[String]) -> io::Result<()> {
+    let Some(format) = args.first().and_then(|name| DeckFormat::parse(name)) else {
+        return usage_error("flashcards needs a format: apkg or csv");
+    };
+
+    let books = load_books();
+    let words: Vec<&str> = args[1..].iter().map(String::as_str).collect();
+    let selection = match Selection::parse(&books, &join_references(&words)) {
+        Ok(selection) => selection,
+        Err(err) => return usage_error(&err),
+    };
+    io::stdout().write_all(&Deck::from_selection(&selection).render(format))
+}
+
+/// Joins the words naming references into the `;`-separated list
+/// [`Selection::parse`] takes, so they need no quoting: `John 3` is one
+/// reference, and `Gen 1-3 John` is split after the longest run of words
+/// that reads as one. A `;` among the words separates references explicitly.
+fn join_references(words: &[&str]) -> String {
+    let joined = words.join(" ");
+    let mut references = Vec::new();
+    for part in joined.split(';') {
+        let tokens: Vec<&str> = part.split_whitespace().collect();
+        let mut start = 0;
+        while start < tokens.len() {
+            let end = (start + 1..=tokens.len())
+                .rev()
+                .find(|&end| Passage::parse(&tokens[start..end].join(" ")).is_some())
+                .unwrap_or(tokens.len());
+            references.push(tokens[start..end].join(" "));
+            start = end;
+        }
+    }
+    references.join(";")
+}
+
+fn import_verses(args: &[String]) -> io::Result<()> {
+    let [name, file] = args else {
+        return usage_error("import-verses needs a translation name and a file");
+    };
+
+    let source = std::fs::read_to_string(file)?;
+    let imported = verses::import_vpl(&source)
+        .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", file, err)))?;
+    let dir = load_config().data_path(&config::VERSES).path;
+    std::fs::create_dir_all(&dir)?;
+    let path = dir.join(format!("{}.tsv", name));
+    std::fs::write(
+        &path,
+        format!(
+            "# Imported from {}\n# Format: book<TAB>chapter<TAB>verse<TAB>text\n{}",
+            file, imported.tsv
+        ),
+    )?;
+
+    println!("Wrote {} verses to {}", imported.verses, path.display());
+    if !imported.skipped.is_empty() {
+        println!(
+            "Skipped books outside the 66: {}",
+            imported.skipped.join(", ")
+        );
+    }
+    Ok(())
+}
+
+/// The arguments of `show`, `search` and `books`: `--json`, the options the
+/// command takes, and the remaining words.
+struct Arguments<'a> {
+    json: bool,
+    options: Vec<(&'static str, &'a str)>,
+    words: Vec<&'a str>,
+}
+
+impl<'a> Arguments<'a> {
+    fn parse(args: &'a [String], options: &[&'static str]) -> Result<Self, String> {
+        let mut parsed = Arguments {
+            json: false,
+            options: Vec::new(),
+            words: Vec::new(),
+        };
+        let mut rest = args.iter();
+        while let Some(arg) = rest.next() {
+            if arg == "--json" {
+                parsed.json = true;
+            } else if let Some(option) = options.iter().find(|option| **option == arg) {
+                let value = rest
+                    .next()
+                    .ok_or_else(|| format!("{} needs a value", option))?;
+                parsed.options.push((option, value));
+            } else if arg.starts_with("--") {
+                return Err(format!("Unknown option '{}'", arg));
+            } else {
+                parsed.words.push(arg);
+            }
+        }
+        Ok(parsed)
+    }
+
+    fn option(&self, name: &str) -> Option<&'a str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| *option == name)
+            .map(|(_, value)| *value)
+    }
+}
+
+/// How text output is shaped for where it is going: colored only on a
+/// terminal, and wrapped to its width.
+struct Terminal {
+    color: bool,
+    width: usize,
+}
+
+impl Terminal {
+    fn detect() -> Self {
+        let width = terminal_size()
+            .map(|(Width(width), _)| width as usize)
+            .or_else(|| std::env::var("COLUMNS").ok()?.parse().ok())
+            .unwrap_or(DEFAULT_WIDTH);
+        Terminal {
+            color: io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none(),
+            width: width.max(MIN_WIDTH),
+        }
+    }
+
+    fn paint(&self, style: &str, text: &str) -> String {
+        if self.color {
+            format!("\x1b[{}m{}\x1b[0m", style, text)
+        } else {
+            text.to_string()
+        }
+    }
+}
+
+/// Splits `text` at spaces into lines of at most `width` characters; a
+/// longer word gets a line to itself.
+fn wrap(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    for word in text.split_whitespace() {
+        let length = line.chars().count();
+        if length > 0 && length + 1 + word.chars().count() > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(' ');
+        }
+        line.push_str(word);
+    }
+    lines.push(line);
+    lines
+}
+
+/// Shortens `text` to `width` characters, ending in "…" when cut.
+fn truncate(text: &str, width: usize) -> String {
+    if text.chars().count() <= width {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(width.saturating_sub(1)).collect();
+    short.push('…');
+    short
+}
+
+fn print_json<T: Serialize>(value: &T) -> io::Result<()> {
+    let json = serde_json::to_string_pretty(value).map_err(io::Error::other)?;
+    writeln!(io::stdout(), "{}", json)
+}
+
+fn show(args: &[String]) -> io::Result<()> {
+    let args = match Arguments::parse(args, &[]) {
+        Ok(args) => args,
+        Err(err) => return usage_error(&err),
+    };
+    if args.words.is_empty() {
+        return usage_error("show needs a reference, e.g. \"Gen 1-3\"");
+    }
+
+    let books = load_books();
+    let selection = match Selection::parse(&books, &join_references(&args.words)) {
+        Ok(selection) => selection,
+        Err(err) => return usage_error(&err),
+    };
+    if args.json {
+        let chapters: Vec<v1::Chapter> = selection
+            .books
+            .iter()
+            .flat_map(|(book, chapters)| chapters.iter().map(|chapter| v1::chapter(book, chapter)))
+            .collect();
+        return print_json(&chapters);
+    }
+
+    // Chapter numbers take a four-column gutter; headings wrap beside it.
+    let terminal = Terminal::detect();
+    let gutter = "    ";
+    let mut out = io::stdout().lock();
+    for (index, (book, chapters)) in selection.books.iter().enumerate() {
+        if index > 0 {
+            writeln!(out)?;
+        }
+        writeln!(out, "{}", terminal.paint(BOLD, &book.name))?;
+        for chapter in chapters {
+            let mut number = Some(format!("{:>3} ", chapter.chapter_number));
+            for section in &chapter.sections {
+                let style = if section.heading == PLACEHOLDER_HEADING {
+                    DIM
+                } else {
+                    ""
+                };
+                for line in wrap(&section.heading, terminal.width - gutter.len()) {
+                    let prefix = match number.take() {
+                        Some(number) => terminal.paint(YELLOW, &number),
+                        None => gutter.to_string(),
+                    };
+                    let line = if style.is_empty() {
+                        line
+                    } else {
+                        terminal.paint(style, &line)
+                    };
+                    writeln!(out, "{}{}", prefix, line)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn search(args: &[String]) -> io::Result<()> {
+    let args = match Arguments::parse(args, &["--limit"]) {
+        Ok(args) => args,
+        Err(err) => return usage_error(&err),
+    };
+    let limit = match args.option("--limit").map(str::parse) {
+        None => DEFAULT_SEARCH_LIMIT,
+        Some(Ok(limit)) if limit > 0 => limit,
+        Some(_) => return usage_error("--limit must be a positive number"),
+    };
+    if args.words.is_empty() {
+        return usage_error("search needs words to look for");
+    }
+
+    let query = args.words.join(" ");
+    let config = load_config();
+    let books = config.canon.apply(get_bible_data());
+    let index = SearchIndex::build(&books, Analyzer::load(&config.data_path(&config::SYNONYMS)));
+    let hits = index.search(&query);
+    if args.json {
+        return print_json(&v1::SearchResults::new(&query, hits, limit));
+    }
+
+    let terminal = Terminal::detect();
+    let mut out = io::stdout().lock();
+    if hits.is_empty() {
+        return writeln!(out, "No headings match '{}'", query);
+    }
+    let shown = &hits[..hits.len().min(limit)];
+    let references: Vec<String> = shown
+        .iter()
+        .map(|hit| format!("{} {}", hit.book, hit.chapter))
+        .collect();
+    let column = references
+        .iter()
+        .map(|r| r.chars().count())
+        .max()
+        .unwrap_or(0);
+    for (hit, reference) in shown.iter().zip(&references) {
+        let padding = " ".repeat(column - reference.chars().count());
+        let room = terminal.width.saturating_sub(column + 2).max(MIN_WIDTH / 2);
+        writeln!(
+            out,
+            "{}{}  {}",
+            terminal.paint(CYAN, reference),
+            padding,
+            truncate(&hit.heading, room)
+        )?;
+    }
+    if hits.len() > shown.len() {
+        let more = format!(
+            "{} of {} matches; see more with --limit",
+            shown.len(),
+            hits.len()
+        );
+        writeln!(out, "{}", terminal.paint(DIM, &more))?;
+    }
+    Ok(())
+}
+
+fn books(args: &[String]) -> io::Result<()> {
+    let args = match Arguments::parse(args, &["--testament"]) {
+        Ok(args) => args,
+        Err(err) => return usage_error(&err),
+    };
+    let testament = match args.option("--testament").map(str::to_lowercase).as_deref() {
+        None => None,
+        Some("old") => Some(Testament::Old),
+        Some("new") => Some(Testament::New),
+        Some(_) => return usage_error("--testament must be old or new"),
+    };
+    if let Some(word) = args.words.first() {
+        return usage_error(&format!("Unexpected argument '{}'", word));
+    }
+
+    let all = load_books();
+    let books: Vec<_> = all
+        .iter()
+        .filter(|book| testament.as_ref().is_none_or(|t| book.testament == *t))
+        .collect();
+    if args.json {
+        return print_json(&v1::book_list(books.iter().copied()));
+    }
+
+    let terminal = Terminal::detect();
+    let name_width = books
+        .iter()
+        .map(|b| b.name.chars().count())
+        .max()
+        .unwrap_or(4);
+    let rows: Vec<[String; 4]> = books
+        .iter()
+        .map(|book| {
+            let info = canon::info(&book.name);
+            [
+                book.name.clone(),
+                info.map_or("", |info| info.osis).to_string(),
+                book.chapters.len().to_string(),
+                info.map_or("", |info| info.genre().label()).to_string(),
+            ]
+        })
+        .collect();
+    // The genre column goes first when the terminal is too narrow for it.
+    let show_genre = terminal.width >= name_width + 36;
+    let mut out = io::stdout().lock();
+    let header = format!("{:<name_width$}  {:<6}  {:>8}", "Book", "OSIS", "Chapters");
+    let header = if show_genre {
+        format!("{}  Genre", header)
+    } else {
+        header
+    };
+    writeln!(out, "{}", terminal.paint(BOLD, &header))?;
+    for [name, osis, chapters, genre] in &rows {
+        let row = format!("{:<name_width$}  {:<6}  {:>8}", name, osis, chapters);
+        if show_genre {
+            writeln!(out, "{}  {}", row, terminal.paint(DIM, genre))?;
+        } else {
+            writeln!(out, "{}", row)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unquoted_words_join_into_references() {
+        assert_eq!(join_references(&["John", "3"]), "John 3");
+        assert_eq!(join_references(&["Gen", "1-3", "John"]), "Gen 1-3;John");
+        assert_eq!(
+            join_references(&["1", "John", "3", "Song", "of", "Solomon", "2"]),
+            "1 John 3;Song of Solomon 2"
+        );
+        assert_eq!(join_references(&["Gen 1-3", "John"]), "Gen 1-3;John");
+        assert_eq!(join_references(&["John", "3;", "4"]), "John 3;4");
+        assert_eq!(join_references(&[]), "");
+    }
+
+    #[test]
+    fn unknown_words_reach_the_selection_error() {
+        assert_eq!(
+            join_references(&["John", "3", "Nowhere", "5"]),
+            "John 3;Nowhere 5"
+        );
+    }
+}
@@ -0,0 +1,378 @@
+//! Server configuration. Each setting comes from the first of: a command-line
+//! flag, an environment variable, the TOML config file, the built-in default.
+//! `--print-config` prints the result as TOML, ready to save as a config file.
+//!
+//! Data files (verses, heading sets, catalogs, cross-references, the harmony
+//! and synonyms) are looked up in the data directory, unless their own
+//! environment variable names another place; the bundled copies are used when
+//! neither exists.
+
+use std::fs::File;
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+
+use log::LevelFilter;
+use serde::{Deserialize, Serialize};
+
+use crate::canon::Profile;
+
+/// Read when present and no other file is named.
+const DEFAULT_FILE: &str = "bible-headlines.toml";
+const LOG_LEVELS: &str = "off, error, warn, info, debug or trace";
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Address to listen on.
+    pub bind: String,
+    pub port: u16,
+    /// Where accounts, bookmarks and notes are saved, and data files are read.
+    pub data_dir: PathBuf,
+    /// Verse translation shown under the headings, from the `verses`
+    /// directory; needs the `verses` feature.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translation: Option<String>,
+    pub canon: Profile,
+    pub log_level: String,
+    /// Worker threads; one per CPU core when unset.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub workers: Option<usize>,
+    pub tls: Tls,
+}
+
+/// HTTPS is served when both files are given.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Tls {
+    /// PEM certificate chain.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cert: Option<PathBuf>,
+    /// PEM private key.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<PathBuf>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            bind: "0.0.0.0".to_string(),
+            port: 8080,
+            data_dir: PathBuf::from("userdata"),
+            translation: None,
+            canon: Profile::default(),
+            log_level: "warn".to_string(),
+            workers: None,
+            tls: Tls::default(),
+        }
+    }
+}
+
+/// A data file or directory: its environment variable, and its name inside
+/// the data directory when that variable is unset.
+pub struct DataFile {
+    env: &'static str,
+    name: &'static str,
+}
+
+pub const SYNONYMS: DataFile = DataFile {
+    env: "SYNONYMS_PATH",
+    name: "synonyms.txt",
+};
+pub const CROSSREFS: DataFile = DataFile {
+    env: "CROSSREFS_PATH",
+    name: "crossrefs.txt",
+};
+pub const TSK: DataFile = DataFile {
+    env: "TSK_PATH",
+    name: "tsk.txt",
+};
+pub const HARMONY: DataFile = DataFile {
+    env: "HARMONY_PATH",
+    name: "harmony.txt",
+};
+pub const HEADINGS: DataFile = DataFile {
+    env: "HEADINGS_DIR",
+    name: "headings",
+};
+pub const LOCALES: DataFile = DataFile {
+    env: "LOCALES_DIR",
+    name: "locales",
+};
+pub const VERSES: DataFile = DataFile {
+    env: "VERSES_DIR",
+    name: "verses",
+};
+
+/// Where a data file is read from. Only a file named by its environment
+/// variable is expected to exist; a missing default is not worth a warning.
+pub struct DataPath {
+    pub path: PathBuf,
+    explicit: bool,
+}
+
+impl DataPath {
+    /// The entry `name` inside this directory.
+    pub fn join(&self, name: &str) -> DataPath {
+        DataPath {
+            path: self.path.join(name),
+            explicit: self.explicit,
+        }
+    }
+
+    /// The file's contents, or `None` (with a warning when it was asked for)
+    /// when it cannot be read.
+    pub fn read(&self, what: &str) -> Option<String> {
+        self.check(what, std::fs::read_to_string(&self.path))
+    }
+
+    /// The directory's entries, like [`DataPath::read`].
+    pub fn read_dir(&self, what: &str) -> Option<std::fs::ReadDir> {
+        self.check(what, std::fs::read_dir(&self.path))
+    }
+
+    fn check<T>(&self, what: &str, result: io::Result<T>) -> Option<T> {
+        match result {
+            Ok(value) => Some(value),
+            Err(err) if !self.explicit && err.kind() == io::ErrorKind::NotFound => None,
+            Err(err) => {
+                log::warn!(
+                    "Could not read {} from {}: {}",
+                    what,
+                    self.path.display(),
+                    err
+                );
+                None
+            }
+        }
+    }
+}
+
+/// A setting's environment variable and command-line flag.
+struct Setting {
+    env: &'static str,
+    flag: &'static str,
+    apply: fn(&mut Config, &str) -> Result<(), String>,
+}
+
+const SETTINGS: &[Setting] = &[
+    Setting {
+        env: "BIND_ADDRESS",
+        flag: "--bind",
+        apply: |config, value| {
+            config.bind = value.to_string();
+            Ok(())
+        },
+    },
+    Setting {
+        env: "PORT",
+        flag: "--port",
+        apply: |config, value| {
+            config.port = value
+                .parse()
+                .map_err(|_| format!("'{}' is not a port number", value))?;
+            Ok(())
+        },
+    },
+    Setting {
+        env: "DATA_DIR",
+        flag: "--data-dir",
+        apply: |config, value| {
+            config.data_dir = PathBuf::from(value);
+            Ok(())
+        },
+    },
+    Setting {
+        env: "VERSE_TRANSLATION",
+        flag: "--translation",
+        apply: |config, value| {
+            config.translation = Some(value.to_string());
+            Ok(())
+        },
+    },
+    Setting {
+        env: "CANON",
+        flag: "--canon",
+        apply: |config, value| {
+            config.canon = Profile::parse(value)
+                .ok_or_else(|| format!("Unknown canon '{}'; use {}", value, Profile::NAMES))?;
+            Ok(())
+        },
+    },
+    Setting {
+        env: "LOG_LEVEL",
+        flag: "--log-level",
+        apply: |config, value| {
+            config.log_level = value.to_lowercase();
+            Ok(())
+        },
+    },
+    Setting {
+        env: "WORKERS",
+        flag: "--workers",
+        apply: |config, value| {
+            config.workers = Some(
+                value
+                    .parse()
+                    .map_err(|_| format!("'{}' is not a number of workers", value))?,
+            );
+            Ok(())
+        },
+    },
+    Setting {
+        env: "TLS_CERT",
+        flag: "--tls-cert",
+        apply: |config, value| {
+            config.tls.cert = Some(PathBuf::from(value));
+            Ok(())
+        },
+    },
+    Setting {
+        env: "TLS_KEY",
+        flag: "--tls-key",
+        apply: |config, value| {
+            config.tls.key = Some(PathBuf::from(value));
+            Ok(())
+        },
+    },
+];
+
+/// Splits `--flag value` and `--flag=value` arguments into pairs.
+fn flags(args: &[String]) -> Result<Vec<(&str, Option<&str>)>, String> {
+    let mut pairs = Vec::new();
+    let mut rest = args.iter();
+    while let Some(arg) = rest.next() {
+        if !arg.starts_with("--") {
+            return Err(format!("Unexpected argument '{}'", arg));
+        }
+        match arg.split_once('=') {
+            Some((flag, value)) => pairs.push((flag, Some(value))),
+            None if arg == "--print-config" => pairs.push((arg.as_str(), None)),
+            None => {
+                let value = rest
+                    .next()
+                    .ok_or_else(|| format!("{} needs a value", arg))?;
+                pairs.push((arg.as_str(), Some(value.as_str())));
+            }
+        }
+    }
+    Ok(pairs)
+}
+
+impl Config {
+    /// Builds the configuration from the server's command-line `args`, the
+    /// environment and the config file (`--config`, `CONFIG_FILE`, or
+    /// `bible-headlines.toml` when it exists).
+    pub fn load(args: &[String]) -> Result<Config, String> {
+        let flags = flags(args)?;
+        let flag = |name: &str| {
+            flags
+                .iter()
+                .rev()
+                .find(|(f, _)| *f == name)
+                .and_then(|(_, v)| *v)
+        };
+
+        let named = flag("--config")
+            .map(str::to_string)
+            .or_else(|| std::env::var("CONFIG_FILE").ok());
+        let mut config = match &named {
+            Some(path) => Config::read(Path::new(path))?,
+            None if Path::new(DEFAULT_FILE).exists() => Config::read(Path::new(DEFAULT_FILE))?,
+            None => Config::default(),
+        };
+
+        for setting in SETTINGS {
+            if let Ok(value) = std::env::var(setting.env) {
+                (setting.apply)(&mut config, &value)
+                    .map_err(|err| format!("{}: {}", setting.env, err))?;
+            }
+        }
+        for (name, value) in &flags {
+            match (SETTINGS.iter().find(|s| s.flag == *name), value) {
+                (Some(setting), Some(value)) => (setting.apply)(&mut config, value)?,
+                _ if *name == "--config" || *name == "--print-config" => {}
+                _ => return Err(format!("Unknown option '{}'", name)),
+            }
+        }
+
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn read(path: &Path) -> Result<Config, String> {
+        let source =
+            std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+        toml::from_str(&source).map_err(|err| format!("{}: {}", path.display(), err))
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        self.log_level()?;
+        if self.workers == Some(0) {
+            return Err("workers must be at least 1".to_string());
+        }
+        match (&self.tls.cert, &self.tls.key) {
+            (Some(_), None) | (None, Some(_)) => {
+                Err("TLS needs both a certificate and a key".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    pub fn log_level(&self) -> Result<LevelFilter, String> {
+        self.log_level
+            .parse()
+            .map_err(|_| format!("Unknown log level '{}'; use {}", self.log_level, LOG_LEVELS))
+    }
+
+    /// Where `file` is read from: its environment variable, or else the data
+    /// directory.
+    pub fn data_path(&self, file: &DataFile) -> DataPath {
+        match std::env::var_os(file.env) {
+            Some(path) => DataPath {
+                path: PathBuf::from(path),
+                explicit: true,
+            },
+            None => DataPath {
+                path: self.data_dir.join(file.name),
+                explicit: false,
+            },
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string_pretty(self).expect("serializable config")
+    }
+
+    /// The rustls server configuration, when TLS is configured.
+    pub fn tls_config(&self) -> io::Result<Option<rustls::ServerConfig>> {
+        let (Some(cert_path), Some(key_path)) = (&self.tls.cert, &self.tls.key) else {
+            return Ok(None);
+        };
+        let with_path = |path: &Path| {
+            let path = path.display().to_string();
+            move |err: io::Error| io::Error::new(err.kind(), format!("{}: {}", path, err))
+        };
+
+        let certs = rustls_pemfile::certs(&mut BufReader::new(
+            File::open(cert_path).map_err(with_path(cert_path))?,
+        ))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(with_path(cert_path))?;
+        let key = rustls_pemfile::private_key(&mut BufReader::new(
+            File::open(key_path).map_err(with_path(key_path))?,
+        ))
+        .map_err(with_path(key_path))?
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("{}: no private key found", key_path.display()),
+            )
+        })?;
+
+        rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)
+            .map(Some)
+            .map_err(io::Error::other)
+    }
+}
@@ -0,0 +1,410 @@
+//! Cross-references between sections.
+//!
+//! Links come from the curated `data/crossrefs.txt` and, optionally, from a
+//! copy of the public-domain Treasury of Scripture Knowledge (TSK). Each end of
+//! a link is a chapter, narrowed to a section when a verse falls inside one of
+//! that chapter's heading ranges.
+
+use actix_web::{web, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::config::DataPath;
+use crate::i18n::Locale;
+use crate::reference::{heading_range, Passage};
+use crate::{canon, AppState, Book};
+
+const DEFAULT_CROSSREFS: &str = include_str!("../data/crossrefs.txt");
+
+/// TSK book abbreviations that differ from (or clash with) `canon` abbreviations.
+const TSK_ABBREVIATIONS: &[(&str, &str)] = &[
+    ("le", "Leviticus"),
+    ("nu", "Numbers"),
+    ("de", "Deuteronomy"),
+    ("jud", "Judges"),
+    ("es", "Esther"),
+    ("ec", "Ecclesiastes"),
+    ("so", "Song of Solomon"),
+    ("da", "Daniel"),
+    ("joe", "Joel"),
+    ("mr", "Mark"),
+    ("lu", "Luke"),
+    ("joh", "John"),
+    ("1pe", "1 Peter"),
+    ("2pe", "2 Peter"),
+    ("1jo", "1 John"),
+    ("2jo", "2 John"),
+    ("3jo", "3 John"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Relation {
+    Parallel,
+    Quotation,
+    Allusion,
+    Related,
+}
+
+impl Relation {
+    fn parse(text: &str) -> Option<Self> {
+        match text.trim().to_lowercase().as_str() {
+            "parallel" => Some(Relation::Parallel),
+            "quotation" | "quote" => Some(Relation::Quotation),
+            "allusion" => Some(Relation::Allusion),
+            "related" => Some(Relation::Related),
+            _ => None,
+        }
+    }
+
+    /// How the link reads from one of its ends, in the page's language.
+    pub fn label<'a>(self, direction: Direction, locale: &Locale<'a>) -> &'a str {
+        let key = match (self, direction) {
+            (Relation::Parallel, _) => "crossref.parallel",
+            (Relation::Quotation, Direction::Outgoing) => "crossref.quotes",
+            (Relation::Quotation, Direction::Incoming) => "crossref.quoted_in",
+            (Relation::Allusion, Direction::Outgoing) => "crossref.alludes_to",
+            (Relation::Allusion, Direction::Incoming) => "crossref.alluded_to_in",
+            (Relation::Related, _) => "crossref.related",
+        };
+        locale.text(key)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Outgoing,
+    Incoming,
+}
+
+/// One end of a cross-reference.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SectionRef {
+    pub book: String,
+    pub chapter: u32,
+    /// Index of the section within the chapter, when a verse pins one down.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub heading: Option<String>,
+    /// The reference as written, e.g. "Matthew 1:23".
+    pub label: String,
+}
+
+impl SectionRef {
+    fn resolve(passage: &Passage, books: &[Book]) -> Self {
+        let chapter_number = passage.first_chapter();
+        let chapter = books
+            .iter()
+            .find(|book| book.name == passage.book.name)
+            .and_then(|book| {
+                book.chapters
+                    .iter()
+                    .find(|c| c.chapter_number == chapter_number)
+            });
+
+        let section = match (chapter, passage.verses) {
+            (Some(chapter), Some((verse, _))) => chapter.sections.iter().position(|section| {
+                heading_range(&section.heading, chapter_number)
+                    .is_some_and(|(start, end)| (start..=end).contains(&verse))
+            }),
+            _ => None,
+        };
+
+        SectionRef {
+            book: passage.book.name.to_string(),
+            chapter: chapter_number,
+            section,
+            heading: chapter
+                .zip(section)
+                .map(|(chapter, index)| chapter.sections[index].heading.clone()),
+            label: passage.to_string(),
+        }
+    }
+
+    fn key(&self) -> SectionKey {
+        (self.book.clone(), self.chapter, self.section)
+    }
+}
+
+type SectionKey = (String, u32, Option<usize>);
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct CrossReference {
+    pub from: SectionRef,
+    pub to: SectionRef,
+    pub relation: Relation,
+}
+
+/// A cross-reference as seen from one of its chapters.
+#[derive(Debug, Serialize)]
+pub struct Link<'a> {
+    pub relation: Relation,
+    pub direction: Direction,
+    /// The section on this chapter's side of the link, if known.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub section: Option<usize>,
+    pub target: &'a SectionRef,
+}
+
+#[derive(Default)]
+pub struct CrossReferences {
+    links: Vec<CrossReference>,
+    by_chapter: HashMap<(String, u32), Vec<(usize, Direction)>>,
+}
+
+impl CrossReferences {
+    /// Loads the curated links (`curated`, or the bundled file when it does
+    /// not exist) plus TSK links from `tsk` when it does.
+    pub fn load(books: &[Book], curated: &DataPath, tsk: &DataPath) -> Self {
+        let mut crossrefs = CrossReferences::default();
+
+        let curated = curated
+            .read("cross-references")
+            .unwrap_or_else(|| DEFAULT_CROSSREFS.to_string());
+        for (line, err) in crossrefs.import_curated(&curated, books) {
+            log::warn!("Skipping cross-reference on line {}: {}", line, err);
+        }
+
+        if let Some(source) = tsk.read("TSK") {
+            let before = crossrefs.links.len();
+            let skipped = crossrefs.import_tsk(&source, books);
+            log::info!(
+                "Imported {} cross-references from {} ({} lines skipped)",
+                crossrefs.links.len() - before,
+                tsk.path.display(),
+                skipped
+            );
+        }
+
+        crossrefs
+    }
+
+    fn push(&mut self, link: CrossReference) {
+        let id = self.links.len();
+        self.by_chapter
+            .entry((link.from.book.clone(), link.from.chapter))
+            .or_default()
+            .push((id, Direction::Outgoing));
+        self.by_chapter
+            .entry((link.to.book.clone(), link.to.chapter))
+            .or_default()
+            .push((id, Direction::Incoming));
+        self.links.push(link);
+    }
+
+    /// Imports `from | to | relation` lines, returning the lines that failed.
+    pub fn import_curated(&mut self, source: &str, books: &[Book]) -> Vec<(usize, String)> {
+        let mut errors = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split('|').collect();
+            let [from, to, relation] = fields[..] else {
+                errors.push((index + 1, "expected 'from | to | relation'".to_string()));
+                continue;
+            };
+            let Some(from) = Passage::parse(from) else {
+                errors.push((index + 1, format!("unknown reference '{}'", from.trim())));
+                continue;
+            };
+            let Some(to) = Passage::parse(to) else {
+                errors.push((index + 1, format!("unknown reference '{}'", to.trim())));
+                continue;
+            };
+            let Some(relation) = Relation::parse(relation) else {
+                errors.push((index + 1, format!("unknown relation '{}'", relation.trim())));
+                continue;
+            };
+
+            self.push(CrossReference {
+                from: SectionRef::resolve(&from, books),
+                to: SectionRef::resolve(&to, books),
+                relation,
+            });
+        }
+
+        errors
+    }
+
+    /// Imports the TSK's whitespace-separated `book chapter verse order references`
+    /// layout, where `book` is a number (1–66) or abbreviation and `references`
+    /// is a `;`-separated list such as `Joh 1:1-3,14; 10:30; Heb 11:3`.
+    ///
+    /// Verse-level links collapse onto sections (or chapters), so duplicates are
+    /// merged and links within a single chapter are dropped. Returns the number
+    /// of lines that could not be parsed.
+    pub fn import_tsk(&mut self, source: &str, books: &[Book]) -> usize {
+        let mut seen: HashSet<(SectionKey, SectionKey)> = self
+            .links
+            .iter()
+            .map(|link| (link.from.key(), link.to.key()))
+            .collect();
+        let mut skipped = 0;
+
+        for line in source.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut columns = line.splitn(5, char::is_whitespace);
+            let (Some(book), Some(chapter), Some(verse), Some(_order), Some(references)) = (
+                columns.next(),
+                columns.next(),
+                columns.next(),
+                columns.next(),
+                columns.next(),
+            ) else {
+                skipped += 1;
+                continue;
+            };
+            let Some(from) = tsk_book(book)
+                .and_then(|book| Passage::parse(&format!("{} {}:{}", book, chapter, verse)))
+            else {
+                skipped += 1;
+                continue;
+            };
+            let from = SectionRef::resolve(&from, books);
+
+            let mut current_book = from.book.clone();
+            for reference in references.split(';') {
+                let reference = reference.split(',').next().unwrap_or("").trim();
+                let Some(to) = tsk_reference(reference, &mut current_book) else {
+                    continue;
+                };
+                let to = SectionRef::resolve(&to, books);
+                if (from.book.as_str(), from.chapter) == (to.book.as_str(), to.chapter) {
+                    continue;
+                }
+
+                if seen.insert((from.key(), to.key())) {
+                    self.push(CrossReference {
+                        from: from.clone(),
+                        to,
+                        relation: Relation::Related,
+                    });
+                }
+            }
+        }
+
+        skipped
+    }
+
+    pub fn all(&self) -> &[CrossReference] {
+        &self.links
+    }
+
+    /// Links touching a chapter, seen from that chapter.
+    pub fn for_chapter(&self, book: &str, chapter: u32) -> Vec<Link<'_>> {
+        self.by_chapter
+            .get(&(book.to_string(), chapter))
+            .into_iter()
+            .flatten()
+            .map(|&(id, direction)| {
+                let link = &self.links[id];
+                let (here, target) = match direction {
+                    Direction::Outgoing => (&link.from, &link.to),
+                    Direction::Incoming => (&link.to, &link.from),
+                };
+                Link {
+                    relation: link.relation,
+                    direction,
+                    section: here.section,
+                    target,
+                }
+            })
+            .collect()
+    }
+}
+
+fn tsk_book(text: &str) -> Option<&'static str> {
+    if let Ok(number) = text.parse::<usize>() {
+        return canon::BOOKS
+            .get(number.checked_sub(1)?)
+            .map(|info| info.name);
+    }
+    let lower = text.to_lowercase();
+    TSK_ABBREVIATIONS
+        .iter()
+        .find(|(abbreviation, _)| *abbreviation == lower)
+        .map(|(_, name)| *name)
+        .or_else(|| canon::lookup(text).map(|info| info.name))
+}
+
+/// Parses one TSK reference; references without a book inherit the previous one.
+fn tsk_reference(reference: &str, current_book: &mut String) -> Option<Passage> {
+    if reference.is_empty() {
+        return None;
+    }
+    let passage = match reference.rsplit_once(char::is_whitespace) {
+        Some((book, numbers)) => {
+            let book = tsk_book(book)?;
+            Passage::parse(&format!("{} {}", book, numbers))?
+        }
+        None => Passage::parse(&format!("{} {}", current_book, reference))?,
+    };
+    *current_book = passage.book.name.to_string();
+    Some(passage)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CrossRefParams {
+    /// Book name or abbreviation.
+    book: Option<String>,
+    chapter: Option<u32>,
+    /// Only links of this kind.
+    relation: Option<Relation>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct CrossRefResponse<'a> {
+    total: usize,
+    crossrefs: Vec<&'a CrossReference>,
+}
+
+/// Cross-references with an end in the given book or chapter.
+#[utoipa::path(
+    get,
+    path = "/api/crossrefs",
+    tag = "references",
+    params(CrossRefParams),
+    responses((status = 200, description = "Cross-references touching the book or chapter", body = CrossRefResponse))
+)]
+pub async fn api_crossrefs(
+    data: web::Data<AppState>,
+    params: web::Query<CrossRefParams>,
+) -> Result<HttpResponse> {
+    let book = params
+        .book
+        .as_deref()
+        .map(|name| canon::lookup(name).map_or(name, |info| info.name));
+
+    let touches = |end: &SectionRef| {
+        book.is_none_or(|book| end.book == book)
+            && params.chapter.is_none_or(|chapter| end.chapter == chapter)
+    };
+    let crossrefs: Vec<&CrossReference> = data
+        .crossrefs
+        .all()
+        .iter()
+        .filter(|link| {
+            params
+                .relation
+                .is_none_or(|relation| link.relation == relation)
+        })
+        .filter(|link| touches(&link.from) || touches(&link.to))
+        .collect();
+
+    Ok(HttpResponse::Ok().json(CrossRefResponse {
+        total: crossrefs.len(),
+        crossrefs,
+    }))
+}
@@ -0,0 +1,362 @@
+//! EPUB 3 export of the headings outline: a title page, a table of contents
+//! following the testament / book / chapter hierarchy, and one section per
+//! book, styled like the web UI.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::export::{headings, Selection};
+use crate::layout::escape;
+use crate::plans::civil_from_days;
+use crate::zip::ZipWriter;
+use crate::{Book, Chapter, Testament, PLACEHOLDER_HEADING};
+
+const CONTAINER: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<container version="1.0" xmlns="urn:oasis:names:tc:opendocument:xmlns:container">
+  <rootfiles>
+    <rootfile full-path="OEBPS/content.opf" media-type="application/oebps-package+xml"/>
+  </rootfiles>
+</container>
+"#;
+
+type Books<'a> = [(&'a Book, Vec<&'a Chapter>)];
+
+/// Builds the e-book for a selection.
+pub fn build(selection: &Selection) -> Vec<u8> {
+    let title = format!("Bible Section Headlines: {}", selection.title);
+    let files: Vec<String> = (1..=selection.books.len())
+        .map(|n| format!("book-{:02}.xhtml", n))
+        .collect();
+
+    let mut zip = ZipWriter::new();
+    zip.stored("mimetype", b"application/epub+zip");
+    zip.deflated("META-INF/container.xml", CONTAINER.as_bytes());
+    zip.deflated("OEBPS/content.opf", package(&title, &files).as_bytes());
+    zip.deflated("OEBPS/nav.xhtml", nav(&selection.books, &files).as_bytes());
+    zip.deflated(
+        "OEBPS/toc.ncx",
+        ncx(&title, &selection.books, &files).as_bytes(),
+    );
+    zip.deflated("OEBPS/styles.css", STYLES.as_bytes());
+    zip.deflated("OEBPS/title.xhtml", title_page(selection).as_bytes());
+    for ((book, chapters), file) in selection.books.iter().zip(&files) {
+        zip.deflated(
+            &format!("OEBPS/{}", file),
+            book_page(book, chapters).as_bytes(),
+        );
+    }
+    zip.finish()
+}
+
+fn testament_name(testament: &Testament) -> &'static str {
+    match testament {
+        Testament::Old => "Old Testament",
+        Testament::New => "New Testament",
+    }
+}
+
+fn xhtml(title: &str, body: &str) -> String {
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE html>
+<html xmlns="http://www.w3.org/1999/xhtml" xmlns:epub="http://www.idpf.org/2007/ops" lang="en" xml:lang="en">
+<head>
+  <meta charset="UTF-8"/>
+  <title>{}</title>
+  <link rel="stylesheet" type="text/css" href="styles.css"/>
+</head>
+<body>
+{}
+</body>
+</html>
+"#,
+        escape(title),
+        body
+    )
+}
+
+/// The current time as `CCYY-MM-DDThh:mm:ssZ`, as `dcterms:modified` requires.
+fn modified() -> String {
+    let seconds = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let (year, month, day) = civil_from_days(seconds.div_euclid(86_400));
+    let time = seconds.rem_euclid(86_400);
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60
+    )
+}
+
+fn identifier(title: &str) -> String {
+    let slug: String = title
+        .chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() {
+                c.to_ascii_lowercase()
+            } else {
+                '-'
+            }
+        })
+        .collect();
+    format!("urn:bible-headlines:{}", slug)
+}
+
+fn package(title: &str, files: &[String]) -> String {
+    let mut manifest = String::new();
+    let mut spine = String::new();
+    for (index, file) in files.iter().enumerate() {
+        manifest.push_str(&format!(
+            "    <item id=\"book-{0}\" href=\"{1}\" media-type=\"application/xhtml+xml\"/>\n",
+            index + 1,
+            file
+        ));
+        spine.push_str(&format!("    <itemref idref=\"book-{}\"/>\n", index + 1));
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<package xmlns="http://www.idpf.org/2007/opf" version="3.0" unique-identifier="book-id" xml:lang="en">
+  <metadata xmlns:dc="http://purl.org/dc/elements/1.1/">
+    <dc:identifier id="book-id">{}</dc:identifier>
+    <dc:title>{}</dc:title>
+    <dc:language>en</dc:language>
+    <dc:creator>Bible Section Headlines</dc:creator>
+    <meta property="dcterms:modified">{}</meta>
+  </metadata>
+  <manifest>
+    <item id="nav" href="nav.xhtml" media-type="application/xhtml+xml" properties="nav"/>
+    <item id="ncx" href="toc.ncx" media-type="application/x-dtbncx+xml"/>
+    <item id="css" href="styles.css" media-type="text/css"/>
+    <item id="title" href="title.xhtml" media-type="application/xhtml+xml"/>
+{}  </manifest>
+  <spine toc="ncx">
+    <itemref idref="title"/>
+    <itemref idref="nav"/>
+{}  </spine>
+</package>
+"#,
+        escape(&identifier(title)),
+        escape(title),
+        modified(),
+        manifest,
+        spine
+    )
+}
+
+fn title_page(selection: &Selection) -> String {
+    let body = format!(
+        r#"<section epub:type="titlepage" class="title-page">
+  <p class="ornament">✦</p>
+  <h1>Bible Section Headlines</h1>
+  <p class="subtitle">{}</p>
+  <p class="ornament">✦</p>
+</section>"#,
+        escape(&selection.title)
+    );
+    xhtml("Bible Section Headlines", &body)
+}
+
+/// The EPUB 3 navigation document: testaments, then books, then chapters.
+fn nav(books: &Books, files: &[String]) -> String {
+    let mut body =
+        String::from("<nav epub:type=\"toc\" id=\"toc\">\n  <h1>Contents</h1>\n  <ol>\n");
+    for testament in [Testament::Old, Testament::New] {
+        let entries: Vec<_> = books
+            .iter()
+            .zip(files)
+            .filter(|((book, _), _)| book.testament == testament)
+            .collect();
+        let Some(((_, _), first_file)) = entries.first() else {
+            continue;
+        };
+
+        body.push_str(&format!(
+            "    <li><a href=\"{}\">{}</a>\n      <ol>\n",
+            first_file,
+            testament_name(&testament)
+        ));
+        for ((book, chapters), file) in entries {
+            body.push_str(&format!(
+                "        <li><a href=\"{}\">{}</a>\n          <ol>\n",
+                file,
+                escape(&book.name)
+            ));
+            for chapter in chapters {
+                body.push_str(&format!(
+                    "            <li><a href=\"{}#chapter-{1}\">Chapter {1}</a></li>\n",
+                    file, chapter.chapter_number
+                ));
+            }
+            body.push_str("          </ol>\n        </li>\n");
+        }
+        body.push_str("      </ol>\n    </li>\n");
+    }
+    body.push_str("  </ol>\n</nav>");
+    xhtml("Contents", &body)
+}
+
+/// The EPUB 2 table of contents, for older e-readers.
+fn ncx(title: &str, books: &Books, files: &[String]) -> String {
+    let mut points = String::new();
+    let mut order = 0;
+    for ((book, chapters), file) in books.iter().zip(files) {
+        order += 1;
+        points.push_str(&format!(
+            "    <navPoint id=\"nav-{0}\" playOrder=\"{0}\">\n      <navLabel><text>{1}</text></navLabel>\n      <content src=\"{2}\"/>\n",
+            order,
+            escape(&book.name),
+            file
+        ));
+        for chapter in chapters {
+            order += 1;
+            points.push_str(&format!(
+                "      <navPoint id=\"nav-{0}\" playOrder=\"{0}\">\n        <navLabel><text>Chapter {1}</text></navLabel>\n        <content src=\"{2}#chapter-{1}\"/>\n      </navPoint>\n",
+                order, chapter.chapter_number, file
+            ));
+        }
+        points.push_str("    </navPoint>\n");
+    }
+
+    format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<ncx xmlns="http://www.daisy.org/z3986/2005/ncx/" version="2005-1">
+  <head>
+    <meta name="dtb:uid" content="{}"/>
+  </head>
+  <docTitle><text>{}</text></docTitle>
+  <navMap>
+{}  </navMap>
+</ncx>
+"#,
+        escape(&identifier(title)),
+        escape(title),
+        points
+    )
+}
+
+fn book_page(book: &Book, chapters: &[&Chapter]) -> String {
+    let mut body = format!(
+        "<section epub:type=\"chapter\" class=\"book\">\n  <p class=\"testament\">{}</p>\n  <h1 class=\"book-title\">{}</h1>\n",
+        testament_name(&book.testament),
+        escape(&book.name)
+    );
+    for chapter in chapters {
+        body.push_str(&format!(
+            "  <section class=\"chapter\" id=\"chapter-{0}\">\n    <h2 class=\"chapter-number\">Chapter {0}</h2>\n",
+            chapter.chapter_number
+        ));
+        let list: Vec<&str> = headings(chapter).collect();
+        if list.is_empty() {
+            body.push_str(&format!(
+                "    <p class=\"placeholder\">{}</p>\n",
+                PLACEHOLDER_HEADING
+            ));
+        } else {
+            body.push_str("    <ul class=\"sections\">\n");
+            for heading in list {
+                body.push_str(&format!("      <li>{}</li>\n", escape(heading)));
+            }
+            body.push_str("    </ul>\n");
+        }
+        body.push_str("  </section>\n");
+    }
+    body.push_str("</section>");
+    xhtml(&book.name, &body)
+}
+
+/// The web UI's typography, using the same families where the reader has them.
+const STYLES: &str = r#"body {
+  font-family: "Inter", "Helvetica Neue", Arial, sans-serif;
+  color: #2d3748;
+  line-height: 1.5;
+  margin: 0 5%;
+}
+
+h1, h2, .subtitle, .ornament {
+  font-family: "Cormorant Garamond", Garamond, Georgia, serif;
+}
+
+.title-page {
+  text-align: center;
+  margin-top: 30%;
+}
+
+.title-page h1 {
+  font-size: 2.4em;
+  color: #667eea;
+  margin: 0.5em 0;
+}
+
+.subtitle {
+  font-size: 1.3em;
+  color: #764ba2;
+}
+
+.ornament {
+  color: #667eea;
+  font-size: 1.5em;
+}
+
+nav ol {
+  list-style: none;
+  padding-left: 1.2em;
+}
+
+nav a {
+  color: #667eea;
+  text-decoration: none;
+}
+
+.testament {
+  font-size: 0.8em;
+  text-transform: uppercase;
+  letter-spacing: 0.1em;
+  color: #764ba2;
+  margin-bottom: 0;
+}
+
+.book-title {
+  font-size: 2.2em;
+  font-weight: 700;
+  color: #667eea;
+  margin-top: 0.2em;
+  border-bottom: 2px solid #e2e8f0;
+  padding-bottom: 0.3em;
+}
+
+.chapter {
+  page-break-inside: avoid;
+  margin-bottom: 1.2em;
+}
+
+.chapter-number {
+  font-size: 1.3em;
+  font-weight: 600;
+  color: #764ba2;
+  margin-bottom: 0.3em;
+}
+
+.sections {
+  margin: 0;
+  padding-left: 1.2em;
+}
+
+.sections li {
+  margin-bottom: 0.25em;
+}
+
+.sections li::marker {
+  color: #667eea;
+}
+
+.placeholder {
+  font-style: italic;
+  color: #a0aec0;
+  margin: 0;
+}
+"#;
@@ -0,0 +1,314 @@
+//! Outline exports of the headings for a selection of books and chapters:
+//! Markdown nested lists, OPML, indented plain text, EPUB and PDF.
+
+use actix_web::{web, HttpResponse, Result};
+use serde::Deserialize;
+use serde_json::json;
+use utoipa::IntoParams;
+
+use crate::reference::Passage;
+use crate::{epub, pdf};
+use crate::{layout, AppState, Book, Chapter, PLACEHOLDER_HEADING};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Markdown,
+    Opml,
+    Text,
+    Epub,
+    /// A print-ready handout laid out in one or two columns.
+    Pdf {
+        columns: usize,
+    },
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name.to_ascii_lowercase().as_str() {
+            "markdown" | "md" => Some(Format::Markdown),
+            "opml" => Some(Format::Opml),
+            "text" | "txt" => Some(Format::Text),
+            "epub" => Some(Format::Epub),
+            "pdf" => Some(Format::Pdf { columns: 1 }),
+            _ => None,
+        }
+    }
+
+    pub fn content_type(self) -> &'static str {
+        match self {
+            Format::Markdown => "text/markdown; charset=utf-8",
+            Format::Opml => "text/x-opml; charset=utf-8",
+            Format::Text => "text/plain; charset=utf-8",
+            Format::Epub => "application/epub+zip",
+            Format::Pdf { .. } => "application/pdf",
+        }
+    }
+
+    /// The file name offered for downloads of binary formats.
+    pub fn download_name(self) -> Option<&'static str> {
+        match self {
+            Format::Epub => Some("bible-headlines.epub"),
+            Format::Pdf { .. } => Some("bible-headlines.pdf"),
+            _ => None,
+        }
+    }
+}
+
+/// The books and chapters chosen for an export, in canonical order.
+pub struct Selection<'a> {
+    pub title: String,
+    pub books: Vec<(&'a Book, Vec<&'a Chapter>)>,
+}
+
+impl<'a> Selection<'a> {
+    /// Selects the chapters covered by `references` ("Genesis", "Gen 1-3", ...),
+    /// separated by ';' (commas belong to verse lists such as "John 3:16,18").
+    /// An empty list selects the whole Bible.
+    pub fn parse(books: &'a [Book], references: &str) -> std::result::Result<Self, String> {
+        let passages = references
+            .split(';')
+            .map(str::trim)
+            .filter(|r| !r.is_empty())
+            .map(|r| Passage::parse(r).ok_or_else(|| format!("Unknown reference '{}'", r)))
+            .collect::<std::result::Result<Vec<_>, _>>()?;
+
+        let title = if passages.is_empty() {
+            "The Whole Bible".to_string()
+        } else {
+            passages
+                .iter()
+                .map(Passage::to_string)
+                .collect::<Vec<_>>()
+                .join("; ")
+        };
+
+        let selected = books
+            .iter()
+            .filter_map(|book| {
+                let chapters: Vec<&Chapter> = book
+                    .chapters
+                    .iter()
+                    .filter(|chapter| {
+                        passages.is_empty()
+                            || passages.iter().any(|p| {
+                                p.book.name == book.name
+                                    && p.includes_chapter(chapter.chapter_number)
+                            })
+                    })
+                    .collect();
+                (!chapters.is_empty()).then_some((book, chapters))
+            })
+            .collect();
+
+        Ok(Selection {
+            title,
+            books: selected,
+        })
+    }
+
+    pub fn render(&self, format: Format) -> Vec<u8> {
+        match format {
+            Format::Markdown => self.markdown().into_bytes(),
+            Format::Opml => self.opml().into_bytes(),
+            Format::Text => self.text().into_bytes(),
+            Format::Epub => epub::build(self),
+            Format::Pdf { columns } => pdf::build(self, columns),
+        }
+    }
+
+    fn markdown(&self) -> String {
+        let mut out = format!("# {}\n\n", self.title);
+        for (book, chapters) in &self.books {
+            out.push_str(&format!("- **{}**\n", markdown_escape(&book.name)));
+            for chapter in chapters {
+                out.push_str(&format!("  - Chapter {}\n", chapter.chapter_number));
+                for heading in headings(chapter) {
+                    out.push_str(&format!("    - {}\n", markdown_escape(heading)));
+                }
+            }
+        }
+        out
+    }
+
+    fn opml(&self) -> String {
+        let mut out = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<opml version=\"2.0\">\n  <head>\n    <title>{}</title>\n  </head>\n  <body>\n",
+            layout::escape(&self.title)
+        );
+        for (book, chapters) in &self.books {
+            out.push_str(&format!(
+                "    <outline text=\"{}\">\n",
+                layout::escape(&book.name)
+            ));
+            for chapter in chapters {
+                out.push_str(&format!(
+                    "      <outline text=\"Chapter {}\">\n",
+                    chapter.chapter_number
+                ));
+                for heading in headings(chapter) {
+                    out.push_str(&format!(
+                        "        <outline text=\"{}\"/>\n",
+                        layout::escape(heading)
+                    ));
+                }
+                out.push_str("      </outline>\n");
+            }
+            out.push_str("    </outline>\n");
+        }
+        out.push_str("  </body>\n</opml>\n");
+        out
+    }
+
+    fn text(&self) -> String {
+        let mut out = format!("{}\n\n", self.title);
+        for (book, chapters) in &self.books {
+            out.push_str(&format!("{}\n", book.name));
+            for chapter in chapters {
+                out.push_str(&format!("  Chapter {}\n", chapter.chapter_number));
+                for heading in headings(chapter) {
+                    out.push_str(&format!("    {}\n", heading));
+                }
+            }
+        }
+        out
+    }
+}
+
+/// Backslash-escapes Markdown syntax in text placed in a list item: inline
+/// markup anywhere, and markers that would start a heading, quote or nested
+/// list at the beginning.
+fn markdown_escape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_[]<>~|#".contains(c) {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    if out.starts_with(['+', '-']) {
+        out.insert(0, '\\');
+    }
+    let digits = out.chars().take_while(char::is_ascii_digit).count();
+    if digits > 0 && out[digits..].starts_with(['.', ')']) {
+        out.insert(digits, '\\');
+    }
+    out
+}
+
+/// A chapter's headings, leaving out the placeholder used for books not yet detailed.
+pub fn headings(chapter: &Chapter) -> impl Iterator<Item = &str> {
+    chapter
+        .sections
+        .iter()
+        .map(|section| section.heading.as_str())
+        .filter(|heading| *heading != PLACEHOLDER_HEADING)
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ExportParams {
+    /// References such as "Genesis;Exodus 1-3"; the whole Bible when absent.
+    #[serde(default)]
+    books: String,
+    /// Column count for PDF handouts: 1 (default) or 2.
+    columns: Option<usize>,
+}
+
+/// An outline of the selected books' headings as Markdown, OPML, text, EPUB or PDF.
+#[utoipa::path(
+    get,
+    path = "/api/export/{format}",
+    tag = "study",
+    params(("format" = String, Path, description = "`markdown`, `opml`, `text`, `epub` or `pdf`"), ExportParams),
+    responses(
+        (status = 200, description = "The outline as a file in the requested format"),
+        (status = 400, description = "Bad references or column count", body = crate::openapi::ErrorBody),
+        (status = 404, description = "Unknown format", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_export(
+    data: web::Data<AppState>,
+    format: web::Path<String>,
+    params: web::Query<ExportParams>,
+) -> Result<HttpResponse> {
+    let format = match (Format::parse(&format), params.columns) {
+        (None, _) => {
+            return Ok(HttpResponse::NotFound().json(json!({
+                "error": format!("Unknown export format '{}'", format)
+            })));
+        }
+        (Some(Format::Pdf { .. }), Some(columns)) if !(1..=2).contains(&columns) => {
+            return Ok(
+                HttpResponse::BadRequest().json(json!({ "error": "columns must be 1 or 2" }))
+            );
+        }
+        (Some(Format::Pdf { .. }), Some(columns)) => Format::Pdf { columns },
+        (Some(format), _) => format,
+    };
+    let selection = match Selection::parse(&data.books, &params.books) {
+        Ok(selection) => selection,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(json!({ "error": err }))),
+    };
+
+    let mut response = HttpResponse::Ok();
+    response.content_type(format.content_type());
+    if let Some(name) = format.download_name() {
+        response.insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", name),
+        ));
+    }
+    Ok(response.body(selection.render(format)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_bible_data;
+
+    #[test]
+    fn markdown_syntax_in_headings_is_escaped() {
+        assert_eq!(
+            markdown_escape("The Lord Is My Shepherd"),
+            "The Lord Is My Shepherd"
+        );
+        assert_eq!(
+            markdown_escape("*Not* [a] _link_ #1 `code`"),
+            r"\*Not\* \[a\] \_link\_ \#1 \`code\`"
+        );
+        assert_eq!(markdown_escape("- Woes"), r"\- Woes");
+        assert_eq!(markdown_escape("12. Psalm"), r"12\. Psalm");
+        assert_eq!(markdown_escape("3) Jesus"), r"3\) Jesus");
+        assert_eq!(markdown_escape("Psalm 23 (1:1-6)"), "Psalm 23 (1:1-6)");
+    }
+
+    #[test]
+    fn references_split_on_semicolons_only() {
+        let books = get_bible_data();
+        let selection = Selection::parse(&books, "John 3:16,18; Gen 1-2").unwrap();
+        assert_eq!(selection.title, "John 3:16; Genesis 1–2");
+        let names: Vec<&str> = selection
+            .books
+            .iter()
+            .map(|(book, _)| book.name.as_str())
+            .collect();
+        assert_eq!(names, ["Genesis", "John"]);
+        assert!(Selection::parse(&books, "Genesis, Exodus").is_err());
+        assert_eq!(
+            Selection::parse(&books, " ; ").unwrap().title,
+            "The Whole Bible"
+        );
+    }
+
+    #[test]
+    fn markdown_outline_nests_books_chapters_and_headings() {
+        let books = get_bible_data();
+        let markdown = Selection::parse(&books, "Gen 1")
+            .unwrap()
+            .render(Format::Markdown);
+        assert_eq!(
+            String::from_utf8(markdown).unwrap(),
+            "# Genesis 1\n\n- **Genesis**\n  - Chapter 1\n    - The Creation of the World\n"
+        );
+    }
+}
@@ -0,0 +1,418 @@
+//! Flashcard decks for memorizing where each story is: "Which chapter is
+//! 'The Burning Bush'?" answered with "Exodus 3". Decks are offered as an
+//! Anki package (.apkg) or as a CSV file for Anki's text import.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use actix_web::{web, HttpResponse, Result};
+use rusqlite::{params, Connection, DatabaseName};
+use serde::Deserialize;
+use serde_json::json;
+use utoipa::IntoParams;
+
+use crate::export::{headings, Selection};
+use crate::layout::escape;
+use crate::zip::ZipWriter;
+use crate::AppState;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DeckFormat {
+    Apkg,
+    Csv,
+}
+
+impl DeckFormat {
+    pub fn parse(name: &str) -> Option<DeckFormat> {
+        match name.to_ascii_lowercase().as_str() {
+            "apkg" | "anki" => Some(DeckFormat::Apkg),
+            "csv" => Some(DeckFormat::Csv),
+            _ => None,
+        }
+    }
+
+    fn content_type(self) -> &'static str {
+        match self {
+            DeckFormat::Apkg => "application/octet-stream",
+            DeckFormat::Csv => "text/csv; charset=utf-8",
+        }
+    }
+
+    fn download_name(self) -> &'static str {
+        match self {
+            DeckFormat::Apkg => "bible-headlines.apkg",
+            DeckFormat::Csv => "bible-headlines.csv",
+        }
+    }
+}
+
+pub struct Card {
+    pub question: String,
+    /// Every chapter with this heading, e.g. "Genesis 5; 1 Chronicles 1".
+    pub answer: String,
+    /// The books the answer points into, as Anki tags.
+    pub tags: Vec<String>,
+}
+
+/// The chapters a heading appears in, grouped by book.
+type Places<'a> = Vec<(&'a str, Vec<u32>)>;
+
+pub struct Deck {
+    pub name: String,
+    pub cards: Vec<Card>,
+}
+
+impl Deck {
+    /// One card per distinct heading in the selection. A heading used in
+    /// several chapters gets a single card listing all of them.
+    pub fn from_selection(selection: &Selection) -> Deck {
+        let mut found: Vec<(&str, Places)> = Vec::new();
+        for (book, chapters) in &selection.books {
+            for chapter in chapters {
+                for heading in headings(chapter) {
+                    let index = match found.iter().position(|(h, _)| *h == heading) {
+                        Some(index) => index,
+                        None => {
+                            found.push((heading, Vec::new()));
+                            found.len() - 1
+                        }
+                    };
+                    let places = &mut found[index].1;
+                    match places.last_mut() {
+                        Some((name, numbers)) if *name == book.name => {
+                            if numbers.last() != Some(&chapter.chapter_number) {
+                                numbers.push(chapter.chapter_number);
+                            }
+                        }
+                        _ => places.push((&book.name, vec![chapter.chapter_number])),
+                    }
+                }
+            }
+        }
+
+        let cards = found
+            .into_iter()
+            .map(|(heading, places)| Card {
+                question: format!("Which chapter is '{}'?", heading),
+                answer: places
+                    .iter()
+                    .map(|(book, numbers)| {
+                        let numbers: Vec<String> = numbers.iter().map(u32::to_string).collect();
+                        format!("{} {}", book, numbers.join(", "))
+                    })
+                    .collect::<Vec<_>>()
+                    .join("; "),
+                tags: places
+                    .iter()
+                    .map(|(book, _)| book.replace(' ', "_"))
+                    .collect(),
+            })
+            .collect();
+
+        Deck {
+            name: format!("Bible Section Headlines::{}", selection.title),
+            cards,
+        }
+    }
+
+    pub fn render(&self, format: DeckFormat) -> Vec<u8> {
+        match format {
+            DeckFormat::Apkg => self.apkg(),
+            DeckFormat::Csv => self.csv().into_bytes(),
+        }
+    }
+
+    /// Anki's text import format, with header lines naming the separator and
+    /// the tags column.
+    fn csv(&self) -> String {
+        let mut out = String::from("#separator:comma\n#html:false\n#tags column:3\n");
+        for card in &self.cards {
+            out.push_str(&format!(
+                "{},{},{}\n",
+                csv_field(&card.question),
+                csv_field(&card.answer),
+                csv_field(&card.tags.join(" "))
+            ));
+        }
+        out
+    }
+
+    /// A legacy (schema 11) Anki collection zipped with an empty media map,
+    /// which every Anki version since 2.1 imports.
+    fn apkg(&self) -> Vec<u8> {
+        let collection = self.collection().expect("in-memory SQLite database");
+        let mut zip = ZipWriter::new();
+        zip.deflated("collection.anki2", &collection);
+        zip.deflated("media", b"{}");
+        zip.finish()
+    }
+
+    fn collection(&self) -> rusqlite::Result<Vec<u8>> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_millis() as i64);
+        let seconds = millis / 1000;
+        let deck_id = stable_id(&self.name);
+
+        let db = Connection::open_in_memory()?;
+        db.execute_batch(SCHEMA)?;
+        db.execute(
+            "INSERT INTO col VALUES (1, ?1, ?2, ?3, 11, 0, 0, 0, ?4, ?5, ?6, ?7, '{}')",
+            params![
+                seconds,
+                millis,
+                millis,
+                collection_config().to_string(),
+                model(deck_id, seconds).to_string(),
+                decks(deck_id, &self.name, seconds).to_string(),
+                deck_config().to_string(),
+            ],
+        )?;
+
+        let mut add_note =
+            db.prepare("INSERT INTO notes VALUES (?1, ?2, ?3, ?4, -1, ?5, ?6, ?7, ?8, 0, '')")?;
+        let mut add_card = db.prepare(
+            "INSERT INTO cards VALUES (?1, ?2, ?3, 0, ?4, -1, 0, 0, ?5, 0, 0, 0, 0, 0, 0, 0, 0, '')",
+        )?;
+        for (index, card) in self.cards.iter().enumerate() {
+            let id = millis + index as i64;
+            let question = escape(&card.question);
+            let tags = if card.tags.is_empty() {
+                String::new()
+            } else {
+                format!(" {} ", card.tags.join(" "))
+            };
+            add_note.execute(params![
+                id,
+                guid(&card.question),
+                MODEL_ID,
+                seconds,
+                tags,
+                format!("{}\u{1f}{}", question, escape(&card.answer)),
+                card.question,
+                checksum(&card.question),
+            ])?;
+            add_card.execute(params![id, id, deck_id, seconds, index as i64 + 1])?;
+        }
+
+        Ok(db.serialize(DatabaseName::Main)?.to_vec())
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// The note type shared by every exported deck, so re-imports update notes
+/// instead of creating a second note type.
+const MODEL_ID: i64 = 1_607_392_319_042;
+
+/// A positive id derived from `name`, so the same deck keeps its id.
+fn stable_id(name: &str) -> i64 {
+    let digest = sha1_smol::Sha1::from(name).digest().bytes();
+    let mut bytes = [0u8; 8];
+    bytes.copy_from_slice(&digest[..8]);
+    (u64::from_be_bytes(bytes) >> 12) as i64
+}
+
+/// Anki matches imported notes to existing ones by guid; deriving it from the
+/// question keeps a reviewer's progress when they import a newer deck.
+fn guid(question: &str) -> String {
+    sha1_smol::Sha1::from(question).digest().to_string()[..16].to_string()
+}
+
+/// First 32 bits of the SHA-1 of the sort field, which Anki uses to spot duplicates.
+fn checksum(sort_field: &str) -> i64 {
+    let digest = sha1_smol::Sha1::from(sort_field).digest().bytes();
+    u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]) as i64
+}
+
+fn collection_config() -> serde_json::Value {
+    json!({
+        "activeDecks": [1],
+        "curDeck": 1,
+        "newSpread": 0,
+        "collapseTime": 1200,
+        "timeLim": 0,
+        "estTimes": true,
+        "dueCounts": true,
+        "curModel": MODEL_ID.to_string(),
+        "nextPos": 1,
+        "sortType": "noteFld",
+        "sortBackwards": false,
+        "addToCur": true
+    })
+}
+
+fn model(deck_id: i64, modified: i64) -> serde_json::Value {
+    let field = |name: &str, ord: u32| {
+        json!({
+            "name": name, "ord": ord, "sticky": false, "rtl": false,
+            "font": "Arial", "size": 20, "media": []
+        })
+    };
+    json!({
+        MODEL_ID.to_string(): {
+            "id": MODEL_ID,
+            "name": "Bible Section Headlines",
+            "type": 0,
+            "mod": modified,
+            "usn": -1,
+            "sortf": 0,
+            "did": deck_id,
+            "tmpls": [{
+                "name": "Chapter",
+                "ord": 0,
+                "qfmt": "{{Question}}",
+                "afmt": "{{FrontSide}}<hr id=answer>{{Answer}}",
+                "did": null,
+                "bqfmt": "",
+                "bafmt": ""
+            }],
+            "flds": [field("Question", 0), field("Answer", 1)],
+            "css": CARD_STYLES,
+            "latexPre": "\\documentclass[12pt]{article}\n\\special{papersize=3in,5in}\n\\usepackage{amssymb,amsmath}\n\\pagestyle{empty}\n\\setlength{\\parindent}{0in}\n\\begin{document}\n",
+            "latexPost": "\\end{document}",
+            "tags": [],
+            "vers": [],
+            "req": [[0, "all", [0]]]
+        }
+    })
+}
+
+fn decks(deck_id: i64, name: &str, modified: i64) -> serde_json::Value {
+    let deck = |id: i64, name: &str| {
+        json!({
+            "id": id, "name": name, "mod": modified, "usn": -1, "desc": "",
+            "dyn": 0, "conf": 1, "collapsed": false, "extendNew": 10, "extendRev": 50,
+            "newToday": [0, 0], "revToday": [0, 0], "lrnToday": [0, 0], "timeToday": [0, 0]
+        })
+    };
+    json!({
+        "1": deck(1, "Default"),
+        deck_id.to_string(): deck(deck_id, name)
+    })
+}
+
+fn deck_config() -> serde_json::Value {
+    json!({
+        "1": {
+            "id": 1,
+            "name": "Default",
+            "mod": 0,
+            "usn": 0,
+            "maxTaken": 60,
+            "autoplay": true,
+            "timer": 0,
+            "replayq": true,
+            "dyn": false,
+            "new": {
+                "delays": [1, 10], "ints": [1, 4, 7], "initialFactor": 2500,
+                "order": 1, "perDay": 20, "bury": true, "separate": true
+            },
+            "lapse": {
+                "delays": [10], "mult": 0, "minInt": 1, "leechFails": 8, "leechAction": 0
+            },
+            "rev": {
+                "perDay": 100, "ease4": 1.3, "fuzz": 0.05, "minSpace": 1,
+                "ivlFct": 1, "maxIvl": 36500, "bury": true
+            }
+        }
+    })
+}
+
+const CARD_STYLES: &str = ".card {
+  font-family: \"Cormorant Garamond\", Garamond, Georgia, serif;
+  font-size: 24px;
+  text-align: center;
+  color: #2d3748;
+  background-color: white;
+}
+hr#answer {
+  border: none;
+  border-top: 2px solid #667eea;
+}";
+
+const SCHEMA: &str = "
+CREATE TABLE col (
+    id integer PRIMARY KEY, crt integer NOT NULL, mod integer NOT NULL,
+    scm integer NOT NULL, ver integer NOT NULL, dty integer NOT NULL,
+    usn integer NOT NULL, ls integer NOT NULL, conf text NOT NULL,
+    models text NOT NULL, decks text NOT NULL, dconf text NOT NULL,
+    tags text NOT NULL
+);
+CREATE TABLE notes (
+    id integer PRIMARY KEY, guid text NOT NULL, mid integer NOT NULL,
+    mod integer NOT NULL, usn integer NOT NULL, tags text NOT NULL,
+    flds text NOT NULL, sfld integer NOT NULL, csum integer NOT NULL,
+    flags integer NOT NULL, data text NOT NULL
+);
+CREATE TABLE cards (
+    id integer PRIMARY KEY, nid integer NOT NULL, did integer NOT NULL,
+    ord integer NOT NULL, mod integer NOT NULL, usn integer NOT NULL,
+    type integer NOT NULL, queue integer NOT NULL, due integer NOT NULL,
+    ivl integer NOT NULL, factor integer NOT NULL, reps integer NOT NULL,
+    lapses integer NOT NULL, left integer NOT NULL, odue integer NOT NULL,
+    odid integer NOT NULL, flags integer NOT NULL, data text NOT NULL
+);
+CREATE TABLE revlog (
+    id integer PRIMARY KEY, cid integer NOT NULL, usn integer NOT NULL,
+    ease integer NOT NULL, ivl integer NOT NULL, lastIvl integer NOT NULL,
+    factor integer NOT NULL, time integer NOT NULL, type integer NOT NULL
+);
+CREATE TABLE graves (usn integer NOT NULL, oid integer NOT NULL, type integer NOT NULL);
+CREATE INDEX ix_notes_usn ON notes (usn);
+CREATE INDEX ix_cards_usn ON cards (usn);
+CREATE INDEX ix_revlog_usn ON revlog (usn);
+CREATE INDEX ix_cards_nid ON cards (nid);
+CREATE INDEX ix_cards_sched ON cards (did, queue, due);
+CREATE INDEX ix_revlog_cid ON revlog (cid);
+CREATE INDEX ix_notes_csum ON notes (csum);
+";
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct FlashcardParams {
+    /// Books (or chapters) to draw cards from, e.g. "Genesis;Exodus"; the whole Bible when absent.
+    #[serde(default)]
+    books: String,
+}
+
+/// A "Which chapter is ...?" flashcard deck for Anki.
+#[utoipa::path(
+    get,
+    path = "/api/flashcards/{format}",
+    tag = "study",
+    params(("format" = String, Path, description = "`apkg` or `csv`"), FlashcardParams),
+    responses(
+        (status = 200, description = "An Anki package or CSV deck"),
+        (status = 400, description = "Bad references", body = crate::openapi::ErrorBody),
+        (status = 404, description = "Unknown format", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_flashcards(
+    data: web::Data<AppState>,
+    format: web::Path<String>,
+    params: web::Query<FlashcardParams>,
+) -> Result<HttpResponse> {
+    let Some(format) = DeckFormat::parse(&format) else {
+        return Ok(HttpResponse::NotFound().json(json!({
+            "error": format!("Unknown flashcard format '{}'", format)
+        })));
+    };
+    let selection = match Selection::parse(&data.books, &params.books) {
+        Ok(selection) => selection,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(json!({ "error": err }))),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type(format.content_type())
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", format.download_name()),
+        ))
+        .body(Deck::from_selection(&selection).render(format)))
+}
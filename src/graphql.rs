@@ -0,0 +1,479 @@
+//! GraphQL over the outline at `/graphql`, for clients that want only some
+//! fields (say, book names and chapter counts), with a playground page.
+//!
+//! ```graphql
+//! { books(genre: GOSPELS) { name chapterCount chapters(from: 5, to: 7) { number sections { heading } } } }
+//! ```
+
+use actix_web::{web, HttpResponse};
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Enum, Object, Result, Schema, SimpleObject,
+};
+use async_graphql_actix_web::{GraphQLRequest, GraphQLResponse};
+
+use crate::canon::{self, BookInfo, Genre};
+use crate::export::Selection;
+use crate::search::SearchIndex;
+use crate::{layout, translations, AppState, Book, Chapter, Testament, PLACEHOLDER_HEADING};
+
+/// Deeper queries than the outline's shape needs are rejected.
+const MAX_DEPTH: usize = 8;
+/// Each field costs 1 and fields under `books` count ten times, since the
+/// list covers the whole Bible; this admits every field of the full outline
+/// but not many aliased copies of it.
+const MAX_COMPLEXITY: usize = 250;
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+
+pub type BibleSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+pub fn build(state: AppState) -> BibleSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(state)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// The two testaments, as GraphQL enum values `OLD` and `NEW`.
+#[derive(Enum, Clone, Copy, PartialEq, Eq)]
+#[graphql(name = "Testament")]
+enum TestamentValue {
+    Old,
+    New,
+}
+
+impl From<&Testament> for TestamentValue {
+    fn from(testament: &Testament) -> Self {
+        match testament {
+            Testament::Old => TestamentValue::Old,
+            Testament::New => TestamentValue::New,
+        }
+    }
+}
+
+/// The books and search index to answer from: the base headings, or the
+/// heading set named by `headings`.
+fn source<'ctx>(
+    ctx: &Context<'ctx>,
+    headings: Option<&str>,
+) -> Result<(&'ctx [Book], &'ctx SearchIndex)> {
+    let data = ctx.data::<AppState>()?;
+    match headings.filter(|id| *id != translations::SetInfo::base().id) {
+        None => Ok((&data.books, &data.search)),
+        Some(id) => data
+            .translations
+            .iter()
+            .find(|set| set.info.id == id)
+            .map(|set| (&set.books[..], &set.search))
+            .ok_or_else(|| format!("Unknown heading set '{}'", id).into()),
+    }
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Books in canonical order. `references` such as "Genesis 1-3; John"
+    /// narrows both the books and their chapters.
+    #[graphql(complexity = "10 * child_complexity")]
+    async fn books<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        testament: Option<TestamentValue>,
+        genre: Option<Genre>,
+        references: Option<String>,
+        #[graphql(desc = "Heading set id from `/api/translations`")] headings: Option<String>,
+    ) -> Result<Vec<BookNode<'ctx>>> {
+        let (books, _) = source(ctx, headings.as_deref())?;
+        let selection = Selection::parse(books, references.as_deref().unwrap_or(""))?;
+        Ok(selection
+            .books
+            .into_iter()
+            .map(|(book, chapters)| BookNode::new(book, chapters))
+            .filter(|node| testament.is_none_or(|t| node.testament() == t))
+            .filter(|node| genre.is_none_or(|g| node.info.is_some_and(|i| i.genre() == g)))
+            .collect())
+    }
+
+    /// One book by name, OSIS id or abbreviation.
+    async fn book<'ctx>(
+        &self,
+        ctx: &Context<'ctx>,
+        name: String,
+        headings: Option<String>,
+    ) -> Result<Option<BookNode<'ctx>>> {
+        let (books, _) = source(ctx, headings.as_deref())?;
+        let name = canon::lookup(&name).map_or(name.as_str(), |info| info.name);
+        Ok(books
+            .iter()
+            .find(|book| book.name == name)
+            .map(|book| BookNode::new(book, book.chapters.iter().collect())))
+    }
+
+    /// Headings matching every search term, best first.
+    async fn search(
+        &self,
+        ctx: &Context<'_>,
+        query: String,
+        #[graphql(default_with = "DEFAULT_SEARCH_LIMIT")] limit: usize,
+        headings: Option<String>,
+    ) -> Result<SearchResults> {
+        let (_, index) = source(ctx, headings.as_deref())?;
+        let hits = index.search(&query);
+        Ok(SearchResults {
+            total: hits.len(),
+            hits: hits
+                .into_iter()
+                .take(limit)
+                .map(|hit| SearchHit {
+                    testament: TestamentValue::from(&hit.testament),
+                    book: hit.book,
+                    chapter: hit.chapter,
+                    heading: hit.heading,
+                    score: hit.score,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// A book with the chapters a query selected.
+struct BookNode<'a> {
+    book: &'a Book,
+    info: Option<&'static BookInfo>,
+    chapters: Vec<&'a Chapter>,
+}
+
+impl<'a> BookNode<'a> {
+    fn new(book: &'a Book, chapters: Vec<&'a Chapter>) -> Self {
+        BookNode {
+            book,
+            info: canon::info(&book.name),
+            chapters,
+        }
+    }
+
+    fn testament(&self) -> TestamentValue {
+        TestamentValue::from(&self.book.testament)
+    }
+}
+
+#[Object(name = "Book")]
+impl<'a> BookNode<'a> {
+    async fn name(&self) -> &str {
+        &self.book.name
+    }
+
+    /// OSIS book identifier, e.g. "1Cor".
+    async fn osis(&self) -> Option<&str> {
+        self.info.map(|info| info.osis)
+    }
+
+    #[graphql(name = "testament")]
+    async fn testament_field(&self) -> TestamentValue {
+        self.testament()
+    }
+
+    async fn genre(&self) -> Option<Genre> {
+        self.info.map(BookInfo::genre)
+    }
+
+    /// Chapters in the whole book, whatever was selected.
+    async fn chapter_count(&self) -> usize {
+        self.book.chapters.len()
+    }
+
+    /// The selected chapters, optionally limited to `from`..=`to`.
+    async fn chapters(&self, from: Option<u32>, to: Option<u32>) -> Vec<ChapterNode<'a>> {
+        self.chapters
+            .iter()
+            .filter(|c| from.is_none_or(|from| c.chapter_number >= from))
+            .filter(|c| to.is_none_or(|to| c.chapter_number <= to))
+            .map(|chapter| ChapterNode {
+                book: self.book,
+                chapter,
+            })
+            .collect()
+    }
+}
+
+struct ChapterNode<'a> {
+    book: &'a Book,
+    chapter: &'a Chapter,
+}
+
+#[Object(name = "Chapter")]
+impl<'a> ChapterNode<'a> {
+    async fn number(&self) -> u32 {
+        self.chapter.chapter_number
+    }
+
+    /// The chapter's reference, e.g. "Genesis 1".
+    async fn reference(&self) -> String {
+        format!("{} {}", self.book.name, self.chapter.chapter_number)
+    }
+
+    async fn sections(&self) -> Vec<SectionNode<'a>> {
+        self.chapter
+            .sections
+            .iter()
+            .enumerate()
+            .map(|(index, section)| SectionNode {
+                index,
+                heading: &section.heading,
+            })
+            .collect()
+    }
+}
+
+struct SectionNode<'a> {
+    index: usize,
+    heading: &'a str,
+}
+
+#[Object(name = "Section")]
+impl SectionNode<'_> {
+    /// Zero-based position within the chapter.
+    async fn index(&self) -> usize {
+        self.index
+    }
+
+    async fn heading(&self) -> &str {
+        self.heading
+    }
+
+    /// True for chapters whose headings have not been written yet.
+    async fn placeholder(&self) -> bool {
+        self.heading == PLACEHOLDER_HEADING
+    }
+}
+
+#[derive(SimpleObject)]
+struct SearchResults {
+    total: usize,
+    hits: Vec<SearchHit>,
+}
+
+#[derive(SimpleObject)]
+struct SearchHit {
+    book: String,
+    testament: TestamentValue,
+    chapter: u32,
+    heading: String,
+    score: f64,
+}
+
+pub async fn api_graphql(
+    schema: web::Data<BibleSchema>,
+    request: GraphQLRequest,
+) -> GraphQLResponse {
+    schema.execute(request.into_inner()).await.into()
+}
+
+pub async fn playground(schema: web::Data<BibleSchema>) -> actix_web::Result<HttpResponse> {
+    let body = BODY.replace("{sdl}", &layout::escape(&schema.sdl()));
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(layout::offline_page(
+            "GraphQL Playground",
+            STYLES,
+            &body,
+            SCRIPT,
+        )))
+}
+
+const BODY: &str = r#"
+        <section class="card playground">
+            <div class="playground-toolbar">
+                <select id="gql-example" aria-label="Examples">
+                    <option value="">Examples…</option>
+                    <option value="names">Book names and chapter counts</option>
+                    <option value="gospels">Sermon on the Mount headings</option>
+                    <option value="search">Search</option>
+                    <option value="filters">Wisdom books of the Old Testament</option>
+                </select>
+                <button id="gql-run" title="Ctrl+Enter">▶ Run</button>
+            </div>
+            <div class="playground-panes">
+                <div class="playground-editor">
+                    <textarea id="gql-query" spellcheck="false" aria-label="Query"></textarea>
+                    <textarea id="gql-variables" spellcheck="false" aria-label="Variables" placeholder="Variables (JSON)"></textarea>
+                </div>
+                <pre id="gql-result" class="playground-result" aria-live="polite"></pre>
+            </div>
+        </section>
+        <section class="card">
+            <details>
+                <summary>Schema</summary>
+                <pre class="playground-schema">{sdl}</pre>
+            </details>
+        </section>"#;
+
+const STYLES: &str = r#"
+        .playground-toolbar {
+            display: flex;
+            gap: 0.75rem;
+            justify-content: space-between;
+            margin-bottom: 1rem;
+        }
+
+        .playground-toolbar select,
+        .playground-toolbar button {
+            padding: 0.5rem 0.9rem;
+            border-radius: 8px;
+            font: inherit;
+        }
+
+        .playground-toolbar select {
+            border: 2px solid #e2e8f0;
+        }
+
+        .playground-toolbar button {
+            border: none;
+            background: #667eea;
+            color: white;
+            cursor: pointer;
+        }
+
+        .playground-panes {
+            display: grid;
+            grid-template-columns: 1fr 1fr;
+            gap: 1rem;
+        }
+
+        .playground-editor {
+            display: flex;
+            flex-direction: column;
+            gap: 0.75rem;
+        }
+
+        .playground textarea,
+        .playground-result,
+        .playground-schema {
+            font-family: ui-monospace, monospace;
+            font-size: 0.85rem;
+        }
+
+        .playground textarea {
+            padding: 0.75rem;
+            border: 2px solid #e2e8f0;
+            border-radius: 8px;
+            resize: vertical;
+        }
+
+        #gql-query {
+            min-height: 22rem;
+        }
+
+        #gql-variables {
+            min-height: 5rem;
+        }
+
+        .playground-result {
+            min-height: 28rem;
+            max-height: 40rem;
+            overflow: auto;
+            padding: 0.75rem;
+            border-radius: 8px;
+            background: #1a202c;
+            color: #e2e8f0;
+            white-space: pre-wrap;
+        }
+
+        .playground-schema {
+            margin-top: 1rem;
+            white-space: pre-wrap;
+            color: #4a5568;
+        }
+
+        details summary {
+            cursor: pointer;
+            font-weight: 600;
+            color: #667eea;
+        }
+
+        @media (max-width: 768px) {
+            .playground-panes {
+                grid-template-columns: 1fr;
+            }
+        }
+"#;
+
+const SCRIPT: &str = r#"    <script>
+        const EXAMPLES = {
+            names: `{
+  books {
+    name
+    chapterCount
+  }
+}`,
+            gospels: `{
+  books(references: "Matthew 5-7") {
+    name
+    chapters {
+      reference
+      sections { heading }
+    }
+  }
+}`,
+            search: `query Search($q: String!) {
+  search(query: $q, limit: 5) {
+    total
+    hits { book chapter heading score }
+  }
+}`,
+            filters: `{
+  books(testament: OLD, genre: WISDOM) {
+    name
+    osis
+    genre
+  }
+}`
+        };
+        const VARIABLES = { search: '{ "q": "parable" }' };
+        const STORAGE_KEY = 'graphql-playground';
+
+        const query = document.getElementById('gql-query');
+        const variables = document.getElementById('gql-variables');
+        const result = document.getElementById('gql-result');
+
+        const saved = JSON.parse(localStorage.getItem(STORAGE_KEY) || 'null');
+        query.value = saved ? saved.query : EXAMPLES.names;
+        variables.value = saved ? saved.variables : '';
+
+        async function run() {
+            localStorage.setItem(STORAGE_KEY, JSON.stringify({ query: query.value, variables: variables.value }));
+            let parsed = {};
+            try {
+                parsed = variables.value.trim() ? JSON.parse(variables.value) : {};
+            } catch (err) {
+                result.textContent = 'Variables are not valid JSON: ' + err.message;
+                return;
+            }
+            result.textContent = '…';
+            try {
+                const response = await fetch('/graphql', {
+                    method: 'POST',
+                    headers: { 'Content-Type': 'application/json' },
+                    body: JSON.stringify({ query: query.value, variables: parsed })
+                });
+                result.textContent = JSON.stringify(await response.json(), null, 2);
+            } catch (err) {
+                result.textContent = String(err);
+            }
+        }
+
+        document.getElementById('gql-run').addEventListener('click', run);
+        query.addEventListener('keydown', event => {
+            if ((event.ctrlKey || event.metaKey) && event.key === 'Enter') run();
+        });
+        document.getElementById('gql-example').addEventListener('change', event => {
+            const name = event.target.value;
+            if (!name) return;
+            query.value = EXAMPLES[name];
+            variables.value = VARIABLES[name] || '';
+            event.target.value = '';
+            run();
+        });
+    </script>
+"#;
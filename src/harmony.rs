@@ -0,0 +1,310 @@
+//! Gospel harmony: parallel sections of Matthew, Mark, Luke and John grouped
+//! into events, driven by `data/harmony.txt`.
+
+use actix_web::{web, HttpResponse, Result};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::config::DataPath;
+use crate::reference::{heading_range, Passage};
+use crate::{chapter_anchor, layout, AppState, Book, PLACEHOLDER_HEADING};
+
+const DEFAULT_HARMONY: &str = include_str!("../data/harmony.txt");
+
+pub const GOSPELS: [&str; 4] = ["Matthew", "Mark", "Luke", "John"];
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HarmonyHeading {
+    pub chapter: u32,
+    pub heading: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HarmonyPassage {
+    pub label: String,
+    pub chapter: u32,
+    /// Loaded headings that fall inside the passage.
+    pub headings: Vec<HarmonyHeading>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HarmonyAccount {
+    pub gospel: &'static str,
+    pub passages: Vec<HarmonyPassage>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct HarmonyEvent {
+    pub title: String,
+    /// One account per Gospel, in canonical order; empty when a Gospel is silent.
+    pub accounts: Vec<HarmonyAccount>,
+}
+
+pub struct Harmony {
+    pub events: Vec<HarmonyEvent>,
+}
+
+impl Harmony {
+    /// Loads `path` (or the bundled file) and resolves it against the loaded books.
+    pub fn load(books: &[Book], path: &DataPath) -> Self {
+        let source = path
+            .read("harmony")
+            .unwrap_or_else(|| DEFAULT_HARMONY.to_string());
+
+        let (harmony, errors) = Harmony::parse(&source, books);
+        for (line, err) in errors {
+            log::warn!("Skipping harmony line {}: {}", line, err);
+        }
+        harmony
+    }
+
+    /// Parses `title | Matthew | Mark | Luke | John` lines, returning the lines that failed.
+    pub fn parse(source: &str, books: &[Book]) -> (Self, Vec<(usize, String)>) {
+        let mut events = Vec::new();
+        let mut errors = Vec::new();
+
+        'lines: for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let columns: Vec<&str> = line.split('|').map(str::trim).collect();
+            if columns.len() != GOSPELS.len() + 1 {
+                errors.push((
+                    index + 1,
+                    "expected 'title | Matthew | Mark | Luke | John'".to_string(),
+                ));
+                continue;
+            }
+
+            let mut accounts = Vec::new();
+            for (gospel, cell) in GOSPELS.iter().zip(&columns[1..]) {
+                let mut passages = Vec::new();
+                for reference in cell.split(';').map(str::trim).filter(|r| !r.is_empty()) {
+                    match Passage::parse(reference) {
+                        Some(passage) if passage.book.name == *gospel => {
+                            passages.push(resolve(&passage, books));
+                        }
+                        _ => {
+                            errors.push((
+                                index + 1,
+                                format!("'{}' is not a {} reference", reference, gospel),
+                            ));
+                            continue 'lines;
+                        }
+                    }
+                }
+                accounts.push(HarmonyAccount { gospel, passages });
+            }
+
+            events.push(HarmonyEvent {
+                title: columns[0].to_string(),
+                accounts,
+            });
+        }
+
+        (Harmony { events }, errors)
+    }
+}
+
+/// Finds the loaded headings inside a passage. Within a verse range only
+/// headings whose own range overlaps it are kept; chapters without ranged
+/// headings contribute all of their headings.
+fn resolve(passage: &Passage, books: &[Book]) -> HarmonyPassage {
+    let (first, last) = passage.chapters.unwrap_or((1, u32::MAX));
+    let mut headings = Vec::new();
+
+    if let Some(book) = books.iter().find(|book| book.name == passage.book.name) {
+        for chapter in book
+            .chapters
+            .iter()
+            .filter(|c| (first..=last).contains(&c.chapter_number))
+        {
+            let ranged = chapter
+                .sections
+                .iter()
+                .any(|s| heading_range(&s.heading, chapter.chapter_number).is_some());
+
+            for section in &chapter.sections {
+                if section.heading == PLACEHOLDER_HEADING {
+                    continue;
+                }
+                let overlaps = match (
+                    passage.verses,
+                    heading_range(&section.heading, chapter.chapter_number),
+                ) {
+                    (Some((start, end)), Some((s, e))) => s <= end && start <= e,
+                    (Some(_), None) => !ranged,
+                    (None, _) => true,
+                };
+                if overlaps {
+                    headings.push(HarmonyHeading {
+                        chapter: chapter.chapter_number,
+                        heading: section.heading.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    HarmonyPassage {
+        label: passage.to_string(),
+        chapter: first,
+        headings,
+    }
+}
+
+/// The Gospel harmony: parallel passages of Matthew, Mark, Luke and John.
+#[utoipa::path(
+    get,
+    path = "/api/harmony",
+    tag = "references",
+    responses((status = 200, description = "Events with each Gospel's account", body = [HarmonyEvent]))
+)]
+pub async fn api_harmony(data: web::Data<AppState>) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(&data.harmony.events))
+}
+
+pub async fn harmony_page(data: web::Data<AppState>) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(render(&data.harmony)))
+}
+
+fn render(harmony: &Harmony) -> String {
+    let mut rows = String::new();
+
+    for event in &harmony.events {
+        rows.push_str(&format!(
+            r#"
+                    <tr>
+                        <th scope="row" class="event-title">{}</th>"#,
+            event.title
+        ));
+
+        for account in &event.accounts {
+            rows.push_str(
+                r#"
+                        <td>"#,
+            );
+            if account.passages.is_empty() {
+                rows.push_str(r#"<span class="absent" aria-label="Not recorded">—</span>"#);
+            }
+            for passage in &account.passages {
+                rows.push_str(&format!(
+                    r#"<div class="passage"><a class="passage-ref" href="/#{}">{}</a>"#,
+                    chapter_anchor(account.gospel, passage.chapter),
+                    passage.label
+                ));
+                for heading in &passage.headings {
+                    rows.push_str(&format!(
+                        r#"<div class="passage-heading">• {}</div>"#,
+                        heading.heading
+                    ));
+                }
+                rows.push_str("</div>");
+            }
+            rows.push_str("</td>");
+        }
+
+        rows.push_str(
+            r#"
+                    </tr>"#,
+        );
+    }
+
+    let header: String = GOSPELS
+        .iter()
+        .map(|gospel| format!(r#"<th scope="col">{}</th>"#, gospel))
+        .collect();
+
+    let body = format!(
+        r#"
+        <div class="card harmony">
+            <p class="intro">Parallel accounts of the life of Jesus across the four Gospels.</p>
+            <div class="table-scroll">
+                <table>
+                    <thead>
+                        <tr><th scope="col">Event</th>{}</tr>
+                    </thead>
+                    <tbody>{}
+                    </tbody>
+                </table>
+            </div>
+        </div>"#,
+        header, rows
+    );
+
+    layout::page("Gospel Harmony", STYLES, &body, "")
+}
+
+const STYLES: &str = r#"
+        .intro {
+            color: #4a5568;
+            margin-bottom: 1.25rem;
+        }
+
+        .table-scroll {
+            overflow-x: auto;
+        }
+
+        table {
+            width: 100%;
+            min-width: 760px;
+            border-collapse: collapse;
+            font-size: 0.9rem;
+        }
+
+        th, td {
+            text-align: left;
+            vertical-align: top;
+            padding: 0.75rem;
+            border-bottom: 1px solid #e2e8f0;
+        }
+
+        thead th {
+            position: sticky;
+            top: 0;
+            background: white;
+            font-family: 'Cormorant Garamond', serif;
+            font-size: 1.3rem;
+            color: #667eea;
+            border-bottom: 2px solid #667eea;
+        }
+
+        .event-title {
+            font-family: 'Cormorant Garamond', serif;
+            font-size: 1.1rem;
+            font-weight: 600;
+            color: #2d3748;
+            width: 20%;
+        }
+
+        .passage + .passage {
+            margin-top: 0.5rem;
+        }
+
+        .passage-ref {
+            color: #667eea;
+            font-weight: 600;
+            text-decoration: none;
+        }
+
+        .passage-ref:hover {
+            text-decoration: underline;
+        }
+
+        .passage-heading {
+            color: #4a5568;
+            margin-top: 0.25rem;
+        }
+
+        .absent {
+            color: #a0aec0;
+        }
+
+        tbody tr:hover {
+            background-color: rgba(102, 126, 234, 0.05);
+        }
+"#;
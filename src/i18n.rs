@@ -0,0 +1,360 @@
+//! Localization of the web UI: message catalogs with localized book names and
+//! abbreviations, and per-request language negotiation (`?lang=`, a `lang`
+//! cookie remembering it, then `Accept-Language`).
+//!
+//! Catalogs are bundled from `data/locales/` and can be replaced at runtime
+//! from the data directory's `locales/` folder (or `LOCALES_DIR`). English is
+//! the fallback for any missing message.
+
+use std::collections::{BTreeMap, HashMap};
+use std::sync::OnceLock;
+
+use actix_web::cookie::{time::Duration, Cookie, SameSite};
+use actix_web::{HttpRequest, HttpResponse, Result};
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::canon;
+use crate::config::DataPath;
+
+const LANG_COOKIE: &str = "lang";
+const LANG_COOKIE_DAYS: i64 = 365;
+
+/// Bundled catalogs, English first: it is the default and the fallback.
+const BUNDLED: &[(&str, &str)] = &[
+    ("en", include_str!("../data/locales/en.txt")),
+    ("es", include_str!("../data/locales/es.txt")),
+    ("ch", include_str!("../data/locales/ch.txt")),
+];
+
+#[derive(Debug, Default)]
+pub struct Catalog {
+    pub code: String,
+    messages: HashMap<String, String>,
+    /// English book name → (localized name, abbreviations).
+    books: HashMap<String, (String, Vec<String>)>,
+}
+
+impl Catalog {
+    /// Parses `key = text` lines; `book.<English name>` keys hold
+    /// "Name | Abbr, Abbr". Returns the catalog and any malformed lines.
+    fn parse(code: &str, source: &str) -> (Catalog, Vec<(usize, String)>) {
+        let mut catalog = Catalog {
+            code: code.to_string(),
+            ..Catalog::default()
+        };
+        let mut errors = Vec::new();
+
+        for (index, line) in source.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((key, text)) = line.split_once('=') else {
+                errors.push((
+                    index + 1,
+                    format!("expected 'key = text', found '{}'", line),
+                ));
+                continue;
+            };
+            let (key, text) = (key.trim(), text.trim());
+
+            if let Some(book) = key.strip_prefix("book.") {
+                let Some(info) = canon::info(book) else {
+                    errors.push((index + 1, format!("unknown book '{}'", book)));
+                    continue;
+                };
+                let (name, abbreviations) = text.split_once('|').unwrap_or((text, ""));
+                let abbreviations = abbreviations
+                    .split(',')
+                    .map(str::trim)
+                    .filter(|a| !a.is_empty())
+                    .map(str::to_string)
+                    .collect();
+                catalog.books.insert(
+                    info.name.to_string(),
+                    (name.trim().to_string(), abbreviations),
+                );
+            } else {
+                catalog.messages.insert(key.to_string(), text.to_string());
+            }
+        }
+        (catalog, errors)
+    }
+}
+
+/// All loaded catalogs; the first is English.
+pub struct Catalogs {
+    catalogs: Vec<Catalog>,
+}
+
+impl Catalogs {
+    /// Reads each catalog from `dir` when it has one, else the bundled copy.
+    fn load(dir: Option<&DataPath>) -> Self {
+        let catalogs = BUNDLED
+            .iter()
+            .map(|(code, bundled)| {
+                let source = dir
+                    .and_then(|dir| dir.join(&format!("{}.txt", code)).read("messages"))
+                    .unwrap_or_else(|| bundled.to_string());
+                let (catalog, errors) = Catalog::parse(code, &source);
+                for (line, err) in errors {
+                    log::warn!("Skipping {} message on line {}: {}", code, line, err);
+                }
+                catalog
+            })
+            .collect();
+        Catalogs { catalogs }
+    }
+
+    fn get(&self, code: &str) -> Option<&Catalog> {
+        self.catalogs.iter().find(|c| c.code == code)
+    }
+
+    pub fn locale(&self, code: &str) -> Locale<'_> {
+        let fallback = &self.catalogs[0];
+        Locale {
+            catalog: self.get(code).unwrap_or(fallback),
+            fallback,
+        }
+    }
+
+    /// The language for a request: `?lang=`, then the `lang` cookie, then the
+    /// best supported `Accept-Language` entry, then English.
+    pub fn negotiate(&self, req: &HttpRequest) -> Locale<'_> {
+        let supported = |code: &str| self.get(code).map(|c| c.code.as_str());
+        let code = requested(req)
+            .and_then(|code| supported(&code))
+            .or_else(|| {
+                req.cookie(LANG_COOKIE)
+                    .and_then(|cookie| supported(cookie.value()))
+            })
+            .or_else(|| {
+                accept_language(req)
+                    .into_iter()
+                    .find_map(|code| supported(&code))
+            })
+            .unwrap_or("en");
+        self.locale(code)
+    }
+}
+
+static CATALOGS: OnceLock<Catalogs> = OnceLock::new();
+
+/// Loads the shared catalogs from `dir` at startup, before any are used.
+pub fn load_catalogs(dir: &DataPath) {
+    CATALOGS.get_or_init(|| Catalogs::load(Some(dir)));
+}
+
+/// The shared catalogs; only the bundled ones unless [`load_catalogs`] ran first.
+pub fn catalogs() -> &'static Catalogs {
+    CATALOGS.get_or_init(|| Catalogs::load(None))
+}
+
+/// Resolves a localized book name or abbreviation, already normalized the
+/// way [`canon::lookup`] normalizes, to the English book name.
+pub fn localized_book(key: &str) -> Option<&'static str> {
+    catalogs().catalogs.iter().find_map(|catalog| {
+        catalog
+            .books
+            .iter()
+            .find(|(_, (name, abbreviations))| {
+                canon::normalize(name) == key
+                    || abbreviations.iter().any(|a| canon::normalize(a) == key)
+            })
+            .map(|(english, _)| english.as_str())
+    })
+}
+
+/// The primary language subtag of `?lang=`, if given.
+fn requested(req: &HttpRequest) -> Option<String> {
+    req.query_string()
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(key, _)| *key == "lang")
+        .map(|(_, value)| primary_subtag(value))
+}
+
+fn primary_subtag(tag: &str) -> String {
+    tag.split(['-', '_'])
+        .next()
+        .unwrap_or("")
+        .trim()
+        .to_ascii_lowercase()
+}
+
+/// The languages of `Accept-Language`, most preferred first.
+fn accept_language(req: &HttpRequest) -> Vec<String> {
+    let Some(header) = req
+        .headers()
+        .get("Accept-Language")
+        .and_then(|v| v.to_str().ok())
+    else {
+        return Vec::new();
+    };
+
+    let mut ranges: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|range| {
+            let mut parts = range.split(';');
+            let tag = parts.next()?.trim();
+            let quality = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse().ok())?;
+            (tag != "*" && quality > 0.0).then(|| (primary_subtag(tag), quality))
+        })
+        .collect();
+    // A stable sort keeps the header's order among equal weights.
+    ranges.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranges.into_iter().map(|(tag, _)| tag).collect()
+}
+
+/// A catalog together with the English fallback.
+#[derive(Clone, Copy)]
+pub struct Locale<'a> {
+    catalog: &'a Catalog,
+    fallback: &'a Catalog,
+}
+
+impl<'a> Locale<'a> {
+    pub fn code(&self) -> &'a str {
+        &self.catalog.code
+    }
+
+    /// The UI's text direction, `ltr` or `rtl`.
+    pub fn direction(&self) -> &'a str {
+        match self.text("language.direction") {
+            "rtl" => "rtl",
+            _ => "ltr",
+        }
+    }
+
+    /// The message for `key`, in English when this language lacks it.
+    pub fn text(&self, key: &str) -> &'a str {
+        self.catalog
+            .messages
+            .get(key)
+            .or_else(|| self.fallback.messages.get(key))
+            .map_or("", String::as_str)
+    }
+
+    /// The message for `key` with `{name}` placeholders filled in.
+    pub fn format(&self, key: &str, args: &[(&str, &str)]) -> String {
+        let mut text = self.text(key).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), value);
+        }
+        text
+    }
+
+    /// A book's name in this language, or its English name.
+    pub fn book_name<'b>(&self, english: &'b str) -> &'b str
+    where
+        'a: 'b,
+    {
+        self.catalog
+            .books
+            .get(english)
+            .map_or(english, |(name, _)| name.as_str())
+    }
+
+    /// The messages the page script formats itself, as a JSON object.
+    pub fn script_messages(&self) -> String {
+        let keys = [
+            "stats.all",
+            "stats.found",
+            "stats.books.one",
+            "stats.books.other",
+            "stats.chapters.one",
+            "stats.chapters.other",
+            "verses.show",
+            "verses.hide",
+            "verses.chapter_text",
+            "verses.unavailable",
+        ];
+        let messages: BTreeMap<&str, &str> = keys.iter().map(|k| (*k, self.text(k))).collect();
+        serde_json::to_string(&messages).unwrap_or_else(|_| "{}".to_string())
+    }
+}
+
+/// Links switching the page to each available language.
+pub fn language_links_html(locale: &Locale) -> String {
+    let mut html = format!(
+        r#"
+            <nav class="language-links" aria-label="{}">"#,
+        locale.text("nav.language")
+    );
+    for catalog in &catalogs().catalogs {
+        let current = if catalog.code == locale.code() {
+            r#" aria-current="true""#
+        } else {
+            ""
+        };
+        html.push_str(&format!(
+            r#"
+                <a href="?lang={0}" lang="{0}" hreflang="{0}"{1}>{2}</a>"#,
+            catalog.code,
+            current,
+            catalog
+                .messages
+                .get("language.name")
+                .map_or("", String::as_str)
+        ));
+    }
+    html.push_str(
+        r#"
+            </nav>"#,
+    );
+    html
+}
+
+/// Remembers an explicit `?lang=` choice so later pages keep the language.
+pub fn remember(req: &HttpRequest, locale: &Locale, response: &mut HttpResponse) {
+    if requested(req).as_deref() != Some(locale.code()) {
+        return;
+    }
+    let cookie = Cookie::build(LANG_COOKIE, locale.code().to_string())
+        .path("/")
+        .same_site(SameSite::Lax)
+        .max_age(Duration::days(LANG_COOKIE_DAYS))
+        .finish();
+    // A freshly built cookie always forms a valid header value.
+    let _ = response.add_cookie(&cookie);
+}
+
+#[derive(Serialize, ToSchema)]
+struct Language {
+    code: String,
+    name: String,
+    /// Share of the English messages this language translates, 0 to 1.
+    coverage: f32,
+    /// Books with a localized name.
+    books: usize,
+}
+
+/// The available languages and how complete each catalog is.
+#[utoipa::path(
+    get,
+    path = "/api/languages",
+    tag = "books",
+    responses((status = 200, description = "UI languages", body = [Language]))
+)]
+pub async fn api_languages() -> Result<HttpResponse> {
+    let all = catalogs();
+    let english = &all.catalogs[0].messages;
+    let languages: Vec<Language> = all
+        .catalogs
+        .iter()
+        .map(|catalog| Language {
+            code: catalog.code.clone(),
+            name: all.locale(&catalog.code).text("language.name").to_string(),
+            coverage: english
+                .keys()
+                .filter(|key| catalog.messages.contains_key(*key))
+                .count() as f32
+                / english.len().max(1) as f32,
+            books: catalog.books.len(),
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(languages))
+}
@@ -0,0 +1,159 @@
+//! Shared page shell for the secondary HTML pages (harmony, quiz, docs...),
+//! matching the look of the main index page.
+
+/// The site's web fonts, loaded from Google Fonts.
+const FONT_LINKS: &str = r#"
+    <link rel="preconnect" href="https://fonts.googleapis.com">
+    <link rel="preconnect" href="https://fonts.gstatic.com" crossorigin>
+    <link href="https://fonts.googleapis.com/css2?family=Cormorant+Garamond:wght@300;400;500;600;700&family=Inter:wght@300;400;500;600&display=swap" rel="stylesheet">"#;
+
+/// Wraps `body` in the site's document shell. `styles` and `script` are
+/// page-specific additions to the shared CSS and an optional inline script.
+pub fn page(title: &str, styles: &str, body: &str, script: &str) -> String {
+    shell(title, FONT_LINKS, styles, body, script)
+}
+
+/// Like [`page`], but self-contained: system fonts instead of web fonts, so
+/// the page makes no requests beyond the server it came from.
+pub fn offline_page(title: &str, styles: &str, body: &str, script: &str) -> String {
+    shell(title, "", &format!("{SYSTEM_FONTS}{styles}"), body, script)
+}
+
+fn shell(title: &str, fonts: &str, styles: &str, body: &str, script: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{title} · Bible Section Headlines</title>{fonts}
+    <style>{BASE_STYLES}{styles}    </style>
+</head>
+<body>
+    <div class="container">
+        <header>
+            <h1>✦ {title} ✦</h1>
+            <p class="subtitle"><a href="/">← Back to all books</a></p>
+        </header>
+{body}
+        <footer>
+            <p>A reverent way to explore Scripture</p>
+        </footer>
+    </div>
+{script}</body>
+</html>"#
+    )
+}
+
+const BASE_STYLES: &str = r#"
+        * {
+            margin: 0;
+            padding: 0;
+            box-sizing: border-box;
+        }
+
+        body {
+            font-family: 'Inter', sans-serif;
+            background: linear-gradient(135deg, #667eea 0%, #764ba2 100%);
+            min-height: 100vh;
+            padding: 1rem;
+            color: #2d3748;
+        }
+
+        .container {
+            max-width: 1400px;
+            margin: 0 auto;
+        }
+
+        header {
+            text-align: center;
+            margin-bottom: 2rem;
+            padding: 1.5rem;
+        }
+
+        h1 {
+            font-family: 'Cormorant Garamond', serif;
+            font-size: 3rem;
+            font-weight: 700;
+            color: #ffffff;
+            margin-bottom: 0.5rem;
+            text-shadow: 2px 2px 4px rgba(0,0,0,0.1);
+            letter-spacing: -1px;
+        }
+
+        .subtitle {
+            font-size: 1.1rem;
+            color: rgba(255,255,255,0.9);
+            font-weight: 300;
+            letter-spacing: 0.5px;
+        }
+
+        .subtitle a {
+            color: inherit;
+            text-decoration: none;
+        }
+
+        .subtitle a:hover {
+            text-decoration: underline;
+        }
+
+        .card {
+            background: white;
+            border-radius: 16px;
+            padding: 1.5rem 2rem;
+            margin-bottom: 1.5rem;
+            box-shadow: 0 10px 40px rgba(0,0,0,0.1);
+        }
+
+        .card h2 {
+            font-family: 'Cormorant Garamond', serif;
+            font-size: 2rem;
+            font-weight: 700;
+            color: #667eea;
+            margin-bottom: 1rem;
+        }
+
+        footer {
+            text-align: center;
+            padding: 2rem;
+            color: rgba(255,255,255,0.9);
+            font-size: 0.9rem;
+        }
+
+        @media (max-width: 768px) {
+            h1 {
+                font-size: 2.2rem;
+            }
+
+            .card {
+                padding: 1.25rem 1.5rem;
+            }
+        }
+"#;
+
+/// Stand-ins for the web fonts, from what the system has installed.
+const SYSTEM_FONTS: &str = r#"
+        body {
+            font-family: system-ui, -apple-system, 'Segoe UI', Roboto, 'Helvetica Neue', Arial, sans-serif;
+        }
+
+        h1, .card h2 {
+            font-family: 'Iowan Old Style', 'Palatino Linotype', Palatino, Georgia, serif;
+        }
+"#;
+
+/// Escapes user-supplied text for use in HTML content and attribute values.
+pub fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,298 @@
+//! Pagination and sparse responses for list endpoints. `?limit=` and
+//! `?cursor=` page through the items, with `Link` and `X-Total-Count` headers
+//! describing the pages; `?fields=` keeps only the named fields of each item
+//! and `?include=` adds related data to that selection.
+
+use std::collections::BTreeMap;
+
+use actix_web::http::header::{HeaderName, LINK};
+use actix_web::{HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use utoipa::IntoParams;
+
+use crate::negotiate;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ListParams {
+    /// Most items per page; every item by default.
+    limit: Option<usize>,
+    /// Where the page starts, from a `next`, `prev`, `first` or `last` link.
+    cursor: Option<String>,
+    /// Comma-separated fields to keep, with dots for nested ones, e.g.
+    /// `name,chapters.chapter_number`.
+    fields: Option<String>,
+    /// Comma-separated related data to add to `fields`, e.g. `sections`;
+    /// without `fields` every item is already complete.
+    include: Option<String>,
+}
+
+impl ListParams {
+    /// Whether the request asks for the plain, complete list.
+    pub fn is_default(&self) -> bool {
+        self.limit.is_none()
+            && self.cursor.is_none()
+            && self.fields.is_none()
+            && self.include.is_none()
+    }
+}
+
+/// A tree of selected fields; `whole` keeps a field with everything under it.
+#[derive(Default)]
+struct Fields {
+    whole: bool,
+    children: BTreeMap<String, Fields>,
+}
+
+impl Fields {
+    fn insert(&mut self, path: &str) {
+        let mut node = self;
+        for part in path.split('.') {
+            node = node.children.entry(part.to_string()).or_default();
+        }
+        node.whole = true;
+    }
+
+    fn project(&self, value: &Value) -> Value {
+        if self.whole {
+            return value.clone();
+        }
+        match value {
+            Value::Object(object) => Value::Object(
+                self.children
+                    .iter()
+                    .filter_map(|(key, fields)| {
+                        Some((key.clone(), fields.project(object.get(key)?)))
+                    })
+                    .collect::<Map<_, _>>(),
+            ),
+            Value::Array(items) => Value::Array(items.iter().map(|v| self.project(v)).collect()),
+            other => other.clone(),
+        }
+    }
+}
+
+/// Whether any item has the dotted `path`, looking through arrays.
+fn has_path(value: &Value, path: &[&str]) -> bool {
+    match (value, path.split_first()) {
+        (_, None) => true,
+        (Value::Object(object), Some((first, rest))) => {
+            object.get(*first).is_some_and(|v| has_path(v, rest))
+        }
+        (Value::Array(items), Some(_)) => items.iter().any(|v| has_path(v, path)),
+        _ => false,
+    }
+}
+
+fn bad_request(message: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({ "error": message }))
+}
+
+/// Percent-encodes a cursor for use in a query string.
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// The request's URL with `cursor` replaced.
+fn link(req: &HttpRequest, cursor: &str) -> String {
+    let mut query: Vec<&str> = req
+        .query_string()
+        .split('&')
+        .filter(|pair| !pair.is_empty() && !pair.starts_with("cursor="))
+        .collect();
+    let cursor = format!("cursor={}", encode(cursor));
+    query.push(&cursor);
+    format!("{}?{}", req.path(), query.join("&"))
+}
+
+/// Responds with the page of `items` the request asks for. `key` gives each
+/// item's cursor, `includes` maps `?include=` names to the field paths they
+/// add, and `cache_control` is sent with the page.
+pub fn respond<T: Serialize>(
+    req: &HttpRequest,
+    params: &ListParams,
+    items: &[T],
+    key: impl Fn(&T) -> String,
+    includes: &[(&str, &[&str])],
+    cache_control: &'static str,
+) -> HttpResponse {
+    let total = items.len();
+    let limit = match params.limit {
+        Some(0) => return bad_request("limit must be at least 1".to_string()),
+        Some(limit) => limit,
+        None => total.max(1),
+    };
+    let keys: Vec<String> = items.iter().map(key).collect();
+    let start = match &params.cursor {
+        None => 0,
+        Some(cursor) => match keys.iter().position(|key| key == cursor) {
+            Some(start) => start,
+            None => return bad_request(format!("Unknown cursor '{}'", cursor)),
+        },
+    };
+    let end = start.saturating_add(limit).min(total);
+    let mut included: Vec<&str> = Vec::new();
+    for name in params.include.iter().flat_map(|list| list.split(',')) {
+        match includes.iter().find(|(include, _)| *include == name.trim()) {
+            Some((_, added)) => included.extend_from_slice(added),
+            None => return bad_request(format!("Unknown include '{}'", name.trim())),
+        }
+    }
+
+    let values: Vec<Value> = items[start..end]
+        .iter()
+        .map(|item| serde_json::to_value(item).expect("serializable item"))
+        .collect();
+    let values = match &params.fields {
+        None => values,
+        Some(list) => {
+            let mut fields = Fields::default();
+            let paths = list.split(',').map(str::trim).chain(included);
+            for path in paths.filter(|path| !path.is_empty()) {
+                let parts: Vec<&str> = path.split('.').collect();
+                if !values.is_empty() && !values.iter().any(|v| has_path(v, &parts)) {
+                    return bad_request(format!("Unknown field '{}'", path));
+                }
+                fields.insert(path);
+            }
+            values.iter().map(|v| fields.project(v)).collect()
+        }
+    };
+
+    let mut response = negotiate::respond_with(req, &values, cache_control);
+    if params.limit.is_some() && total > 0 {
+        let mut links = vec![
+            format!("<{}>; rel=\"first\"", link(req, &keys[0])),
+            format!(
+                "<{}>; rel=\"last\"",
+                link(req, &keys[(total - 1) / limit * limit])
+            ),
+        ];
+        if start > 0 {
+            let prev = start.saturating_sub(limit);
+            links.push(format!("<{}>; rel=\"prev\"", link(req, &keys[prev])));
+        }
+        if end < total {
+            links.push(format!("<{}>; rel=\"next\"", link(req, &keys[end])));
+        }
+        if let Ok(value) = links.join(", ").parse() {
+            response.headers_mut().insert(LINK, value);
+        }
+    }
+    if let Ok(value) = total.to_string().parse() {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static("x-total-count"), value);
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::test::TestRequest;
+
+    use crate::cache;
+
+    #[derive(Serialize)]
+    struct Item {
+        name: String,
+        chapters: Vec<u32>,
+    }
+
+    fn items() -> Vec<Item> {
+        ["Gen", "Exod", "Lev", "Num", "Deut"]
+            .iter()
+            .map(|name| Item {
+                name: name.to_string(),
+                chapters: vec![1, 2],
+            })
+            .collect()
+    }
+
+    async fn get(query: &str) -> (HttpResponse, Value) {
+        let req = TestRequest::get()
+            .uri(&format!("/api/books?{}", query))
+            .to_http_request();
+        let params = actix_web::web::Query::<ListParams>::from_query(query)
+            .unwrap()
+            .into_inner();
+        let response = respond(
+            &req,
+            &params,
+            &items(),
+            |item| item.name.clone(),
+            &[],
+            cache::API_CACHE_CONTROL,
+        );
+        let (response, body) = response.into_parts();
+        let body = to_bytes(body).await.unwrap();
+        let value = serde_json::from_slice(&body).unwrap();
+        (response.set_body(()).map_into_boxed_body(), value)
+    }
+
+    fn names(value: &Value) -> Vec<&str> {
+        value
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|item| item["name"].as_str().unwrap())
+            .collect()
+    }
+
+    fn header<'a>(response: &'a HttpResponse, name: &str) -> &'a str {
+        response.headers().get(name).unwrap().to_str().unwrap()
+    }
+
+    #[actix_web::test]
+    async fn pages_follow_the_cursor() {
+        let (response, value) = get("limit=2&cursor=Exod").await;
+        assert_eq!(names(&value), ["Exod", "Lev"]);
+        assert_eq!(header(&response, "x-total-count"), "5");
+        let links = header(&response, "link");
+        assert!(links.contains("cursor=Gen>; rel=\"first\""));
+        assert!(links.contains("cursor=Deut>; rel=\"last\""));
+        assert!(links.contains("cursor=Gen>; rel=\"prev\""));
+        assert!(links.contains("cursor=Num>; rel=\"next\""));
+    }
+
+    #[actix_web::test]
+    async fn huge_limits_return_the_rest() {
+        let (response, value) = get(&format!("limit={}&cursor=Exod", usize::MAX)).await;
+        assert_eq!(names(&value), ["Exod", "Lev", "Num", "Deut"]);
+        assert!(!header(&response, "link").contains("rel=\"next\""));
+    }
+
+    #[actix_web::test]
+    async fn bad_parameters_are_rejected() {
+        let (response, _) = get("limit=0").await;
+        assert_eq!(response.status(), 400);
+        let (response, value) = get("cursor=Ruth").await;
+        assert_eq!(response.status(), 400);
+        assert_eq!(value["error"], "Unknown cursor 'Ruth'");
+        let (response, _) = get("fields=title").await;
+        assert_eq!(response.status(), 400);
+    }
+
+    #[actix_web::test]
+    async fn fields_keep_only_the_named_ones() {
+        let (_, value) = get("fields=name&limit=1").await;
+        assert_eq!(value, json!([{ "name": "Gen" }]));
+    }
+
+    #[actix_web::test]
+    async fn unknown_includes_are_rejected_without_fields() {
+        let (response, value) = get("include=bogus").await;
+        assert_eq!(response.status(), 400);
+        assert_eq!(value["error"], "Unknown include 'bogus'");
+    }
+}
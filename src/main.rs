@@ -1,25 +1,73 @@
-use actix_web::{web, App, HttpServer, HttpResponse, Result};
+use actix_web::http::header;
+use actix_web::{middleware, web, App, HttpRequest, HttpServer, HttpResponse, Result};
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+mod accounts;
+mod cache;
+mod canon;
+mod cli;
+mod config;
+mod crossref;
+mod epub;
+mod export;
+mod flashcards;
+mod graphql;
+mod harmony;
+mod i18n;
+mod layout;
+mod listing;
+mod negotiate;
+mod openapi;
+mod overlay;
+mod pdf;
+mod plans;
+mod query;
+mod quiz;
+mod reference;
+mod search;
+mod suggest;
+mod translations;
+mod tui;
+mod v1;
+mod verses;
+mod zip;
+
+use accounts::{AccountStore, Annotations};
+use cache::{DataVersion, PageCache};
+use config::Config;
+use crossref::CrossReferences;
+use harmony::Harmony;
+use i18n::Locale;
+use plans::ReadingPlan;
+use negotiate::Precomputed;
+use quiz::QuizSessions;
+use search::{Analyzer, SearchIndex};
+use suggest::SuggestionTrie;
+use translations::{HeadingSet, SetInfo};
+
+/// Heading text used for chapters whose section headings have not been written yet.
+const PLACEHOLDER_HEADING: &str = "Section headings to be added";
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct SectionHeading {
     heading: String,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct Chapter {
     chapter_number: u32,
     sections: Vec<SectionHeading>,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 enum Testament {
     Old,
     New,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 struct Book {
     name: String,
     testament: Testament,
@@ -29,6 +77,32 @@ struct Book {
 #[derive(Clone)]
 struct AppState {
     books: Arc<Vec<Book>>,
+    /// `/api/books` serialized and compressed once, in every format.
+    books_body: Arc<Precomputed>,
+    data_version: Arc<DataVersion>,
+    /// Rendered `/` pages for visitors who are not signed in.
+    pages: Arc<PageCache>,
+    search: Arc<SearchIndex>,
+    suggestions: Arc<SuggestionTrie>,
+    crossrefs: Arc<CrossReferences>,
+    harmony: Arc<Harmony>,
+    plans: Arc<Vec<ReadingPlan>>,
+    accounts: Arc<AccountStore>,
+    quiz: Arc<QuizSessions>,
+    /// Heading sets in other languages, from the `headings` data folder.
+    translations: Arc<Vec<HeadingSet>>,
+    #[cfg(feature = "verses")]
+    verses: Option<Arc<verses::VerseText>>,
+}
+
+impl AppState {
+    /// Whether chapter cards offer verse text: needs the `verses` feature and `VERSE_TRANSLATION`.
+    fn verses_enabled(&self) -> bool {
+        #[cfg(feature = "verses")]
+        return self.verses.is_some();
+        #[cfg(not(feature = "verses"))]
+        return false;
+    }
 }
 
 fn create_placeholder_book(name: &str, testament: Testament, chapter_count: u32) -> Book {
@@ -40,7 +114,7 @@ fn create_placeholder_book(name: &str, testament: Testament, chapter_count: u32)
                 chapter_number: num,
                 sections: vec![
                     SectionHeading {
-                        heading: format!("Section headings to be added"),
+                        heading: PLACEHOLDER_HEADING.to_string(),
                     },
                 ],
             })
@@ -373,21 +447,101 @@ fn get_bible_data() -> Vec<Book> {
     ]
 }
 
-async fn index(data: web::Data<AppState>) -> Result<HttpResponse> {
-    let html = generate_html(&data.books);
-    Ok(HttpResponse::Ok()
+async fn index(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let locale = i18n::catalogs().negotiate(&req);
+    let set = translations::requested(&req, &data.translations);
+    let base = SetInfo::base();
+    let page = PageContext {
+        locale,
+        headings: set.map_or(&base, |set| &set.info),
+        heading_sets: &data.translations,
+        show_verses: data.verses_enabled(),
+        crossrefs: &data.crossrefs,
+        account: data
+            .accounts
+            .current_user(&req)
+            .and_then(|username| data.accounts.annotations(&username)),
+    };
+    let books = set.map_or(&data.books[..], |set| &set.books[..]);
+    // Signed-in pages carry personal headings and notes, so only others are cached.
+    let (rendered, cache_control) = if page.account.is_some() {
+        (Arc::new(cache::page(generate_html(books, &page))), cache::PRIVATE_CACHE_CONTROL)
+    } else {
+        let cached = data.pages.get_or_render(&data.data_version, locale.code(), &page.headings.id, || {
+            generate_html(books, &page)
+        });
+        (cached, cache::PAGE_CACHE_CONTROL)
+    };
+    let response = HttpResponse::Ok()
         .content_type("text/html; charset=utf-8")
-        .body(html))
+        .insert_header((header::VARY, "Accept-Language, Cookie"))
+        .body(rendered.html.clone());
+    let mut response = cache::validated(&req, response, &rendered.etag, Some(data.data_version.loaded), cache_control);
+    i18n::remember(&req, &locale, &mut response);
+    translations::remember(&req, set, &mut response);
+    Ok(response)
+}
+
+/// Per-request extras layered onto the rendered page.
+struct PageContext<'a> {
+    /// The negotiated language for UI strings and book names.
+    locale: Locale<'a>,
+    /// The language and direction of the headings shown.
+    headings: &'a SetInfo,
+    heading_sets: &'a [HeadingSet],
+    show_verses: bool,
+    crossrefs: &'a CrossReferences,
+    /// The signed-in user's bookmarks and notes, if any.
+    account: Option<Annotations>,
+}
+
+/// The element id of a chapter card, e.g. "1-john-3".
+fn chapter_anchor(book: &str, chapter: u32) -> String {
+    format!("{}-{}", book.to_lowercase().replace(' ', "-"), chapter)
 }
 
-async fn api_books(data: web::Data<AppState>) -> Result<HttpResponse> {
-    Ok(HttpResponse::Ok().json(&*data.books))
+fn see_also_html(books: &[Book], book: &Book, chapter: &Chapter, page: &PageContext) -> String {
+    let crossrefs = page.crossrefs;
+    let links = crossrefs.for_chapter(&book.name, chapter.chapter_number);
+    if links.is_empty() {
+        return String::new();
+    }
+
+    let mut html = format!(r#"
+                    <div class="see-also">
+                        <span class="see-also-label">{}</span>"#, page.locale.text("chapter.see_also"));
+    for link in links {
+        let target = link.target;
+        let rendered = books
+            .iter()
+            .find(|b| b.name == target.book)
+            .is_some_and(|b| b.chapters.iter().any(|c| c.chapter_number == target.chapter));
+        let reference = if rendered {
+            let anchor = chapter_anchor(&target.book, target.chapter);
+            format!(r##"<a class="see-also-link" href="#{0}" onclick="return revealChapter('{0}')">{1}</a>"##, anchor, target.label)
+        } else {
+            target.label.clone()
+        };
+        html.push_str(&format!(r#"
+                        <span class="see-also-item"><span class="see-also-relation">{}</span> {}</span>"#, link.relation.label(link.direction, &page.locale), reference));
+    }
+    html.push_str(r#"
+                    </div>"#);
+    html
 }
 
-fn generate_html(books: &[Book]) -> String {
+fn generate_html(books: &[Book], page: &PageContext) -> String {
+    let locale = &page.locale;
     let mut content = String::new();
     
     for book in books {
+        let display_name = locale.book_name(&book.name);
+        // The client-side filter matches both the English and the localized name.
+        let search_names = if display_name == book.name {
+            search::fold(&book.name)
+        } else {
+            format!("{} {}", search::fold(&book.name), search::fold(display_name))
+        };
         let testament_class = match book.testament {
             Testament::Old => "old-testament",
             Testament::New => "new-testament",
@@ -400,33 +554,68 @@ fn generate_html(books: &[Book]) -> String {
                     <span class="expand-icon">▼</span>
                 </div>
                 <div class="chapters-grid">
-        "#, testament_class, book.name.to_lowercase(), testament_class, book.name));
+        "#, testament_class, search_names, testament_class, display_name));
         
         for chapter in &book.chapters {
+            let custom = page.account.as_ref().map_or_else(Vec::new, |account| {
+                overlay::for_chapter(&account.headings, &book.name, chapter.chapter_number)
+            });
+            let sections = overlay::merge(chapter, &custom);
+            // Personal headings are user input; base headings are trusted data.
+            let heading_text = |section: &overlay::MergedHeading| {
+                if section.personal {
+                    layout::escape(&section.heading)
+                } else if section.heading == PLACEHOLDER_HEADING {
+                    locale.text("chapter.placeholder").to_string()
+                } else {
+                    section.heading.clone()
+                }
+            };
+
             let mut search_text = String::new();
-            for section in &chapter.sections {
-                search_text.push_str(&format!("{} ", section.heading.to_lowercase()));
+            for section in &sections {
+                search_text.push_str(&format!("{} ", search::fold(&heading_text(section))));
             }
             
             content.push_str(&format!(r#"
-                <div class="chapter-card" data-search-text="{}">
+                <div class="chapter-card" id="{}" data-search-text="{}">
                     <div class="chapter-header">
-                        <span class="chapter-number">Chapter {}</span>
+                        <span class="chapter-number">{}</span>
                     </div>
-                    <div class="sections">
-            "#, search_text, chapter.chapter_number));
+                    <div class="sections" lang="{}" dir="{}">
+            "#, chapter_anchor(&book.name, chapter.chapter_number), search_text,
+                locale.format("chapter.label", &[("number", &chapter.chapter_number.to_string())]),
+                page.headings.language, page.headings.direction.as_str()));
             
-            for section in &chapter.sections {
+            for section in &sections {
+                let mut tools = String::new();
+                if let Some(account) = &page.account {
+                    tools.push_str(&overlay::heading_tools_html(&book.name, chapter.chapter_number, section));
+                    if let Some(index) = section.section {
+                        tools.push_str(&account.section_html(&book.name, chapter.chapter_number, index));
+                    }
+                }
                 content.push_str(&format!(r#"
-                        <div class="section-heading">
+                        <div class="section-heading{}">
                             <span class="bullet">•</span>
-                            <span class="heading-text">{}</span>
+                            <span class="heading-text"{}>{}</span>{}
                         </div>
-                "#, section.heading));
+                "#, if section.personal { " personal" } else { "" },
+                    // A reader's own headings may be in any script.
+                    if section.personal { r#" dir="auto""# } else { "" },
+                    heading_text(section), tools));
             }
             
             content.push_str(r#"
-                    </div>
+                    </div>"#);
+            if page.account.is_some() {
+                content.push_str(&overlay::add_heading_html(&book.name, chapter.chapter_number));
+            }
+            content.push_str(&see_also_html(books, book, chapter, page));
+            if page.show_verses {
+                content.push_str(&verses::toggle_html(&book.name, chapter.chapter_number, locale));
+            }
+            content.push_str(r#"
                 </div>
             "#);
         }
@@ -438,11 +627,11 @@ fn generate_html(books: &[Book]) -> String {
     }
     
     format!(r#"<!DOCTYPE html>
-<html lang="en">
+<html lang="{lang}" dir="{dir}">
 <head>
     <meta charset="UTF-8">
     <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Bible Section Headlines</title>
+    <title>{title}</title>
     <link rel="preconnect" href="https://fonts.googleapis.com">
     <link rel="preconnect" href="https://fonts.gstatic.com" crossorigin>
     <link href="https://fonts.googleapis.com/css2?family=Cormorant+Garamond:wght@300;400;500;600;700&family=Inter:wght@300;400;500;600&display=swap" rel="stylesheet">
@@ -490,6 +679,41 @@ fn generate_html(books: &[Book]) -> String {
             margin-bottom: 1.5rem;
         }}
         
+        .page-links {{
+            display: flex;
+            justify-content: center;
+            gap: 1.5rem;
+        }}
+        
+        .page-links a {{
+            color: #ffffff;
+            font-weight: 500;
+            text-decoration: none;
+            border-bottom: 1px solid rgba(255,255,255,0.5);
+        }}
+        
+        .page-links a:hover {{
+            border-bottom-color: #ffffff;
+        }}
+        
+        .language-links {{
+            display: flex;
+            justify-content: center;
+            gap: 1rem;
+            margin-top: 0.75rem;
+            font-size: 0.85rem;
+        }}
+        
+        .language-links a {{
+            color: rgba(255,255,255,0.8);
+            text-decoration: none;
+        }}
+        
+        .language-links a[aria-current] {{
+            color: #ffffff;
+            font-weight: 600;
+        }}
+        
         .controls {{
             background: white;
             border-radius: 16px;
@@ -508,7 +732,8 @@ fn generate_html(books: &[Book]) -> String {
         
         .search-box {{
             width: 100%;
-            padding: 1rem 1rem 1rem 3rem;
+            padding-block: 1rem;
+            padding-inline: 3rem 1rem;
             border: 2px solid #e2e8f0;
             border-radius: 12px;
             font-size: 1rem;
@@ -526,13 +751,54 @@ fn generate_html(books: &[Book]) -> String {
         
         .search-icon {{
             position: absolute;
-            left: 1rem;
+            inset-inline-start: 1rem;
             top: 50%;
             transform: translateY(-50%);
             color: #667eea;
             font-size: 1.2rem;
         }}
         
+        .suggestions {{
+            position: absolute;
+            top: calc(100% + 0.35rem);
+            inset-inline: 0;
+            z-index: 10;
+            list-style: none;
+            background: white;
+            border: 1px solid #e2e8f0;
+            border-radius: 12px;
+            box-shadow: 0 10px 40px rgba(0,0,0,0.1);
+            max-height: 320px;
+            overflow-y: auto;
+            padding: 0.35rem;
+        }}
+        
+        .suggestions[hidden] {{
+            display: none;
+        }}
+        
+        .suggestion {{
+            display: flex;
+            justify-content: space-between;
+            align-items: baseline;
+            gap: 1rem;
+            padding: 0.6rem 0.75rem;
+            border-radius: 8px;
+            cursor: pointer;
+            color: #2d3748;
+        }}
+        
+        .suggestion:hover,
+        .suggestion[aria-selected="true"] {{
+            background-color: rgba(102, 126, 234, 0.1);
+        }}
+        
+        .suggestion-meta {{
+            font-size: 0.8rem;
+            color: #4a5568;
+            white-space: nowrap;
+        }}
+        
         .filter-buttons {{
             display: flex;
             gap: 0.75rem;
@@ -645,7 +911,7 @@ fn generate_html(books: &[Book]) -> String {
             content: '';
             position: absolute;
             top: 0;
-            left: 0;
+            inset-inline-start: 0;
             width: 4px;
             height: 100%;
             background: linear-gradient(180deg, #667eea 0%, #764ba2 100%);
@@ -711,6 +977,41 @@ fn generate_html(books: &[Book]) -> String {
             font-weight: 400;
         }}
         
+        .chapter-card.targeted {{
+            border-color: #667eea;
+            box-shadow: 0 0 0 3px rgba(102, 126, 234, 0.25);
+        }}
+        
+        .see-also {{
+            margin-top: 1rem;
+            padding-top: 0.75rem;
+            border-top: 1px dashed #e2e8f0;
+            display: flex;
+            flex-wrap: wrap;
+            gap: 0.4rem 0.9rem;
+            font-size: 0.85rem;
+        }}
+        
+        .see-also-label {{
+            font-weight: 600;
+            color: #4a5568;
+            width: 100%;
+        }}
+        
+        .see-also-relation {{
+            color: #4a5568;
+        }}
+        
+        .see-also-link {{
+            color: #667eea;
+            font-weight: 500;
+            text-decoration: none;
+        }}
+        
+        .see-also-link:hover {{
+            text-decoration: underline;
+        }}
+        
         .highlight {{
             background-color: #fef08a;
             color: #854d0e;
@@ -770,13 +1071,17 @@ fn generate_html(books: &[Book]) -> String {
                 align-items: center;
             }}
         }}
-    </style>
+{verse_styles}{account_styles}    </style>
 </head>
 <body>
     <div class="container">
         <header>
-            <h1>✦ Bible Section Headlines ✦</h1>
-            <p class="subtitle">Explore all 66 Books of Scripture</p>
+            <h1>✦ {title} ✦</h1>
+            <p class="subtitle">{subtitle}</p>
+            <nav class="page-links">
+                <a href="/harmony">{harmony}</a>
+                <a href="/quiz">{quiz}</a>{account_nav}
+            </nav>{language_links}{heading_set_links}
         </header>
         
         <div class="controls">
@@ -786,14 +1091,22 @@ fn generate_html(books: &[Book]) -> String {
                     type="text" 
                     class="search-box" 
                     id="searchBox" 
-                    placeholder="Search books, chapters, or section headings..."
-                    oninput="filterContent()"
+                    dir="auto"
+                    placeholder="{search_placeholder}"
+                    aria-label="{search_label}"
+                    role="combobox"
+                    aria-autocomplete="list"
+                    aria-expanded="false"
+                    aria-controls="suggestions"
+                    autocomplete="off"
+                    oninput="filterContent(); updateSuggestions()"
                 >
+                <ul class="suggestions" id="suggestions" role="listbox" aria-label="{suggestions_label}" hidden></ul>
             </div>
             <div class="filter-buttons">
-                <button class="filter-btn active" onclick="filterTestament('all')">All Books</button>
-                <button class="filter-btn" onclick="filterTestament('old')">Old Testament</button>
-                <button class="filter-btn" onclick="filterTestament('new')">New Testament</button>
+                <button class="filter-btn active" onclick="filterTestament('all')">{filter_all}</button>
+                <button class="filter-btn" onclick="filterTestament('old')">{filter_old}</button>
+                <button class="filter-btn" onclick="filterTestament('new')">{filter_new}</button>
             </div>
         </div>
         
@@ -804,11 +1117,17 @@ fn generate_html(books: &[Book]) -> String {
         </div>
         
         <footer>
-            <p>A reverent way to explore Scripture • Add detailed sections as you study</p>
+            <p>{footer}</p>
         </footer>
     </div>
     
     <script>
+        const MESSAGES = {script_messages};
+        
+        function message(key, values) {{
+            return MESSAGES[key].replace(/\{{(\w+)\}}/g, (_, name) => values[name]);
+        }}
+        
         let currentTestament = 'all';
         
         function toggleBook(header) {{
@@ -828,6 +1147,12 @@ fn generate_html(books: &[Book]) -> String {
             filterContent();
         }}
         
+        // Mirrors search::fold on the server: NFC without combining marks
+        // (accents, niqqud, harakat) or tatweel, lowercased.
+        function fold(text) {{
+            return text.normalize('NFD').replace(/[\p{{M}}\u0640]/gu, '').normalize('NFC').toLowerCase();
+        }}
+        
         function highlightText(element, searchTerm) {{
             if (!searchTerm || searchTerm.length < 2) {{
                 return;
@@ -835,11 +1160,39 @@ fn generate_html(books: &[Book]) -> String {
             
             const textElements = element.querySelectorAll('.heading-text, .book-title, .chapter-number');
             textElements.forEach(el => {{
+                // Fold character by character, remembering where each folded
+                // character came from, so matches map back onto the original text.
                 const originalText = el.textContent;
-                const regex = new RegExp(`(${{searchTerm.replace(/[.*+?^${{}}()|[\]\\]/g, '\\$&')}})`, 'gi');
-                const highlightedText = originalText.replace(regex, '<span class="highlight">$1</span>');
-                if (originalText !== highlightedText) {{
-                    el.innerHTML = highlightedText;
+                let folded = '';
+                const origins = [];
+                let offset = 0;
+                for (const character of originalText) {{
+                    const part = fold(character);
+                    for (let i = 0; i < part.length; i++) {{
+                        origins.push(offset);
+                    }}
+                    folded += part;
+                    offset += character.length;
+                }}
+                origins.push(offset);
+                
+                const pieces = [];
+                let last = 0;
+                let match = folded.indexOf(searchTerm);
+                while (match !== -1) {{
+                    const start = origins[match];
+                    const end = origins[match + searchTerm.length];
+                    pieces.push(document.createTextNode(originalText.slice(last, start)));
+                    const highlight = document.createElement('span');
+                    highlight.className = 'highlight';
+                    highlight.textContent = originalText.slice(start, end);
+                    pieces.push(highlight);
+                    last = end;
+                    match = folded.indexOf(searchTerm, match + searchTerm.length);
+                }}
+                if (pieces.length > 0) {{
+                    pieces.push(document.createTextNode(originalText.slice(last)));
+                    el.replaceChildren(...pieces);
                 }}
             }});
         }}
@@ -852,7 +1205,7 @@ fn generate_html(books: &[Book]) -> String {
         }}
         
         function filterContent() {{
-            const searchTerm = document.getElementById('searchBox').value.toLowerCase();
+            const searchTerm = fold(document.getElementById('searchBox').value);
             const bookCards = document.querySelectorAll('.book-card');
             let visibleBooks = 0;
             let visibleChapters = 0;
@@ -904,9 +1257,140 @@ fn generate_html(books: &[Book]) -> String {
             // Update stats
             const stats = document.getElementById('stats');
             if (searchTerm === '' && currentTestament === 'all') {{
-                stats.textContent = `Showing all 66 books`;
+                stats.textContent = MESSAGES['stats.all'];
             }} else {{
-                stats.textContent = `Found ${{visibleBooks}} book${{visibleBooks !== 1 ? 's' : ''}} with ${{visibleChapters}} chapter${{visibleChapters !== 1 ? 's' : ''}}`;
+                stats.textContent = message('stats.found', {{
+                    books: message(visibleBooks === 1 ? 'stats.books.one' : 'stats.books.other', {{ count: visibleBooks }}),
+                    chapters: message(visibleChapters === 1 ? 'stats.chapters.one' : 'stats.chapters.other', {{ count: visibleChapters }})
+                }});
+            }}
+        }}
+        
+        function revealChapter(anchor) {{
+            const chapter = document.getElementById(anchor);
+            if (!chapter) {{
+                return true;
+            }}
+            
+            // Clear filters that would keep the target hidden
+            if (chapter.classList.contains('hidden') || chapter.closest('.book-card').classList.contains('hidden')) {{
+                document.getElementById('searchBox').value = '';
+                currentTestament = 'all';
+                document.querySelectorAll('.filter-btn').forEach((btn, index) => {{
+                    btn.classList.toggle('active', index === 0);
+                }});
+                filterContent();
+            }}
+            
+            chapter.closest('.book-card').classList.remove('collapsed');
+            document.querySelectorAll('.chapter-card.targeted').forEach(card => card.classList.remove('targeted'));
+            chapter.classList.add('targeted');
+            chapter.scrollIntoView({{ behavior: 'smooth', block: 'center' }});
+            history.replaceState(null, '', '#' + anchor);
+            return false;
+        }}
+        
+        let suggestionTimer = null;
+        let activeSuggestion = -1;
+        
+        function updateSuggestions() {{
+            clearTimeout(suggestionTimer);
+            const prefix = document.getElementById('searchBox').value;
+            if (prefix.trim() === '') {{
+                closeSuggestions();
+                return;
+            }}
+            suggestionTimer = setTimeout(() => {{
+                fetch('/api/suggest?prefix=' + encodeURIComponent(prefix))
+                    .then(response => response.ok ? response.json() : [])
+                    .then(renderSuggestions)
+                    .catch(() => closeSuggestions());
+            }}, 120);
+        }}
+        
+        function renderSuggestions(suggestions) {{
+            const list = document.getElementById('suggestions');
+            const searchBox = document.getElementById('searchBox');
+            list.innerHTML = '';
+            activeSuggestion = -1;
+            
+            suggestions.forEach((suggestion, index) => {{
+                const option = document.createElement('li');
+                option.id = 'suggestion-' + index;
+                option.className = 'suggestion';
+                option.setAttribute('role', 'option');
+                option.setAttribute('aria-selected', 'false');
+                option.dataset.value = suggestion.kind === 'heading' ? suggestion.text : suggestion.book;
+                
+                const text = document.createElement('span');
+                text.textContent = suggestion.text;
+                text.dir = 'auto';
+                const meta = document.createElement('span');
+                meta.className = 'suggestion-meta';
+                meta.textContent = suggestion.kind === 'heading'
+                    ? `${{suggestion.book}} ${{suggestion.chapter}}`
+                    : suggestion.kind === 'abbreviation' ? suggestion.book : 'Book';
+                option.append(text, meta);
+                
+                // mousedown fires before the search box loses focus
+                option.addEventListener('mousedown', event => {{
+                    event.preventDefault();
+                    chooseSuggestion(index);
+                }});
+                list.appendChild(option);
+            }});
+            
+            list.hidden = suggestions.length === 0;
+            searchBox.setAttribute('aria-expanded', suggestions.length > 0 ? 'true' : 'false');
+            searchBox.removeAttribute('aria-activedescendant');
+        }}
+        
+        function closeSuggestions() {{
+            clearTimeout(suggestionTimer);
+            const searchBox = document.getElementById('searchBox');
+            document.getElementById('suggestions').hidden = true;
+            searchBox.setAttribute('aria-expanded', 'false');
+            searchBox.removeAttribute('aria-activedescendant');
+            activeSuggestion = -1;
+        }}
+        
+        function highlightSuggestion(index) {{
+            const options = document.querySelectorAll('#suggestions .suggestion');
+            if (options.length === 0) {{
+                return;
+            }}
+            activeSuggestion = (index + options.length) % options.length;
+            options.forEach((option, i) => {{
+                option.setAttribute('aria-selected', i === activeSuggestion ? 'true' : 'false');
+            }});
+            options[activeSuggestion].scrollIntoView({{ block: 'nearest' }});
+            document.getElementById('searchBox')
+                .setAttribute('aria-activedescendant', options[activeSuggestion].id);
+        }}
+        
+        function chooseSuggestion(index) {{
+            const option = document.getElementById('suggestion-' + index);
+            if (!option) {{
+                return;
+            }}
+            document.getElementById('searchBox').value = option.dataset.value;
+            closeSuggestions();
+            filterContent();
+        }}
+        
+        function handleSearchKeys(event) {{
+            const listOpen = !document.getElementById('suggestions').hidden;
+            if (event.key === 'ArrowDown') {{
+                event.preventDefault();
+                listOpen ? highlightSuggestion(activeSuggestion + 1) : updateSuggestions();
+            }} else if (event.key === 'ArrowUp' && listOpen) {{
+                event.preventDefault();
+                highlightSuggestion(activeSuggestion - 1);
+            }} else if (event.key === 'Enter' && listOpen && activeSuggestion >= 0) {{
+                event.preventDefault();
+                chooseSuggestion(activeSuggestion);
+            }} else if (event.key === 'Escape' && listOpen) {{
+                closeSuggestions();
             }}
         }}
         
@@ -915,33 +1399,177 @@ fn generate_html(books: &[Book]) -> String {
             document.querySelectorAll('.book-card').forEach(card => {{
                 card.classList.add('collapsed');
             }});
+            const searchBox = document.getElementById('searchBox');
+            searchBox.addEventListener('keydown', handleSearchKeys);
+            searchBox.addEventListener('blur', closeSuggestions);
             filterContent();
+            if (location.hash.length > 1) {{
+                revealChapter(decodeURIComponent(location.hash.slice(1)));
+            }}
         }});
-    </script>
+{verse_script}{account_script}    </script>
 </body>
-</html>"#, content)
+</html>"#,
+        content,
+        verse_styles = if page.show_verses { verses::STYLES } else { "" },
+        verse_script = if page.show_verses { verses::SCRIPT } else { "" },
+        lang = locale.code(),
+        dir = locale.direction(),
+        heading_set_links = translations::set_links_html(page.heading_sets, page.headings),
+        title = locale.text("page.title"),
+        subtitle = locale.text("page.subtitle"),
+        harmony = locale.text("nav.harmony"),
+        quiz = locale.text("nav.quiz"),
+        language_links = i18n::language_links_html(locale),
+        search_placeholder = locale.text("search.placeholder"),
+        search_label = locale.text("search.label"),
+        suggestions_label = locale.text("search.suggestions"),
+        filter_all = locale.text("filter.all"),
+        filter_old = locale.text("filter.old"),
+        filter_new = locale.text("filter.new"),
+        footer = locale.text("page.footer"),
+        script_messages = locale.script_messages(),
+        account_nav = accounts::nav_html(page.account.as_ref(), locale),
+        account_styles = if page.account.is_some() { [accounts::STYLES, overlay::STYLES].concat() } else { String::new() },
+        account_script = if page.account.is_some() { [accounts::SCRIPT, overlay::SCRIPT].concat() } else { String::new() },
+    )
 }
 
 #[actix_web::main]
 async fn main() -> std::io::Result<()> {
-    let bible_data = get_bible_data();
+    // Commands go to the CLI; `--` flags configure the server.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().is_some_and(|arg| !arg.starts_with("--") || arg == "--help") {
+        return cli::run(&args);
+    }
+    let config = match Config::load(&args) {
+        Ok(config) => config,
+        Err(err) => return cli::usage_error(&err),
+    };
+    if args.iter().any(|arg| arg == "--print-config") {
+        print!("{}", config.to_toml());
+        return Ok(());
+    }
+    if let Ok(level) = config.log_level() {
+        env_logger::Builder::new().filter_level(level).init();
+    }
+    #[cfg(not(feature = "verses"))]
+    if config.translation.is_some() {
+        log::warn!("Verse translation ignored: built without the verses feature");
+    }
+    let tls = config.tls_config()?;
+
+    i18n::load_catalogs(&config.data_path(&config::LOCALES));
+    let bible_data = config.canon.apply(get_bible_data());
+    let analyzer = Analyzer::load(&config.data_path(&config::SYNONYMS));
+    let heading_sets =
+        HeadingSet::load_all(&bible_data, &config.data_path(&config::HEADINGS), &analyzer);
+    let search_index = SearchIndex::build(&bible_data, analyzer);
+    let suggestions = SuggestionTrie::build(&bible_data);
+    let crossrefs = CrossReferences::load(
+        &bible_data,
+        &config.data_path(&config::CROSSREFS),
+        &config.data_path(&config::TSK),
+    );
+    let harmony = Harmony::load(&bible_data, &config.data_path(&config::HARMONY));
+    let plans = ReadingPlan::build_all(&bible_data);
+    let accounts = AccountStore::load(&config.data_dir)?;
+    let data_version = DataVersion::compute(&bible_data, &heading_sets);
+    let books_body = Precomputed::new(&bible_data);
     let app_state = web::Data::new(AppState {
         books: Arc::new(bible_data),
+        books_body: Arc::new(books_body),
+        data_version: Arc::new(data_version),
+        pages: Arc::new(PageCache::new()),
+        search: Arc::new(search_index),
+        suggestions: Arc::new(suggestions),
+        crossrefs: Arc::new(crossrefs),
+        harmony: Arc::new(harmony),
+        plans: Arc::new(plans),
+        accounts: Arc::new(accounts),
+        quiz: Arc::new(QuizSessions::new()),
+        translations: Arc::new(heading_sets),
+        #[cfg(feature = "verses")]
+        verses: verses::VerseText::load(
+            config.translation.as_deref(),
+            &config.data_path(&config::VERSES),
+        )
+        .map(Arc::new),
     });
+    let graphql_schema = web::Data::new(graphql::build(app_state.get_ref().clone()));
 
-    let port = std::env::var("PORT").unwrap_or_else(|_| "8080".to_string());
-    let bind_address = format!("0.0.0.0:{}", port);
-
-    println!("🚀 Server starting at http://{}", bind_address);
-    println!("📖 View Bible Section Headlines");
+    let scheme = if tls.is_some() { "https" } else { "http" };
+    log::info!("Server starting at {}://{}:{}", scheme, config.bind, config.port);
     
-    HttpServer::new(move || {
-        App::new()
+    let server = HttpServer::new(move || {
+        let app = App::new()
             .app_data(app_state.clone())
+            .app_data(graphql_schema.clone())
+            .wrap(middleware::Compress::default())
+            .wrap(middleware::Logger::default())
             .route("/", web::get().to(index))
-            .route("/api/books", web::get().to(api_books))
-    })
-    .bind(&bind_address)?
+            .route("/api/books", web::get().to(overlay::api_books))
+            .route("/api/v1/books", web::get().to(v1::api_books))
+            .route("/api/v1/books/{id}", web::get().to(v1::api_book))
+            .route("/api/v1/chapters/{id}", web::get().to(v1::api_chapter))
+            .route("/api/v1/search", web::get().to(v1::api_search))
+            .route("/api/v1/schemas", web::get().to(v1::api_schemas))
+            .route("/api/v1/schemas/{name}", web::get().to(v1::api_schema))
+            .route("/api/openapi.json", web::get().to(openapi::api_openapi))
+            .route("/api/docs", web::get().to(openapi::docs_page))
+            .route("/graphql", web::get().to(graphql::playground))
+            .route("/graphql", web::post().to(graphql::api_graphql))
+            .route("/api/languages", web::get().to(i18n::api_languages))
+            .route("/api/translations", web::get().to(translations::api_translations))
+            .route("/api/search", web::get().to(search::api_search))
+            .route("/api/query", web::get().to(query::api_query))
+            .route("/api/suggest", web::get().to(suggest::api_suggest))
+            .route("/api/crossrefs", web::get().to(crossref::api_crossrefs))
+            .route("/harmony", web::get().to(harmony::harmony_page))
+            .route("/api/harmony", web::get().to(harmony::api_harmony))
+            .route("/api/export/{format}", web::get().to(export::api_export))
+            .route("/api/flashcards/{format}", web::get().to(flashcards::api_flashcards))
+            .route("/quiz", web::get().to(quiz::quiz_page))
+            .route("/api/quiz/question", web::get().to(quiz::api_question))
+            .route("/api/quiz/answer", web::post().to(quiz::api_answer))
+            .route("/api/quiz/score", web::get().to(quiz::api_score))
+            .route("/api/quiz/reset", web::post().to(quiz::api_reset))
+            .route("/api/plans", web::get().to(plans::api_plans))
+            .route("/api/plans/{id}", web::get().to(plans::api_plan))
+            .route("/api/plans/{id}/day/{day}", web::get().to(plans::api_plan_day))
+            .route("/api/plans/{id}/calendar.ics", web::get().to(plans::api_plan_ics))
+            .route("/login", web::get().to(accounts::login_page))
+            .route("/api/account", web::get().to(accounts::api_account))
+            .route("/api/account/register", web::post().to(accounts::api_register))
+            .route("/api/account/login", web::post().to(accounts::api_login))
+            .route("/api/account/logout", web::post().to(accounts::api_logout))
+            .route("/api/bookmarks", web::get().to(accounts::api_bookmarks))
+            .route("/api/bookmarks", web::post().to(accounts::api_add_bookmark))
+            .route("/api/bookmarks/{id}", web::delete().to(accounts::api_delete_bookmark))
+            .route("/api/notes", web::get().to(accounts::api_notes))
+            .route("/api/notes", web::post().to(accounts::api_add_note))
+            .route("/api/notes/{id}", web::put().to(accounts::api_update_note))
+            .route("/api/notes/{id}", web::delete().to(accounts::api_delete_note))
+            .route("/api/headings", web::get().to(overlay::api_headings))
+            .route("/api/headings", web::post().to(overlay::api_add_heading))
+            .route("/api/headings/{id}", web::put().to(overlay::api_update_heading))
+            .route("/api/headings/{id}", web::delete().to(overlay::api_delete_heading));
+        #[cfg(feature = "verses")]
+        let app = app.route(
+            "/api/verses/{book}/{chapter}",
+            web::get().to(verses::api_chapter_verses),
+        );
+        app
+    });
+    let server = match config.workers {
+        Some(workers) => server.workers(workers),
+        None => server,
+    };
+    let address = (config.bind.as_str(), config.port);
+    match tls {
+        Some(tls) => server.bind_rustls_0_23(address, tls)?,
+        None => server.bind(address)?,
+    }
     .run()
     .await
 }
@@ -0,0 +1,253 @@
+//! Content negotiation for API responses: JSON, CBOR or MessagePack chosen
+//! from the `Accept` header, and bodies serialized and compressed once at
+//! startup for data that never changes.
+
+use std::io::Write;
+
+use actix_web::http::header::{self, HeaderValue};
+use actix_web::web::Bytes;
+use actix_web::{HttpRequest, HttpResponse};
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use serde::Serialize;
+use serde_json::json;
+
+use crate::cache::{self, DataVersion};
+
+/// Brotli settings for precomputed bodies: best compression, since it is paid once.
+const BROTLI_QUALITY: u32 = 11;
+const BROTLI_WINDOW: u32 = 22;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Encoding {
+    Json,
+    Cbor,
+    MessagePack,
+}
+
+impl Encoding {
+    const ALL: [Encoding; 3] = [Encoding::Json, Encoding::Cbor, Encoding::MessagePack];
+
+    fn content_type(self) -> &'static str {
+        match self {
+            Encoding::Json => "application/json",
+            Encoding::Cbor => "application/cbor",
+            Encoding::MessagePack => "application/msgpack",
+        }
+    }
+
+    fn from_media_type(media_type: &str) -> Option<Encoding> {
+        match media_type {
+            // JSON is the default for wildcards and clients that do not say.
+            "application/json" | "application/*" | "*/*" => Some(Encoding::Json),
+            "application/cbor" => Some(Encoding::Cbor),
+            "application/msgpack" | "application/x-msgpack" | "application/vnd.msgpack" => {
+                Some(Encoding::MessagePack)
+            }
+            _ => None,
+        }
+    }
+
+    /// The best format the request's `Accept` header allows, JSON when it has
+    /// none, or `None` when it accepts none of them.
+    pub fn negotiate(req: &HttpRequest) -> Option<Encoding> {
+        let accept = req
+            .headers()
+            .get(header::ACCEPT)
+            .and_then(|v| v.to_str().ok())
+            .unwrap_or("")
+            .trim();
+        if accept.is_empty() {
+            return Some(Encoding::Json);
+        }
+        preferences(accept)
+            .into_iter()
+            .find_map(|media_type| Encoding::from_media_type(&media_type))
+    }
+
+    pub fn encode<T: Serialize + ?Sized>(self, value: &T) -> Vec<u8> {
+        match self {
+            // Serializing the API's own types cannot fail.
+            Encoding::Json => serde_json::to_vec(value).expect("serializable value"),
+            Encoding::Cbor => {
+                let mut body = Vec::new();
+                ciborium::into_writer(value, &mut body).expect("serializable value");
+                body
+            }
+            // Structs become maps, so field names survive as in JSON.
+            Encoding::MessagePack => rmp_serde::to_vec_named(value).expect("serializable value"),
+        }
+    }
+}
+
+/// The values of an `Accept`-style header with their weights, most preferred
+/// first, leaving out those refused with `q=0`.
+fn weighted(header: &str) -> Vec<(String, f32)> {
+    let mut values: Vec<(String, f32)> = header
+        .split(',')
+        .filter_map(|item| {
+            let mut parts = item.split(';');
+            let value = parts.next()?.trim().to_ascii_lowercase();
+            let quality = parts
+                .find_map(|p| p.trim().strip_prefix("q="))
+                .map_or(Some(1.0), |q| q.trim().parse().ok())?;
+            (!value.is_empty() && quality > 0.0).then_some((value, quality))
+        })
+        .collect();
+    // A stable sort keeps the header's order among equal weights.
+    values.sort_by(|a, b| b.1.total_cmp(&a.1));
+    values
+}
+
+/// The values of an `Accept`-style header, most preferred first.
+fn preferences(header: &str) -> Vec<String> {
+    weighted(header)
+        .into_iter()
+        .map(|(value, _)| value)
+        .collect()
+}
+
+fn not_acceptable() -> HttpResponse {
+    HttpResponse::NotAcceptable().json(json!({
+        "error": "Supported formats are application/json, application/cbor and application/msgpack"
+    }))
+}
+
+/// Serializes `value` in the negotiated format, cacheable like the data it
+/// comes from. The compression middleware takes care of `Accept-Encoding`.
+pub fn respond<T: Serialize + ?Sized>(req: &HttpRequest, value: &T) -> HttpResponse {
+    respond_with(req, value, cache::API_CACHE_CONTROL)
+}
+
+/// Serializes `value` in the negotiated format with the given
+/// `Cache-Control`, validated by a tag over the body.
+pub fn respond_with<T: Serialize + ?Sized>(
+    req: &HttpRequest,
+    value: &T,
+    cache_control: &'static str,
+) -> HttpResponse {
+    let Some(encoding) = Encoding::negotiate(req) else {
+        return not_acceptable();
+    };
+    let body = encoding.encode(value);
+    let etag = cache::weak_etag(&body);
+    let response = HttpResponse::Ok()
+        .content_type(encoding.content_type())
+        .insert_header((header::VARY, cache::API_VARY))
+        .body(body);
+    cache::validated(req, response, &etag, None, cache_control)
+}
+
+/// One format's body, uncompressed and compressed ahead of time.
+struct Variants {
+    encoding: Encoding,
+    /// Entity tag of the uncompressed body; compressed ones add a suffix.
+    etag: String,
+    identity: Bytes,
+    gzip: Bytes,
+    brotli: Bytes,
+}
+
+/// A response body serialized in every format and compressed with gzip and
+/// Brotli once, for data fixed at startup such as the book list.
+pub struct Precomputed {
+    variants: Vec<Variants>,
+}
+
+impl Precomputed {
+    pub fn new<T: Serialize + ?Sized>(value: &T) -> Self {
+        let variants = Encoding::ALL
+            .iter()
+            .map(|&encoding| {
+                let identity = encoding.encode(value);
+                Variants {
+                    encoding,
+                    etag: cache::etag(&identity),
+                    gzip: gzip(&identity).into(),
+                    brotli: brotli(&identity).into(),
+                    identity: identity.into(),
+                }
+            })
+            .collect();
+        Precomputed { variants }
+    }
+
+    /// The negotiated variant, validated against the client's cached copy.
+    pub fn respond(&self, req: &HttpRequest, version: &DataVersion) -> HttpResponse {
+        let Some(encoding) = Encoding::negotiate(req) else {
+            return not_acceptable();
+        };
+        let Some(variants) = self.variants.iter().find(|v| v.encoding == encoding) else {
+            return not_acceptable();
+        };
+
+        let accepted = req
+            .headers()
+            .get(header::ACCEPT_ENCODING)
+            .and_then(|v| v.to_str().ok())
+            .map(weighted)
+            .unwrap_or_default();
+        let weight = |names: &[&str]| {
+            accepted
+                .iter()
+                .find(|(coding, _)| names.contains(&coding.as_str()))
+                .map(|(_, quality)| *quality)
+        };
+        // Brotli wins ties: browsers list gzip first but both equally weighted.
+        let coding = match (weight(&["br", "*"]), weight(&["gzip", "x-gzip", "*"])) {
+            (Some(br), gzip) if gzip.is_none_or(|gzip| br >= gzip) => {
+                Some(("br", &variants.brotli))
+            }
+            (_, Some(_)) => Some(("gzip", &variants.gzip)),
+            _ => None,
+        };
+
+        let mut response = HttpResponse::Ok();
+        response
+            .content_type(encoding.content_type())
+            .insert_header((header::VARY, cache::API_VARY));
+        let (response, etag) = match coding {
+            Some((name, body)) => (
+                response
+                    .insert_header((header::CONTENT_ENCODING, HeaderValue::from_static(name)))
+                    .body(body.clone()),
+                // Each encoding is a different representation, with its own tag.
+                format!("{}-{}\"", variants.etag.trim_end_matches('"'), name),
+            ),
+            // Marked as identity so the compression middleware leaves it alone.
+            None => (
+                response
+                    .insert_header((
+                        header::CONTENT_ENCODING,
+                        HeaderValue::from_static("identity"),
+                    ))
+                    .body(variants.identity.clone()),
+                variants.etag.clone(),
+            ),
+        };
+        cache::validated(
+            req,
+            response,
+            &etag,
+            Some(version.loaded),
+            cache::API_CACHE_CONTROL,
+        )
+    }
+}
+
+fn gzip(body: &[u8]) -> Vec<u8> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::best());
+    // Writing to a Vec cannot fail.
+    encoder.write_all(body).expect("in-memory write");
+    encoder.finish().expect("in-memory write")
+}
+
+fn brotli(body: &[u8]) -> Vec<u8> {
+    let mut compressed = Vec::new();
+    {
+        let mut writer =
+            brotli::CompressorWriter::new(&mut compressed, 4096, BROTLI_QUALITY, BROTLI_WINDOW);
+        writer.write_all(body).expect("in-memory write");
+    }
+    compressed
+}
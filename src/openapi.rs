@@ -0,0 +1,609 @@
+//! The OpenAPI 3 description of the JSON API, generated from the handlers'
+//! `#[utoipa::path]` attributes and the types they serialize, and a docs page
+//! that renders it without any external scripts.
+
+use std::sync::OnceLock;
+
+use actix_web::{HttpResponse, Result};
+use serde::Serialize;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::{Modify, OpenApi, ToSchema};
+
+use crate::accounts::{Bookmark, Note};
+use crate::layout;
+use crate::overlay::CustomHeading;
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Bible Section Headlines API",
+        description = "Section headings for every book and chapter of the Bible, with search, \
+            cross-references, study tools and per-user annotations. Responses are JSON unless \
+            noted; `/api/books`, `/api/search`, `/api/query` and `/api/suggest` also speak CBOR \
+            and MessagePack through `Accept`."
+    ),
+    paths(
+        crate::overlay::api_books,
+        crate::i18n::api_languages,
+        crate::translations::api_translations,
+        crate::search::api_search,
+        crate::query::api_query,
+        crate::suggest::api_suggest,
+        crate::crossref::api_crossrefs,
+        crate::harmony::api_harmony,
+        crate::export::api_export,
+        crate::flashcards::api_flashcards,
+        crate::quiz::api_question,
+        crate::quiz::api_answer,
+        crate::quiz::api_score,
+        crate::quiz::api_reset,
+        crate::plans::api_plans,
+        crate::plans::api_plan,
+        crate::plans::api_plan_day,
+        crate::plans::api_plan_ics,
+        crate::accounts::api_register,
+        crate::accounts::api_login,
+        crate::accounts::api_logout,
+        crate::accounts::api_account,
+        crate::accounts::api_bookmarks,
+        crate::accounts::api_add_bookmark,
+        crate::accounts::api_delete_bookmark,
+        crate::accounts::api_notes,
+        crate::accounts::api_add_note,
+        crate::accounts::api_update_note,
+        crate::accounts::api_delete_note,
+        crate::overlay::api_headings,
+        crate::overlay::api_add_heading,
+        crate::overlay::api_update_heading,
+        crate::overlay::api_delete_heading,
+        crate::v1::api_books,
+        crate::v1::api_book,
+        crate::v1::api_chapter,
+        crate::v1::api_search,
+        crate::v1::api_schemas,
+        crate::v1::api_schema,
+    ),
+    modifiers(&SessionCookie),
+    tags(
+        (name = "books", description = "The headings themselves, and the languages and heading sets they come in"),
+        (name = "search", description = "Ranked search, boolean queries and completions"),
+        (name = "references", description = "Cross-references, the Gospel harmony and verse text"),
+        (name = "study", description = "Outline exports, flashcards and the heading quiz"),
+        (name = "plans", description = "Reading plans"),
+        (name = "account", description = "Accounts, bookmarks, notes and personal headings"),
+        (name = "v1", description = "The versioned API: stable OSIS ids, snake_case values and published JSON Schemas"),
+    )
+)]
+struct ApiDoc;
+
+#[cfg(feature = "verses")]
+#[derive(OpenApi)]
+#[openapi(paths(crate::verses::api_chapter_verses))]
+struct VersesDoc;
+
+/// Registers the `session` cookie set by sign-in as the API's security scheme.
+struct SessionCookie;
+
+impl Modify for SessionCookie {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "session",
+            SecurityScheme::ApiKey(ApiKey::Cookie(ApiKeyValue::with_description(
+                "session",
+                "Set by `/api/account/register` and `/api/account/login`",
+            ))),
+        );
+    }
+}
+
+// Bodies the handlers build with `json!`, described here for the document.
+
+/// The body of every error response.
+#[derive(Serialize, ToSchema)]
+pub struct ErrorBody {
+    error: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct Username {
+    username: String,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct BookmarkList {
+    total: usize,
+    bookmarks: Vec<Bookmark>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct NoteList {
+    total: usize,
+    notes: Vec<Note>,
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct HeadingList {
+    total: usize,
+    headings: Vec<CustomHeading>,
+}
+
+/// The document as JSON, built on first use.
+fn document() -> &'static str {
+    static DOCUMENT: OnceLock<String> = OnceLock::new();
+    DOCUMENT.get_or_init(|| {
+        #[allow(unused_mut)]
+        let mut openapi = ApiDoc::openapi();
+        #[cfg(feature = "verses")]
+        openapi.merge(VersesDoc::openapi());
+        openapi.to_pretty_json().expect("serializable document")
+    })
+}
+
+pub async fn api_openapi() -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok()
+        .content_type("application/json")
+        .body(document()))
+}
+
+pub async fn docs_page() -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(layout::offline_page(
+            "API Documentation",
+            STYLES,
+            BODY,
+            SCRIPT,
+        )))
+}
+
+const BODY: &str = r#"
+        <section class="card">
+            <p class="docs-intro" id="docs-intro">Loading the API description…</p>
+            <p class="docs-links">
+                <a href="/api/openapi.json">openapi.json</a>
+                <span id="docs-version"></span>
+            </p>
+            <nav class="docs-toc" id="docs-toc" aria-label="Sections"></nav>
+        </section>
+        <div id="docs-operations"></div>
+        <section class="card" id="schemas">
+            <h2>Schemas</h2>
+            <div id="docs-schemas"></div>
+        </section>"#;
+
+const STYLES: &str = r#"
+        .docs-intro {
+            color: #4a5568;
+            line-height: 1.6;
+        }
+
+        .docs-links {
+            margin-top: 0.75rem;
+            font-size: 0.9rem;
+            color: #718096;
+        }
+
+        .docs-links a,
+        .docs-toc a,
+        .schema-link {
+            color: #667eea;
+        }
+
+        .docs-toc {
+            display: flex;
+            flex-wrap: wrap;
+            gap: 0.5rem 1.25rem;
+            margin-top: 1rem;
+        }
+
+        .tag-description {
+            color: #718096;
+            margin-bottom: 1rem;
+        }
+
+        .operation {
+            border: 1px solid #e2e8f0;
+            border-radius: 10px;
+            margin-bottom: 0.75rem;
+        }
+
+        .operation summary {
+            display: flex;
+            align-items: baseline;
+            gap: 0.75rem;
+            padding: 0.75rem 1rem;
+            cursor: pointer;
+        }
+
+        .method {
+            flex: none;
+            min-width: 4.5rem;
+            padding: 0.15rem 0.5rem;
+            border-radius: 6px;
+            color: white;
+            font-size: 0.8rem;
+            font-weight: 600;
+            text-align: center;
+            text-transform: uppercase;
+        }
+
+        .method-get { background: #38a169; }
+        .method-post { background: #3182ce; }
+        .method-put { background: #d69e2e; }
+        .method-delete { background: #e53e3e; }
+
+        .operation-path {
+            font-family: ui-monospace, monospace;
+            font-weight: 600;
+            overflow-wrap: anywhere;
+        }
+
+        .operation-summary {
+            color: #718096;
+            font-size: 0.9rem;
+        }
+
+        .operation-body {
+            padding: 0 1rem 1rem;
+        }
+
+        .operation-body h3 {
+            font-size: 0.8rem;
+            text-transform: uppercase;
+            letter-spacing: 0.05em;
+            color: #a0aec0;
+            margin: 1rem 0 0.5rem;
+        }
+
+        .docs-table {
+            width: 100%;
+            border-collapse: collapse;
+            font-size: 0.9rem;
+        }
+
+        .docs-table th,
+        .docs-table td {
+            text-align: start;
+            vertical-align: top;
+            padding: 0.4rem 0.6rem;
+            border-bottom: 1px solid #edf2f7;
+        }
+
+        .docs-table code,
+        .schema-type {
+            font-family: ui-monospace, monospace;
+            font-size: 0.85rem;
+        }
+
+        .required {
+            color: #e53e3e;
+            font-size: 0.75rem;
+        }
+
+        .try-it {
+            display: flex;
+            flex-wrap: wrap;
+            gap: 0.5rem;
+            align-items: flex-end;
+        }
+
+        .try-it label {
+            display: flex;
+            flex-direction: column;
+            gap: 0.2rem;
+            font-size: 0.8rem;
+            color: #4a5568;
+        }
+
+        .try-it input,
+        .try-it select {
+            padding: 0.4rem 0.6rem;
+            border: 2px solid #e2e8f0;
+            border-radius: 6px;
+            font: inherit;
+        }
+
+        .try-it button {
+            padding: 0.45rem 1rem;
+            border: none;
+            border-radius: 6px;
+            background: #667eea;
+            color: white;
+            font: inherit;
+            cursor: pointer;
+        }
+
+        .try-output {
+            margin-top: 0.75rem;
+            max-height: 24rem;
+            overflow: auto;
+            padding: 0.75rem;
+            border-radius: 8px;
+            background: #1a202c;
+            color: #e2e8f0;
+            font-size: 0.8rem;
+            white-space: pre-wrap;
+        }
+
+        .schema {
+            margin-bottom: 1.5rem;
+        }
+
+        .schema h3 {
+            font-family: ui-monospace, monospace;
+            margin-bottom: 0.25rem;
+        }
+
+        .schema p {
+            color: #718096;
+            font-size: 0.9rem;
+            margin-bottom: 0.5rem;
+        }
+"#;
+
+const SCRIPT: &str = r#"    <script>
+        let spec = null;
+
+        function element(tag, className, text) {
+            const node = document.createElement(tag);
+            if (className) node.className = className;
+            if (text !== undefined) node.textContent = text;
+            return node;
+        }
+
+        function refName(ref) {
+            return ref.split('/').pop();
+        }
+
+        function schemaLink(name) {
+            const link = element('a', 'schema-link', name);
+            link.href = '#schema-' + name;
+            return link;
+        }
+
+        // A compact, TypeScript-like description of a schema, with links to named schemas.
+        function typeNode(schema) {
+            const span = element('span', 'schema-type');
+            if (!schema) {
+                span.textContent = 'any';
+            } else if (schema.$ref) {
+                span.appendChild(schemaLink(refName(schema.$ref)));
+            } else if (schema.enum) {
+                span.textContent = schema.enum.map(value => JSON.stringify(value)).join(' | ');
+            } else if (schema.oneOf || schema.anyOf) {
+                (schema.oneOf || schema.anyOf).forEach((part, index) => {
+                    if (index) span.append(' | ');
+                    span.appendChild(typeNode(part));
+                });
+            } else if (schema.allOf) {
+                schema.allOf.forEach((part, index) => {
+                    if (index) span.append(' & ');
+                    span.appendChild(typeNode(part));
+                });
+            } else if (schema.type === 'array' || (Array.isArray(schema.type) && schema.type.includes('array'))) {
+                span.appendChild(typeNode(schema.items));
+                span.append('[]');
+                if (Array.isArray(schema.type) && schema.type.includes('null')) span.append(' | null');
+            } else if (Array.isArray(schema.type)) {
+                span.textContent = schema.type.join(' | ');
+            } else {
+                span.textContent = schema.type || 'object';
+                if (schema.format) span.textContent += ' (' + schema.format + ')';
+            }
+            return span;
+        }
+
+        // Properties of an object schema, following allOf for flattened fields.
+        function properties(schema, into) {
+            into = into || { fields: [], required: new Set() };
+            if (!schema) return into;
+            if (schema.$ref) return properties(spec.components.schemas[refName(schema.$ref)], into);
+            (schema.allOf || []).forEach(part => properties(part, into));
+            (schema.required || []).forEach(name => into.required.add(name));
+            Object.entries(schema.properties || {}).forEach(([name, field]) => into.fields.push([name, field]));
+            return into;
+        }
+
+        function fieldTable(schema) {
+            const { fields, required } = properties(schema);
+            const table = element('table', 'docs-table');
+            fields.forEach(([name, field]) => {
+                const row = table.insertRow();
+                const nameCell = row.insertCell();
+                nameCell.appendChild(element('code', '', name));
+                if (required.has(name)) nameCell.append(' ', element('span', 'required', 'required'));
+                row.insertCell().appendChild(typeNode(field));
+                row.insertCell().textContent = field.description || '';
+            });
+            return table;
+        }
+
+        function parametersTable(parameters) {
+            const table = element('table', 'docs-table');
+            const head = table.createTHead().insertRow();
+            ['Name', 'In', 'Type', 'Description'].forEach(label => head.appendChild(element('th', '', label)));
+            parameters.forEach(parameter => {
+                const row = table.insertRow();
+                const nameCell = row.insertCell();
+                nameCell.appendChild(element('code', '', parameter.name));
+                if (parameter.required) nameCell.append(' ', element('span', 'required', 'required'));
+                row.insertCell().textContent = parameter.in;
+                row.insertCell().appendChild(typeNode(parameter.schema));
+                row.insertCell().textContent = parameter.description || '';
+            });
+            return table;
+        }
+
+        function responsesTable(responses) {
+            const table = element('table', 'docs-table');
+            Object.entries(responses).forEach(([status, response]) => {
+                const row = table.insertRow();
+                row.insertCell().appendChild(element('code', '', status));
+                row.insertCell().textContent = response.description || '';
+                const bodyCell = row.insertCell();
+                Object.entries(response.content || {}).forEach(([type, media], index) => {
+                    if (index) bodyCell.appendChild(element('br'));
+                    bodyCell.appendChild(element('code', '', type));
+                    if (media.schema) bodyCell.append(' ', typeNode(media.schema));
+                });
+            });
+            return table;
+        }
+
+        // A form that calls a GET operation on this server and shows the reply.
+        function tryItForm(path, parameters) {
+            const form = element('form', 'try-it');
+            const inputs = parameters.map(parameter => {
+                const label = element('label', '', parameter.name + (parameter.in === 'path' ? ' (path)' : ''));
+                const input = element('input');
+                input.name = parameter.name;
+                input.required = !!parameter.required;
+                label.appendChild(input);
+                form.appendChild(label);
+                return [parameter, input];
+            });
+            const accept = element('select');
+            ['application/json', 'application/cbor', 'application/msgpack'].forEach(type => accept.appendChild(new Option(type, type)));
+            const acceptLabel = element('label', '', 'Accept');
+            acceptLabel.appendChild(accept);
+            form.appendChild(acceptLabel);
+            form.appendChild(element('button', '', 'Send'));
+            const output = element('pre', 'try-output');
+            output.hidden = true;
+
+            form.addEventListener('submit', async event => {
+                event.preventDefault();
+                let url = path;
+                const query = new URLSearchParams();
+                inputs.forEach(([parameter, input]) => {
+                    if (parameter.in === 'path') {
+                        url = url.replace('{' + parameter.name + '}', encodeURIComponent(input.value));
+                    } else if (input.value) {
+                        query.append(parameter.name, input.value);
+                    }
+                });
+                if (query.toString()) url += '?' + query;
+                output.hidden = false;
+                output.textContent = 'GET ' + url + '\n…';
+                try {
+                    const response = await fetch(url, { headers: { Accept: accept.value } });
+                    const type = response.headers.get('Content-Type') || '';
+                    let body;
+                    if (type.includes('json')) {
+                        body = JSON.stringify(await response.json(), null, 2);
+                    } else if (type.startsWith('text/')) {
+                        body = await response.text();
+                    } else {
+                        body = '(' + (await response.arrayBuffer()).byteLength + ' bytes of ' + type + ')';
+                    }
+                    output.textContent = 'GET ' + url + '\n' + response.status + ' ' + response.statusText + '\n\n' + body;
+                } catch (err) {
+                    output.textContent = 'GET ' + url + '\n' + err;
+                }
+            });
+
+            const wrapper = element('div');
+            wrapper.append(form, output);
+            return wrapper;
+        }
+
+        function operationNode(method, path, operation) {
+            const details = element('details', 'operation');
+            const summary = element('summary');
+            summary.append(
+                element('span', 'method method-' + method, method),
+                element('span', 'operation-path', path),
+                element('span', 'operation-summary', operation.summary || '')
+            );
+            details.appendChild(summary);
+
+            const body = element('div', 'operation-body');
+            if (operation.description) body.appendChild(element('p', 'tag-description', operation.description));
+            if (operation.security) body.appendChild(element('p', 'tag-description', 'Requires the session cookie from signing in.'));
+            const parameters = operation.parameters || [];
+            if (parameters.length) {
+                body.appendChild(element('h3', '', 'Parameters'));
+                body.appendChild(parametersTable(parameters));
+            }
+            const request = operation.requestBody && operation.requestBody.content;
+            if (request) {
+                body.appendChild(element('h3', '', 'Request body'));
+                Object.entries(request).forEach(([type, media]) => {
+                    const line = element('p');
+                    line.append(element('code', '', type), ' ', typeNode(media.schema));
+                    body.appendChild(line);
+                    body.appendChild(fieldTable(media.schema));
+                });
+            }
+            body.appendChild(element('h3', '', 'Responses'));
+            body.appendChild(responsesTable(operation.responses || {}));
+            if (method === 'get') {
+                body.appendChild(element('h3', '', 'Try it'));
+                body.appendChild(tryItForm(path, parameters));
+            }
+            details.appendChild(body);
+            return details;
+        }
+
+        function render() {
+            document.getElementById('docs-intro').textContent = spec.info.description || '';
+            document.getElementById('docs-version').textContent = '· version ' + spec.info.version + ' · OpenAPI ' + spec.openapi;
+
+            const tags = (spec.tags || []).map(tag => tag.name);
+            const byTag = new Map(tags.map(tag => [tag, []]));
+            Object.entries(spec.paths).forEach(([path, item]) => {
+                ['get', 'post', 'put', 'delete'].forEach(method => {
+                    const operation = item[method];
+                    if (!operation) return;
+                    const tag = (operation.tags || ['other'])[0];
+                    if (!byTag.has(tag)) byTag.set(tag, []);
+                    byTag.get(tag).push([method, path, operation]);
+                });
+            });
+
+            const toc = document.getElementById('docs-toc');
+            const container = document.getElementById('docs-operations');
+            byTag.forEach((operations, tag) => {
+                if (!operations.length) return;
+                const info = (spec.tags || []).find(t => t.name === tag) || {};
+                const section = element('section', 'card');
+                section.id = 'tag-' + tag;
+                section.appendChild(element('h2', '', tag.charAt(0).toUpperCase() + tag.slice(1)));
+                if (info.description) section.appendChild(element('p', 'tag-description', info.description));
+                operations.forEach(([method, path, operation]) => section.appendChild(operationNode(method, path, operation)));
+                container.appendChild(section);
+
+                const link = element('a', '', section.firstChild.textContent);
+                link.href = '#tag-' + tag;
+                toc.appendChild(link);
+            });
+            const schemasLink = element('a', '', 'Schemas');
+            schemasLink.href = '#schemas';
+            toc.appendChild(schemasLink);
+
+            const schemas = document.getElementById('docs-schemas');
+            Object.entries((spec.components || {}).schemas || {})
+                .sort(([a], [b]) => a.localeCompare(b))
+                .forEach(([name, schema]) => {
+                    const section = element('div', 'schema');
+                    section.id = 'schema-' + name;
+                    section.appendChild(element('h3', '', name));
+                    if (schema.description) section.appendChild(element('p', '', schema.description));
+                    if (schema.enum || schema.oneOf) {
+                        section.appendChild(typeNode(schema));
+                    } else {
+                        section.appendChild(fieldTable(schema));
+                    }
+                    schemas.appendChild(section);
+                });
+
+            if (location.hash) document.getElementById(location.hash.slice(1))?.scrollIntoView();
+        }
+
+        fetch('/api/openapi.json')
+            .then(response => response.json())
+            .then(openapi => { spec = openapi; render(); })
+            .catch(err => { document.getElementById('docs-intro').textContent = 'Could not load the API description: ' + err; });
+    </script>
+"#;
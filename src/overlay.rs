@@ -0,0 +1,563 @@
+//! Personal heading overlays: a signed-in user's own section titles, which
+//! either replace a base heading or add a new one to a chapter. The index page
+//! and `/api/books` merge the overlay into the base headings and mark which
+//! headings are personal.
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::accounts::{now, sign_in_required};
+use crate::listing::{self, ListParams};
+use crate::{
+    cache, canon, layout, negotiate, translations, v1, AppState, Book, Chapter, Testament,
+};
+
+const MAX_HEADING_LENGTH: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OverlayKind {
+    /// Shown in place of a base heading.
+    Override,
+    /// Added alongside the base headings.
+    Supplement,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct CustomHeading {
+    pub id: u64,
+    pub book: String,
+    pub chapter: u32,
+    pub kind: OverlayKind,
+    /// For overrides, the section replaced; for supplements, the section the
+    /// heading follows, or the end of the chapter when absent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub section: Option<usize>,
+    pub heading: String,
+    pub updated: u64,
+}
+
+/// A heading after merging a chapter's base headings with an overlay.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct MergedHeading {
+    pub heading: String,
+    /// Position of the base section this heading stands for; `None` for supplements.
+    #[serde(skip)]
+    pub section: Option<usize>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub personal: bool,
+    /// The overlay entry this heading came from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<u64>,
+    /// The base heading an override replaces.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub replaces: Option<String>,
+}
+
+/// Merges a chapter's base headings with the overlay entries for that chapter.
+pub fn merge(chapter: &Chapter, overlay: &[&CustomHeading]) -> Vec<MergedHeading> {
+    let supplement = |custom: &CustomHeading| MergedHeading {
+        heading: custom.heading.clone(),
+        section: None,
+        personal: true,
+        id: Some(custom.id),
+        replaces: None,
+    };
+
+    let mut merged = Vec::with_capacity(chapter.sections.len() + overlay.len());
+    for (index, base) in chapter.sections.iter().enumerate() {
+        let replacement = overlay
+            .iter()
+            .find(|c| c.kind == OverlayKind::Override && c.section == Some(index));
+        merged.push(match replacement {
+            Some(custom) => MergedHeading {
+                heading: custom.heading.clone(),
+                section: Some(index),
+                personal: true,
+                id: Some(custom.id),
+                replaces: Some(base.heading.clone()),
+            },
+            None => MergedHeading {
+                heading: base.heading.clone(),
+                section: Some(index),
+                personal: false,
+                id: None,
+                replaces: None,
+            },
+        });
+        merged.extend(
+            overlay
+                .iter()
+                .filter(|c| c.kind == OverlayKind::Supplement && c.section == Some(index))
+                .map(|c| supplement(c)),
+        );
+    }
+    merged.extend(
+        overlay
+            .iter()
+            .filter(|c| {
+                c.kind == OverlayKind::Supplement
+                    && c.section.is_none_or(|s| s >= chapter.sections.len())
+            })
+            .map(|c| supplement(c)),
+    );
+    merged
+}
+
+#[derive(Serialize, ToSchema)]
+struct MergedChapter {
+    chapter_number: u32,
+    sections: Vec<MergedHeading>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct MergedBook<'a> {
+    name: &'a str,
+    testament: &'a Testament,
+    chapters: Vec<MergedChapter>,
+}
+
+/// `/api/books`: the base books (or the heading set named by `?headings=`),
+/// merged with the signed-in user's overlay. `?limit=`, `?cursor=`,
+/// `?fields=` and `?include=sections` page through and trim the list.
+#[utoipa::path(
+    get,
+    path = "/api/books",
+    tag = "books",
+    params(("headings" = Option<String>, Query, description = "Heading set id from `/api/translations`"), ListParams),
+    responses(
+        (status = 200, description = "Every book with its chapters and headings, or the requested page and fields", content(([MergedBook] = "application/json"), ([MergedBook] = "application/cbor"), ([MergedBook] = "application/msgpack")),
+            headers(
+                ("Link" = String, description = "`first`, `last`, `prev` and `next` pages when `limit` is given"),
+                ("X-Total-Count" = usize, description = "Books in the whole list, when paging or selecting fields"),
+            )),
+        (status = 304, description = "The client's cached copy is current"),
+        (status = 400, description = "Bad `limit`, `cursor`, `fields` or `include`", body = crate::openapi::ErrorBody),
+        (status = 406, description = "No supported format is acceptable", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_books(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<ListParams>,
+) -> Result<HttpResponse> {
+    let set = translations::requested(&req, &data.translations);
+    let user = data.accounts.current_user(&req);
+    if user.is_none() && params.is_default() {
+        let body = set.map_or(&*data.books_body, |set| &set.body);
+        return Ok(body.respond(&req, &data.data_version));
+    }
+    // A signed-in user's copy carries their own headings, so only they may keep it.
+    let cache_control = match user {
+        Some(_) => cache::PRIVATE_CACHE_CONTROL,
+        None => cache::API_CACHE_CONTROL,
+    };
+    let overlay = user
+        .and_then(|username| data.accounts.annotations(&username))
+        .map(|account| account.headings)
+        .unwrap_or_default();
+
+    let books = set.map_or(&data.books[..], |set| &set.books[..]);
+    let books: Vec<MergedBook> = books
+        .iter()
+        .map(|book| MergedBook {
+            name: &book.name,
+            testament: &book.testament,
+            chapters: book
+                .chapters
+                .iter()
+                .map(|chapter| MergedChapter {
+                    chapter_number: chapter.chapter_number,
+                    sections: merge(
+                        chapter,
+                        &for_chapter(&overlay, &book.name, chapter.chapter_number),
+                    ),
+                })
+                .collect(),
+        })
+        .collect();
+    if params.is_default() {
+        return Ok(negotiate::respond_with(&req, &books, cache_control));
+    }
+    Ok(listing::respond(
+        &req,
+        &params,
+        &books,
+        |book| v1::book_id(book.name),
+        &[(
+            "sections",
+            &["chapters.chapter_number", "chapters.sections"],
+        )],
+        cache_control,
+    ))
+}
+
+/// The overlay entries for one chapter.
+pub fn for_chapter<'a>(
+    overlay: &'a [CustomHeading],
+    book: &str,
+    chapter: u32,
+) -> Vec<&'a CustomHeading> {
+    overlay
+        .iter()
+        .filter(|c| c.book == book && c.chapter == chapter)
+        .collect()
+}
+
+// ---------------------------------------------------------------------------
+// Page rendering
+// ---------------------------------------------------------------------------
+
+/// Badge and edit buttons shown after a heading while signed in.
+pub fn heading_tools_html(book: &str, chapter: u32, heading: &MergedHeading) -> String {
+    match (heading.id, &heading.replaces) {
+        (Some(id), replaces) => {
+            let title = match replaces {
+                Some(base) => format!("Your heading, replacing “{}”", layout::escape(base)),
+                None => "Your heading".to_string(),
+            };
+            let remove = if replaces.is_some() {
+                "Restore"
+            } else {
+                "Remove"
+            };
+            format!(
+                r#"
+                            <span class="personal-mark" title="{}">Personal</span>
+                            <button class="heading-action" onclick="editHeading({}, this)">Edit</button>
+                            <button class="heading-action" onclick="removeHeading({})">{}</button>"#,
+                title, id, id, remove
+            )
+        }
+        (None, _) => format!(
+            r#"
+                            <button class="heading-action" onclick="renameHeading('{}', {}, {})" aria-label="Write your own heading">Rename</button>"#,
+            book,
+            chapter,
+            heading.section.unwrap_or_default()
+        ),
+    }
+}
+
+/// Button at the end of a chapter card for adding a heading of one's own.
+pub fn add_heading_html(book: &str, chapter: u32) -> String {
+    format!(
+        r#"
+                    <button class="heading-action add-heading" onclick="addHeading('{}', {})">+ Add my own heading</button>"#,
+        book, chapter
+    )
+}
+
+pub const STYLES: &str = r#"
+        .section-heading.personal .heading-text {
+            font-style: italic;
+            color: #553c9a;
+        }
+
+        .personal-mark {
+            font-size: 0.65rem;
+            font-weight: 600;
+            text-transform: uppercase;
+            letter-spacing: 0.5px;
+            color: white;
+            background: #764ba2;
+            border-radius: 4px;
+            padding: 0.1rem 0.35rem;
+            align-self: center;
+        }
+
+        .heading-action {
+            border: none;
+            background: none;
+            color: #667eea;
+            font-family: 'Inter', sans-serif;
+            font-size: 0.75rem;
+            cursor: pointer;
+            padding: 0;
+            align-self: center;
+        }
+
+        .heading-action:hover {
+            text-decoration: underline;
+        }
+
+        .add-heading {
+            margin-top: 0.75rem;
+        }
+"#;
+
+pub const SCRIPT: &str = r#"
+        function saveHeading(request) {
+            request.then(() => location.reload()).catch(reportError);
+        }
+
+        function renameHeading(book, chapter, section) {
+            const heading = prompt('Your heading for this section:');
+            if (heading) {
+                saveHeading(sendJson('POST', '/api/headings', { book: book, chapter: chapter, kind: 'override', section: section, heading: heading }));
+            }
+        }
+
+        function addHeading(book, chapter) {
+            const heading = prompt('New heading for the end of this chapter:');
+            if (heading) {
+                saveHeading(sendJson('POST', '/api/headings', { book: book, chapter: chapter, kind: 'supplement', heading: heading }));
+            }
+        }
+
+        function editHeading(id, button) {
+            const current = button.closest('.section-heading').querySelector('.heading-text').textContent;
+            const heading = prompt('Your heading:', current);
+            if (heading) {
+                saveHeading(sendJson('PUT', '/api/headings/' + id, { heading: heading }));
+            }
+        }
+
+        function removeHeading(id) {
+            saveHeading(sendJson('DELETE', '/api/headings/' + id));
+        }
+"#;
+
+// ---------------------------------------------------------------------------
+// Handlers
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize, ToSchema)]
+pub struct NewHeading {
+    book: String,
+    chapter: u32,
+    kind: OverlayKind,
+    section: Option<usize>,
+    heading: String,
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct HeadingText {
+    heading: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct OverlayFilter {
+    /// Only this book's headings.
+    book: Option<String>,
+    /// Only this chapter's headings.
+    chapter: Option<u32>,
+}
+
+fn bad_request(message: impl Into<String>) -> HttpResponse {
+    HttpResponse::BadRequest().json(json!({ "error": message.into() }))
+}
+
+fn check_heading(text: &str) -> std::result::Result<String, HttpResponse> {
+    let text = text.trim();
+    if text.is_empty() || text.chars().count() > MAX_HEADING_LENGTH {
+        return Err(bad_request(format!(
+            "Headings are 1 to {} characters",
+            MAX_HEADING_LENGTH
+        )));
+    }
+    Ok(text.to_string())
+}
+
+/// Finds the chapter a new heading belongs to, normalizing the book name.
+fn find_chapter<'a>(books: &'a [Book], book: &str, chapter: u32) -> Option<(&'a str, &'a Chapter)> {
+    let name = canon::lookup(book).map_or(book, |info| info.name);
+    let book = books.iter().find(|b| b.name == name)?;
+    let chapter = book.chapters.iter().find(|c| c.chapter_number == chapter)?;
+    Some((&book.name, chapter))
+}
+
+/// The signed-in user's personal headings, optionally for one book or chapter.
+#[utoipa::path(
+    get,
+    path = "/api/headings",
+    tag = "account",
+    security(("session" = [])),
+    params(OverlayFilter),
+    responses((status = 200, description = "Personal headings", body = crate::openapi::HeadingList), (status = 401, description = "Not signed in", body = crate::openapi::ErrorBody))
+)]
+pub async fn api_headings(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    filter: web::Query<OverlayFilter>,
+) -> Result<HttpResponse> {
+    let Some(account) = data
+        .accounts
+        .current_user(&req)
+        .and_then(|username| data.accounts.annotations(&username))
+    else {
+        return Ok(sign_in_required());
+    };
+    let book = filter
+        .book
+        .as_deref()
+        .map(|book| canon::lookup(book).map_or(book, |info| info.name));
+    let headings: Vec<&CustomHeading> = account
+        .headings
+        .iter()
+        .filter(|c| book.is_none_or(|book| c.book == book))
+        .filter(|c| filter.chapter.is_none_or(|chapter| c.chapter == chapter))
+        .collect();
+    Ok(HttpResponse::Ok().json(json!({ "total": headings.len(), "headings": headings })))
+}
+
+/// Adds a personal heading that overrides or supplements the base headings.
+#[utoipa::path(
+    post,
+    path = "/api/headings",
+    tag = "account",
+    security(("session" = [])),
+    request_body = NewHeading,
+    responses(
+        (status = 201, description = "The new heading; a second override of a section replaces the first", body = CustomHeading),
+        (status = 400, description = "No such chapter or section, or bad heading length", body = crate::openapi::ErrorBody),
+        (status = 401, description = "Not signed in", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_add_heading(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    body: web::Json<NewHeading>,
+) -> Result<HttpResponse> {
+    let Some(username) = data.accounts.current_user(&req) else {
+        return Ok(sign_in_required());
+    };
+    let heading = match check_heading(&body.heading) {
+        Ok(heading) => heading,
+        Err(response) => return Ok(response),
+    };
+    let Some((book, chapter)) = find_chapter(&data.books, &body.book, body.chapter) else {
+        return Ok(bad_request("No such chapter"));
+    };
+    match (body.kind, body.section) {
+        (OverlayKind::Override, None) => {
+            return Ok(bad_request("Overrides need the section they replace"));
+        }
+        (_, Some(section)) if section >= chapter.sections.len() => {
+            return Ok(bad_request("No such section"));
+        }
+        _ => {}
+    }
+
+    let result = data
+        .accounts
+        .update(&username, |user, next_id| {
+            // A second override of the same section replaces the first.
+            let existing = user.headings.iter_mut().find(|c| {
+                body.kind == OverlayKind::Override
+                    && c.kind == OverlayKind::Override
+                    && c.book == book
+                    && c.chapter == chapter.chapter_number
+                    && c.section == body.section
+            });
+            if let Some(existing) = existing {
+                existing.heading = heading;
+                existing.updated = now();
+                return existing.clone();
+            }
+
+            *next_id += 1;
+            let custom = CustomHeading {
+                id: *next_id,
+                book: book.to_string(),
+                chapter: chapter.chapter_number,
+                kind: body.kind,
+                section: body.section,
+                heading,
+                updated: now(),
+            };
+            user.headings.push(custom.clone());
+            custom
+        })
+        .await;
+    match result {
+        Ok(custom) => Ok(HttpResponse::Created().json(custom)),
+        Err(err) => Ok(err.response()),
+    }
+}
+
+/// Changes a personal heading's text.
+#[utoipa::path(
+    put,
+    path = "/api/headings/{id}",
+    tag = "account",
+    security(("session" = [])),
+    params(("id" = u64, Path, description = "Heading id")),
+    request_body = HeadingText,
+    responses(
+        (status = 200, description = "The updated heading", body = CustomHeading),
+        (status = 400, description = "Bad heading length", body = crate::openapi::ErrorBody),
+        (status = 404, description = "No such heading", body = crate::openapi::ErrorBody),
+        (status = 401, description = "Not signed in", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_update_heading(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    id: web::Path<u64>,
+    body: web::Json<HeadingText>,
+) -> Result<HttpResponse> {
+    let Some(username) = data.accounts.current_user(&req) else {
+        return Ok(sign_in_required());
+    };
+    let heading = match check_heading(&body.heading) {
+        Ok(heading) => heading,
+        Err(response) => return Ok(response),
+    };
+    let id = id.into_inner();
+
+    let result = data
+        .accounts
+        .update(&username, |user, _| {
+            let custom = user.headings.iter_mut().find(|c| c.id == id)?;
+            custom.heading = heading;
+            custom.updated = now();
+            Some(custom.clone())
+        })
+        .await;
+    match result {
+        Ok(Some(custom)) => Ok(HttpResponse::Ok().json(custom)),
+        Ok(None) => Ok(HttpResponse::NotFound().json(json!({ "error": "No such heading" }))),
+        Err(err) => Ok(err.response()),
+    }
+}
+
+/// Deletes a personal heading.
+#[utoipa::path(
+    delete,
+    path = "/api/headings/{id}",
+    tag = "account",
+    security(("session" = [])),
+    params(("id" = u64, Path, description = "Heading id")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, description = "No such heading", body = crate::openapi::ErrorBody),
+        (status = 401, description = "Not signed in", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_delete_heading(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    id: web::Path<u64>,
+) -> Result<HttpResponse> {
+    let Some(username) = data.accounts.current_user(&req) else {
+        return Ok(sign_in_required());
+    };
+    let id = id.into_inner();
+    let result = data
+        .accounts
+        .update(&username, |user, _| {
+            let before = user.headings.len();
+            user.headings.retain(|c| c.id != id);
+            before != user.headings.len()
+        })
+        .await;
+    match result {
+        Ok(true) => Ok(HttpResponse::NoContent().finish()),
+        Ok(false) => Ok(HttpResponse::NotFound().json(json!({ "error": "No such heading" }))),
+        Err(err) => Ok(err.response()),
+    }
+}
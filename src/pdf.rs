@@ -0,0 +1,469 @@
+//! Print-ready PDF handouts of the headings outline, written directly without
+//! external libraries. Text uses the standard Helvetica fonts every PDF reader
+//! provides, so nothing is embedded; line breaking uses their published
+//! character widths.
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use std::io::Write;
+
+use crate::export::{headings, Selection};
+use crate::PLACEHOLDER_HEADING;
+
+// US Letter, in points.
+const PAGE_WIDTH: f32 = 612.0;
+const PAGE_HEIGHT: f32 = 792.0;
+const MARGIN: f32 = 54.0;
+const GUTTER: f32 = 24.0;
+const CONTENT_TOP: f32 = PAGE_HEIGHT - 66.0;
+const CONTENT_BOTTOM: f32 = 54.0;
+
+const PURPLE: Color = (0.400, 0.494, 0.918); // #667eea
+const VIOLET: Color = (0.463, 0.294, 0.635); // #764ba2
+const INK: Color = (0.176, 0.216, 0.282); // #2d3748
+const MUTED: Color = (0.443, 0.502, 0.588); // #718096
+
+type Color = (f32, f32, f32);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Font {
+    Regular,
+    Bold,
+    Italic,
+}
+
+impl Font {
+    fn resource(self) -> &'static str {
+        match self {
+            Font::Regular => "F1",
+            Font::Bold => "F2",
+            Font::Italic => "F3",
+        }
+    }
+
+    /// Advance width of a WinAnsi character in thousandths of the font size.
+    fn width(self, byte: u8) -> u16 {
+        match byte {
+            32..=126 => match self {
+                Font::Bold => HELVETICA_BOLD_WIDTHS[(byte - 32) as usize],
+                // Helvetica-Oblique shares Helvetica's metrics.
+                Font::Regular | Font::Italic => HELVETICA_WIDTHS[(byte - 32) as usize],
+            },
+            0x85 | 0x97 => 1000, // ellipsis, em dash
+            0x95 => 350,         // bullet
+            0x91 | 0x92 if self == Font::Bold => 278,
+            0x91 | 0x92 => 222, // single quotes
+            0x93 | 0x94 if self == Font::Bold => 500,
+            0x93 | 0x94 => 333, // double quotes
+            _ => 556,           // en dash and accented letters
+        }
+    }
+
+    fn measure(self, text: &[u8], size: f32) -> f32 {
+        text.iter().map(|&b| self.width(b) as f32).sum::<f32>() * size / 1000.0
+    }
+}
+
+#[rustfmt::skip]
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+#[rustfmt::skip]
+const HELVETICA_BOLD_WIDTHS: [u16; 95] = [
+    278, 333, 474, 556, 556, 889, 722, 238, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 333, 333, 584, 584, 584, 611,
+    975, 722, 722, 722, 722, 667, 611, 778, 722, 278, 556, 722, 611, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 333, 278, 333, 584, 556,
+    333, 556, 611, 556, 611, 556, 333, 611, 611, 278, 278, 556, 278, 889, 611, 611,
+    611, 611, 389, 556, 333, 611, 556, 778, 556, 556, 500, 389, 280, 389, 584,
+];
+
+/// Encodes text as WinAnsi, the encoding of the standard fonts.
+fn win_ansi(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|c| match c {
+            ' '..='~' => c as u8,
+            '\u{a0}'..='\u{ff}' => c as u32 as u8,
+            '…' => 0x85,
+            '‘' => 0x91,
+            '’' => 0x92,
+            '“' => 0x93,
+            '”' => 0x94,
+            '•' => 0x95,
+            '–' => 0x96,
+            '—' => 0x97,
+            _ => b'?',
+        })
+        .collect()
+}
+
+struct Text {
+    font: Font,
+    size: f32,
+    color: Color,
+    x: f32,
+    y: f32,
+    text: Vec<u8>,
+}
+
+/// Flows outline entries down columns and across pages.
+struct Layout {
+    columns: usize,
+    pages: Vec<Vec<Text>>,
+    column: usize,
+    y: f32,
+    /// Top of the columns on the current page (lower on the title page).
+    top: f32,
+}
+
+impl Layout {
+    fn new(columns: usize) -> Self {
+        Layout {
+            columns,
+            pages: vec![Vec::new()],
+            column: 0,
+            y: CONTENT_TOP,
+            top: CONTENT_TOP,
+        }
+    }
+
+    fn column_width(&self) -> f32 {
+        (PAGE_WIDTH - 2.0 * MARGIN - GUTTER * (self.columns - 1) as f32) / self.columns as f32
+    }
+
+    fn column_x(&self) -> f32 {
+        MARGIN + self.column as f32 * (self.column_width() + GUTTER)
+    }
+
+    fn at_column_top(&self) -> bool {
+        self.y >= self.top
+    }
+
+    /// Moves to the next column or page unless `height` still fits.
+    fn ensure(&mut self, height: f32) {
+        if self.y - height >= CONTENT_BOTTOM || self.at_column_top() {
+            return;
+        }
+        if self.column + 1 < self.columns {
+            self.column += 1;
+        } else {
+            self.pages.push(Vec::new());
+            self.column = 0;
+            self.top = CONTENT_TOP;
+        }
+        self.y = self.top;
+    }
+
+    fn text(&mut self, font: Font, size: f32, color: Color, x: f32, text: Vec<u8>) {
+        let y = self.y;
+        self.pages
+            .last_mut()
+            .expect("layout always has a page")
+            .push(Text {
+                font,
+                size,
+                color,
+                x,
+                y,
+                text,
+            });
+    }
+
+    /// A full-width title block at the top of the first page.
+    fn title(&mut self, title: &str) {
+        self.y -= 20.0;
+        self.text(
+            Font::Bold,
+            20.0,
+            PURPLE,
+            MARGIN,
+            win_ansi("Bible Section Headlines"),
+        );
+        self.y -= 20.0;
+        self.text(Font::Regular, 12.0, VIOLET, MARGIN, win_ansi(title));
+        self.y -= 24.0;
+        self.top = self.y;
+    }
+
+    fn book(&mut self, name: &str) {
+        if !self.at_column_top() {
+            self.y -= 10.0;
+        }
+        // Keep the book title with its first chapter and heading.
+        self.ensure(22.0 + 15.0 + 12.5);
+        self.y -= 18.0;
+        let x = self.column_x();
+        self.text(Font::Bold, 16.0, PURPLE, x, win_ansi(name));
+        self.y -= 4.0;
+    }
+
+    fn chapter(&mut self, number: u32) {
+        self.ensure(15.0 + 12.5);
+        self.y -= 15.0;
+        let x = self.column_x();
+        self.text(
+            Font::Bold,
+            10.5,
+            VIOLET,
+            x,
+            win_ansi(&format!("Chapter {}", number)),
+        );
+    }
+
+    fn heading(&mut self, heading: &str) {
+        const SIZE: f32 = 9.5;
+        const INDENT: f32 = 10.0;
+        let width = self.column_width() - INDENT;
+
+        for (index, line) in wrap(&win_ansi(heading), Font::Regular, SIZE, width)
+            .into_iter()
+            .enumerate()
+        {
+            self.ensure(12.5);
+            self.y -= 12.5;
+            let x = self.column_x();
+            if index == 0 {
+                self.text(Font::Regular, SIZE, PURPLE, x + 2.0, vec![0x95]);
+            }
+            self.text(Font::Regular, SIZE, INK, x + INDENT, line);
+        }
+    }
+
+    fn placeholder(&mut self, text: &str) {
+        self.ensure(12.5);
+        self.y -= 12.5;
+        let x = self.column_x() + 10.0;
+        self.text(Font::Italic, 9.0, MUTED, x, win_ansi(text));
+    }
+}
+
+/// Greedy word wrap; words wider than a line are left to overflow.
+fn wrap(text: &[u8], font: Font, size: f32, width: f32) -> Vec<Vec<u8>> {
+    let mut lines: Vec<Vec<u8>> = Vec::new();
+    let mut line: Vec<u8> = Vec::new();
+    for word in text.split(|&b| b == b' ').filter(|w| !w.is_empty()) {
+        let candidate_width =
+            font.measure(&line, size) + font.measure(b" ", size) + font.measure(word, size);
+        if !line.is_empty() && candidate_width > width {
+            lines.push(std::mem::take(&mut line));
+        }
+        if !line.is_empty() {
+            line.push(b' ');
+        }
+        line.extend_from_slice(word);
+    }
+    if !line.is_empty() || lines.is_empty() {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Escapes bytes for a PDF literal string.
+fn pdf_string(text: &[u8]) -> String {
+    let mut out = String::from("(");
+    for &b in text {
+        match b {
+            b'(' | b')' | b'\\' => {
+                out.push('\\');
+                out.push(b as char);
+            }
+            32..=126 => out.push(b as char),
+            _ => out.push_str(&format!("\\{:03o}", b)),
+        }
+    }
+    out.push(')');
+    out
+}
+
+fn content_stream(texts: &[Text], header: &[u8], page: usize, pages: usize) -> Vec<u8> {
+    let mut ops = String::new();
+
+    // Running header with a rule beneath it.
+    ops.push_str(&format!(
+        "BT /F1 8 Tf {} {} {} rg {} {} Td {} Tj ET\n",
+        MUTED.0,
+        MUTED.1,
+        MUTED.2,
+        MARGIN,
+        PAGE_HEIGHT - 36.0,
+        pdf_string(header)
+    ));
+    ops.push_str(&format!(
+        "0.886 0.910 0.941 RG 0.75 w {} {} m {} {} l S\n",
+        MARGIN,
+        PAGE_HEIGHT - 42.0,
+        PAGE_WIDTH - MARGIN,
+        PAGE_HEIGHT - 42.0
+    ));
+
+    for text in texts {
+        ops.push_str(&format!(
+            "BT /{} {} Tf {} {} {} rg {:.2} {:.2} Td {} Tj ET\n",
+            text.font.resource(),
+            text.size,
+            text.color.0,
+            text.color.1,
+            text.color.2,
+            text.x,
+            text.y,
+            pdf_string(&text.text)
+        ));
+    }
+
+    let footer = format!("Page {} of {}", page, pages).into_bytes();
+    let footer_x = (PAGE_WIDTH - Font::Regular.measure(&footer, 8.0)) / 2.0;
+    ops.push_str(&format!(
+        "BT /F1 8 Tf {} {} {} rg {:.2} 30 Td {} Tj ET\n",
+        MUTED.0,
+        MUTED.1,
+        MUTED.2,
+        footer_x,
+        pdf_string(&footer)
+    ));
+
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    // Writing to a Vec cannot fail.
+    encoder.write_all(ops.as_bytes()).expect("in-memory write");
+    encoder.finish().expect("in-memory write")
+}
+
+/// Lays out the selection in one or two columns and returns the PDF file.
+pub fn build(selection: &Selection, columns: usize) -> Vec<u8> {
+    let mut layout = Layout::new(columns.max(1));
+    layout.title(&selection.title);
+    for (book, chapters) in &selection.books {
+        layout.book(&book.name);
+        for chapter in chapters {
+            layout.chapter(chapter.chapter_number);
+            let mut any = false;
+            for heading in headings(chapter) {
+                layout.heading(heading);
+                any = true;
+            }
+            if !any {
+                layout.placeholder(PLACEHOLDER_HEADING);
+            }
+        }
+    }
+
+    let header = win_ansi(&format!("Bible Section Headlines — {}", selection.title));
+    let page_count = layout.pages.len();
+
+    // Objects 1-5 are fixed; each page then takes a page object and a content stream.
+    let mut objects: Vec<Vec<u8>> = Vec::new();
+    let kids: Vec<String> = (0..page_count)
+        .map(|i| format!("{} 0 R", 6 + 2 * i))
+        .collect();
+    objects.push(b"<< /Type /Catalog /Pages 2 0 R >>".to_vec());
+    objects.push(
+        format!(
+            "<< /Type /Pages /Kids [{}] /Count {} >>",
+            kids.join(" "),
+            page_count
+        )
+        .into_bytes(),
+    );
+    for font in ["Helvetica", "Helvetica-Bold", "Helvetica-Oblique"] {
+        objects.push(
+            format!(
+                "<< /Type /Font /Subtype /Type1 /BaseFont /{} /Encoding /WinAnsiEncoding >>",
+                font
+            )
+            .into_bytes(),
+        );
+    }
+    for (index, texts) in layout.pages.iter().enumerate() {
+        objects.push(
+            format!(
+                "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 3 0 R /F2 4 0 R /F3 5 0 R >> >> /Contents {} 0 R >>",
+                PAGE_WIDTH,
+                PAGE_HEIGHT,
+                7 + 2 * index
+            )
+            .into_bytes(),
+        );
+        let stream = content_stream(texts, &header, index + 1, page_count);
+        let mut object = format!(
+            "<< /Length {} /Filter /FlateDecode >>\nstream\n",
+            stream.len()
+        )
+        .into_bytes();
+        object.extend_from_slice(&stream);
+        object.extend_from_slice(b"\nendstream");
+        objects.push(object);
+    }
+    let title = win_ansi(&format!("Bible Section Headlines: {}", selection.title));
+    objects.push(
+        format!(
+            "<< /Title {} /Producer (bible-headlines) >>",
+            pdf_string(&title)
+        )
+        .into_bytes(),
+    );
+
+    let mut pdf = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+    let mut offsets = Vec::with_capacity(objects.len());
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+        pdf.extend_from_slice(object);
+        pdf.extend_from_slice(b"\nendobj\n");
+    }
+
+    let xref = pdf.len();
+    pdf.extend_from_slice(
+        format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes(),
+    );
+    for offset in offsets {
+        pdf.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend_from_slice(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R /Info {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            objects.len(),
+            xref
+        )
+        .into_bytes()
+        .as_slice(),
+    );
+    pdf
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn text_is_encoded_as_win_ansi() {
+        assert_eq!(
+            win_ansi("Génesis – “Light”"),
+            b"G\xe9nesis \x96 \x93Light\x94"
+        );
+        assert_eq!(win_ansi("שלום"), b"????");
+    }
+
+    #[test]
+    fn strings_are_escaped() {
+        assert_eq!(pdf_string(b"(1:2) \\ ok"), r"(\(1:2\) \\ ok)");
+        assert_eq!(pdf_string(b"\xe9"), r"(\351)");
+    }
+
+    #[test]
+    fn wrapping_breaks_between_words() {
+        let text = b"The Parable of the Sower and the Seed";
+        let lines = wrap(text, Font::Regular, 10.0, 80.0);
+        assert!(lines.len() > 1);
+        assert_eq!(lines.join(&b' '), text);
+        for line in &lines[..lines.len() - 1] {
+            assert!(Font::Regular.measure(line, 10.0) <= 80.0);
+        }
+        assert_eq!(wrap(b"", Font::Regular, 10.0, 80.0), vec![Vec::<u8>::new()]);
+    }
+}
@@ -0,0 +1,678 @@
+//! Reading plans that split books into days on section boundaries rather than
+//! by arbitrary chapter counts, with an iCalendar export.
+
+use actix_web::{web, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::time::{SystemTime, UNIX_EPOCH};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{AppState, Book, Testament};
+
+/// Approximate order in which the books' events took place (or were written).
+#[rustfmt::skip]
+const CHRONOLOGICAL_ORDER: [&str; 66] = [
+    "Genesis", "Job", "Exodus", "Leviticus", "Numbers", "Deuteronomy", "Joshua", "Judges",
+    "Ruth", "1 Samuel", "2 Samuel", "1 Chronicles", "Psalms", "Proverbs", "Ecclesiastes",
+    "Song of Solomon", "1 Kings", "2 Chronicles", "Obadiah", "Joel", "Jonah", "Amos", "Hosea",
+    "Isaiah", "Micah", "2 Kings", "Nahum", "Zephaniah", "Jeremiah", "Habakkuk", "Lamentations",
+    "Ezekiel", "Daniel", "Ezra", "Haggai", "Zechariah", "Esther", "Nehemiah", "Malachi",
+    "Mark", "Matthew", "Luke", "John", "Acts", "James", "Galatians", "1 Thessalonians",
+    "2 Thessalonians", "1 Corinthians", "2 Corinthians", "Romans", "Ephesians", "Philippians",
+    "Colossians", "Philemon", "1 Timothy", "Titus", "1 Peter", "Hebrews", "2 Timothy",
+    "2 Peter", "Jude", "1 John", "2 John", "3 John", "Revelation",
+];
+
+enum BookOrder {
+    Canonical,
+    NewTestament,
+    Chronological,
+}
+
+struct PlanDefinition {
+    id: &'static str,
+    name: &'static str,
+    description: &'static str,
+    days: usize,
+    order: BookOrder,
+}
+
+const PLANS: &[PlanDefinition] = &[
+    PlanDefinition {
+        id: "bible-in-a-year",
+        name: "Bible in a Year",
+        description: "All 66 books in canonical order over 365 days",
+        days: 365,
+        order: BookOrder::Canonical,
+    },
+    PlanDefinition {
+        id: "nt-90",
+        name: "New Testament in 90 Days",
+        description: "Matthew through Revelation over 90 days",
+        days: 90,
+        order: BookOrder::NewTestament,
+    },
+    PlanDefinition {
+        id: "chronological",
+        name: "Chronological Bible",
+        description: "All 66 books in approximate historical order over 365 days",
+        days: 365,
+        order: BookOrder::Chronological,
+    },
+];
+
+/// The sections of one chapter read on a given day.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Portion {
+    pub book: String,
+    pub chapter: u32,
+    pub headings: Vec<String>,
+    /// True when the chapter's other sections fall on a neighbouring day.
+    pub partial: bool,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct PlanDay {
+    pub day: usize,
+    /// A short reference list such as "Genesis 1–3; Exodus 1".
+    pub summary: String,
+    pub portions: Vec<Portion>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ReadingPlan {
+    pub id: &'static str,
+    pub name: &'static str,
+    pub description: &'static str,
+    #[serde(skip)]
+    pub days: Vec<PlanDay>,
+}
+
+/// One section in reading order, the smallest piece a day can hold.
+struct Unit<'a> {
+    book: &'a str,
+    chapter: u32,
+    heading: &'a str,
+    chapter_sections: usize,
+}
+
+impl ReadingPlan {
+    /// Builds every predefined plan from the loaded books.
+    pub fn build_all(books: &[Book]) -> Vec<ReadingPlan> {
+        PLANS
+            .iter()
+            .map(|plan| ReadingPlan::build(plan, books))
+            .collect()
+    }
+
+    fn build(plan: &PlanDefinition, books: &[Book]) -> ReadingPlan {
+        let ordered: Vec<&Book> = match plan.order {
+            BookOrder::Canonical => books.iter().collect(),
+            BookOrder::NewTestament => books
+                .iter()
+                .filter(|book| book.testament == Testament::New)
+                .collect(),
+            BookOrder::Chronological => CHRONOLOGICAL_ORDER
+                .iter()
+                .filter_map(|name| books.iter().find(|book| book.name == *name))
+                .collect(),
+        };
+
+        let units: Vec<Unit> = ordered
+            .iter()
+            .flat_map(|book| {
+                book.chapters.iter().flat_map(move |chapter| {
+                    chapter.sections.iter().map(move |section| Unit {
+                        book: &book.name,
+                        chapter: chapter.chapter_number,
+                        heading: &section.heading,
+                        chapter_sections: chapter.sections.len(),
+                    })
+                })
+            })
+            .collect();
+
+        // Spread sections evenly; a plan never has more days than sections.
+        let day_count = plan.days.min(units.len());
+        let days = (0..day_count)
+            .map(|day| {
+                let start = day * units.len() / day_count;
+                let end = (day + 1) * units.len() / day_count;
+                plan_day(day + 1, &units[start..end])
+            })
+            .collect();
+
+        ReadingPlan {
+            id: plan.id,
+            name: plan.name,
+            description: plan.description,
+            days,
+        }
+    }
+}
+
+fn plan_day(day: usize, units: &[Unit]) -> PlanDay {
+    let mut portions: Vec<Portion> = Vec::new();
+    for unit in units {
+        match portions.last_mut() {
+            Some(last) if last.book == unit.book && last.chapter == unit.chapter => {
+                last.headings.push(unit.heading.to_string());
+            }
+            _ => portions.push(Portion {
+                book: unit.book.to_string(),
+                chapter: unit.chapter,
+                headings: vec![unit.heading.to_string()],
+                partial: false,
+            }),
+        }
+        let last = portions.last_mut().expect("portion was just pushed");
+        last.partial = last.headings.len() < unit.chapter_sections;
+    }
+
+    PlanDay {
+        day,
+        summary: summarize(&portions),
+        portions,
+    }
+}
+
+/// Collapses portions into "Genesis 1–3; Exodus 1" style references.
+fn summarize(portions: &[Portion]) -> String {
+    let mut parts: Vec<(String, u32, u32)> = Vec::new();
+    for portion in portions {
+        match parts.last_mut() {
+            Some((book, _, end)) if *book == portion.book && *end + 1 == portion.chapter => {
+                *end = portion.chapter;
+            }
+            Some((book, _, end)) if *book == portion.book && *end == portion.chapter => {}
+            _ => parts.push((portion.book.clone(), portion.chapter, portion.chapter)),
+        }
+    }
+
+    parts
+        .iter()
+        .map(|(book, start, end)| {
+            if start == end {
+                format!("{} {}", book, start)
+            } else {
+                format!("{} {}–{}", book, start, end)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+// ---------------------------------------------------------------------------
+// Dates
+// ---------------------------------------------------------------------------
+
+/// A calendar date, stored as days since 1970-01-01.
+#[derive(Debug, Clone, Copy)]
+struct Date(i64);
+
+impl Date {
+    /// Parses `YYYY-MM-DD`. Years stop at 9999, since iCalendar dates have
+    /// four-digit years.
+    fn parse(text: &str) -> Option<Date> {
+        let mut parts = text.splitn(3, '-');
+        let year: i64 = parts.next()?.parse().ok()?;
+        let month: u32 = parts.next()?.parse().ok()?;
+        let day: u32 = parts.next()?.parse().ok()?;
+        if !(1..=9999).contains(&year)
+            || !(1..=12).contains(&month)
+            || day == 0
+            || day > days_in_month(year, month)
+        {
+            return None;
+        }
+        Some(Date(days_from_civil(year, month, day)))
+    }
+
+    fn today() -> Date {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs() as i64);
+        Date(seconds.div_euclid(86_400))
+    }
+
+    /// January 1st of the current year.
+    fn start_of_year() -> Date {
+        let (year, _, _) = Date::today().civil();
+        Date(days_from_civil(year, 1, 1))
+    }
+
+    fn add_days(self, days: i64) -> Date {
+        Date(self.0 + days)
+    }
+
+    /// Whether `days` consecutive days starting here all end by 9999-12-31.
+    fn span_fits(self, days: usize) -> bool {
+        self.0 + days as i64 - 1 <= days_from_civil(9999, 12, 31)
+    }
+
+    fn civil(self) -> (i64, u32, u32) {
+        civil_from_days(self.0)
+    }
+
+    fn iso(self) -> String {
+        let (y, m, d) = self.civil();
+        format!("{:04}-{:02}-{:02}", y, m, d)
+    }
+
+    fn basic(self) -> String {
+        let (y, m, d) = self.civil();
+        format!("{:04}{:02}{:02}", y, m, d)
+    }
+}
+
+fn is_leap_year(year: i64) -> bool {
+    (year % 4 == 0 && year % 100 != 0) || year % 400 == 0
+}
+
+fn days_in_month(year: i64, month: u32) -> u32 {
+    match month {
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Howard Hinnant's civil calendar algorithms.
+fn days_from_civil(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year - era * 400;
+    let month = month as i64;
+    let doy = (153 * (if month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+pub fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+// ---------------------------------------------------------------------------
+// iCalendar
+// ---------------------------------------------------------------------------
+
+fn ics_escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace('\n', "\\n")
+}
+
+/// Folds a content line to 75 octets as RFC 5545 requires.
+fn ics_line(out: &mut String, line: &str) {
+    let mut width = 0;
+    for c in line.chars() {
+        if width + c.len_utf8() > 75 {
+            out.push_str("\r\n ");
+            width = 1;
+        }
+        out.push(c);
+        width += c.len_utf8();
+    }
+    out.push_str("\r\n");
+}
+
+fn to_ics(plan: &ReadingPlan, start: Date) -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs() as i64);
+    let seconds = now.rem_euclid(86_400);
+    let stamp = format!(
+        "{}T{:02}{:02}{:02}Z",
+        Date(now.div_euclid(86_400)).basic(),
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    );
+
+    let mut ics = String::new();
+    ics_line(&mut ics, "BEGIN:VCALENDAR");
+    ics_line(&mut ics, "VERSION:2.0");
+    ics_line(
+        &mut ics,
+        "PRODID:-//Bible Section Headlines//Reading Plans//EN",
+    );
+    ics_line(&mut ics, "CALSCALE:GREGORIAN");
+    ics_line(&mut ics, &format!("X-WR-CALNAME:{}", ics_escape(plan.name)));
+
+    for day in &plan.days {
+        let date = start.add_days(day.day as i64 - 1);
+        let description: Vec<String> = day
+            .portions
+            .iter()
+            .map(|p| format!("{} {}: {}", p.book, p.chapter, p.headings.join(" • ")))
+            .collect();
+
+        ics_line(&mut ics, "BEGIN:VEVENT");
+        ics_line(
+            &mut ics,
+            &format!(
+                "UID:{}-day-{}-{}@bible-headlines",
+                plan.id,
+                day.day,
+                date.basic()
+            ),
+        );
+        ics_line(&mut ics, &format!("DTSTAMP:{}", stamp));
+        ics_line(&mut ics, &format!("DTSTART;VALUE=DATE:{}", date.basic()));
+        ics_line(&mut ics, "DURATION:P1D");
+        ics_line(
+            &mut ics,
+            &format!(
+                "SUMMARY:{}",
+                ics_escape(&format!("Day {}: {}", day.day, day.summary))
+            ),
+        );
+        ics_line(
+            &mut ics,
+            &format!("DESCRIPTION:{}", ics_escape(&description.join("\n"))),
+        );
+        ics_line(&mut ics, "END:VEVENT");
+    }
+
+    ics_line(&mut ics, "END:VCALENDAR");
+    ics
+}
+
+// ---------------------------------------------------------------------------
+// Handlers
+// ---------------------------------------------------------------------------
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PlanParams {
+    /// First day of the plan as YYYY-MM-DD; defaults to January 1st of this year.
+    start: Option<String>,
+}
+
+impl PlanParams {
+    /// The date of day 1, which must leave room for every day of `plan`.
+    fn start(&self, plan: &ReadingPlan) -> std::result::Result<Date, HttpResponse> {
+        let start = match &self.start {
+            None => Date::start_of_year(),
+            Some(text) => Date::parse(text).ok_or_else(|| {
+                HttpResponse::BadRequest()
+                    .json(json!({ "error": format!("Invalid start date '{}', expected YYYY-MM-DD", text) }))
+            })?,
+        };
+        if !start.span_fits(plan.days.len()) {
+            return Err(HttpResponse::BadRequest().json(json!({
+                "error": format!("'{}' would run past 9999-12-31 from {}", plan.name, start.iso())
+            })));
+        }
+        Ok(start)
+    }
+}
+
+fn find_plan<'a>(
+    data: &'a AppState,
+    id: &str,
+) -> std::result::Result<&'a ReadingPlan, HttpResponse> {
+    data.plans.iter().find(|plan| plan.id == id).ok_or_else(|| {
+        HttpResponse::NotFound().json(json!({ "error": format!("Unknown reading plan '{}'", id) }))
+    })
+}
+
+/// The plan named `id` and the date of its first day.
+fn find_dated_plan<'a>(
+    data: &'a AppState,
+    id: &str,
+    params: &PlanParams,
+) -> std::result::Result<(&'a ReadingPlan, Date), HttpResponse> {
+    let plan = find_plan(data, id)?;
+    Ok((plan, params.start(plan)?))
+}
+
+#[derive(Serialize, ToSchema)]
+struct PlanSummary<'a> {
+    #[serde(flatten)]
+    plan: &'a ReadingPlan,
+    days: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+struct PlanDetail<'a> {
+    id: &'a str,
+    name: &'a str,
+    description: &'a str,
+    /// The date of day 1.
+    start: String,
+    days: Vec<DatedDay<'a>>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct DatedDay<'a> {
+    #[serde(flatten)]
+    day: &'a PlanDay,
+    date: String,
+}
+
+/// The predefined reading plans.
+#[utoipa::path(
+    get,
+    path = "/api/plans",
+    tag = "plans",
+    responses((status = 200, description = "Reading plans", body = [PlanSummary]))
+)]
+pub async fn api_plans(data: web::Data<AppState>) -> Result<HttpResponse> {
+    let plans: Vec<PlanSummary> = data
+        .plans
+        .iter()
+        .map(|plan| PlanSummary {
+            plan,
+            days: plan.days.len(),
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(plans))
+}
+
+/// Every day of a plan, dated from `start`.
+#[utoipa::path(
+    get,
+    path = "/api/plans/{id}",
+    tag = "plans",
+    params(("id" = String, Path, description = "Plan id from `/api/plans`"), PlanParams),
+    responses(
+        (status = 200, description = "The plan with every day's date", body = PlanDetail),
+        (status = 400, description = "Bad start date", body = crate::openapi::ErrorBody),
+        (status = 404, description = "Unknown plan", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_plan(
+    data: web::Data<AppState>,
+    id: web::Path<String>,
+    params: web::Query<PlanParams>,
+) -> Result<HttpResponse> {
+    let (plan, start) = match find_dated_plan(&data, &id, &params) {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    let days: Vec<DatedDay> = plan
+        .days
+        .iter()
+        .map(|day| DatedDay {
+            day,
+            date: start.add_days(day.day as i64 - 1).iso(),
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(PlanDetail {
+        id: plan.id,
+        name: plan.name,
+        description: plan.description,
+        start: start.iso(),
+        days,
+    }))
+}
+
+/// The sections to read on one day of a plan.
+#[utoipa::path(
+    get,
+    path = "/api/plans/{id}/day/{day}",
+    tag = "plans",
+    params(("id" = String, Path, description = "Plan id from `/api/plans`"), ("day" = usize, Path, description = "Day number, from 1"), PlanParams),
+    responses(
+        (status = 200, description = "The day's portions", body = DatedDay),
+        (status = 400, description = "Bad start date", body = crate::openapi::ErrorBody),
+        (status = 404, description = "Unknown plan or day", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_plan_day(
+    data: web::Data<AppState>,
+    path: web::Path<(String, usize)>,
+    params: web::Query<PlanParams>,
+) -> Result<HttpResponse> {
+    let (id, number) = path.into_inner();
+    let (plan, start) = match find_dated_plan(&data, &id, &params) {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    match number.checked_sub(1).and_then(|index| plan.days.get(index)) {
+        Some(day) => Ok(HttpResponse::Ok().json(DatedDay {
+            day,
+            date: start.add_days(day.day as i64 - 1).iso(),
+        })),
+        None => Ok(HttpResponse::NotFound().json(json!({
+            "error": format!("'{}' has days 1 to {}", plan.name, plan.days.len())
+        }))),
+    }
+}
+
+/// A plan as an iCalendar file for calendar apps.
+#[utoipa::path(
+    get,
+    path = "/api/plans/{id}/calendar.ics",
+    tag = "plans",
+    params(("id" = String, Path, description = "Plan id from `/api/plans`"), PlanParams),
+    responses(
+        (status = 200, description = "An iCalendar file with one event per day", content_type = "text/calendar"),
+        (status = 400, description = "Bad start date", body = crate::openapi::ErrorBody),
+        (status = 404, description = "Unknown plan", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_plan_ics(
+    data: web::Data<AppState>,
+    id: web::Path<String>,
+    params: web::Query<PlanParams>,
+) -> Result<HttpResponse> {
+    let (plan, start) = match find_dated_plan(&data, &id, &params) {
+        Ok(found) => found,
+        Err(response) => return Ok(response),
+    };
+
+    Ok(HttpResponse::Ok()
+        .content_type("text/calendar; charset=utf-8")
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}.ics\"", plan.id),
+        ))
+        .body(to_ics(plan, start)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_bible_data;
+
+    #[test]
+    fn dates_parse_and_print() {
+        let date = Date::parse("2024-02-29").expect("leap day");
+        assert_eq!(date.iso(), "2024-02-29");
+        assert_eq!(date.basic(), "20240229");
+        assert_eq!(date.add_days(1).iso(), "2024-03-01");
+        assert_eq!(Date::parse("1970-01-01").map(|d| d.0), Some(0));
+        assert_eq!(
+            Date::parse("0001-01-01").map(Date::iso).as_deref(),
+            Some("0001-01-01")
+        );
+        assert_eq!(
+            Date::parse("9999-12-31").map(Date::basic).as_deref(),
+            Some("99991231")
+        );
+
+        // Every day of a plan must fall by 9999-12-31.
+        let last = Date::parse("9999-12-31").unwrap();
+        assert!(last.span_fits(1));
+        assert!(!last.span_fits(2));
+        assert!(Date::parse("9999-10-03").unwrap().span_fits(90));
+        assert!(!Date::parse("9999-12-01").unwrap().span_fits(90));
+    }
+
+    #[test]
+    fn invalid_dates_are_rejected() {
+        for text in [
+            "2023-02-29",
+            "1900-02-29",
+            "2024-13-01",
+            "2024-04-31",
+            "2024-01-00",
+            "2024-01",
+            "0000-01-01",
+            "10000-01-01",
+            "9000000000000000000-01-01",
+            "-5-01-01",
+            "today",
+        ] {
+            assert!(Date::parse(text).is_none(), "{} should not parse", text);
+        }
+    }
+
+    #[test]
+    fn civil_conversion_round_trips() {
+        for days in [-719_162, -1, 0, 59, 60, 11_016, 2_932_896] {
+            let (year, month, day) = civil_from_days(days);
+            assert_eq!(days_from_civil(year, month, day), days);
+        }
+    }
+
+    #[test]
+    fn ics_lines_fold_at_75_octets() {
+        let mut out = String::new();
+        ics_line(&mut out, &format!("SUMMARY:{}", "é".repeat(60)));
+        for line in out.split("\r\n").filter(|line| !line.is_empty()) {
+            assert!(line.len() <= 75, "{} octets", line.len());
+        }
+        let unfolded = out.trim_end_matches("\r\n").replace("\r\n ", "");
+        assert_eq!(unfolded, format!("SUMMARY:{}", "é".repeat(60)));
+        assert_eq!(ics_escape("a,b;c\\d\ne"), r"a\,b\;c\\d\ne");
+    }
+
+    #[test]
+    fn plans_cover_every_section_once() {
+        let books = get_bible_data();
+        let sections: usize = books
+            .iter()
+            .flat_map(|book| &book.chapters)
+            .map(|chapter| chapter.sections.len())
+            .sum();
+        let plan = ReadingPlan::build_all(&books)
+            .into_iter()
+            .find(|plan| plan.id == "bible-in-a-year")
+            .unwrap();
+        assert_eq!(plan.days.len(), 365);
+        let read: usize = plan
+            .days
+            .iter()
+            .flat_map(|day| &day.portions)
+            .map(|portion| portion.headings.len())
+            .sum();
+        assert_eq!(read, sections);
+        assert!(plan.days[0].summary.starts_with("Genesis 1"));
+    }
+}
@@ -0,0 +1,670 @@
+//! Boolean, field-scoped query language over the loaded books.
+//!
+//! ```text
+//! book:Matthew heading:"parable" -sower testament:new chapter:10..20
+//! (faith OR grace) AND NOT book:Romans
+//! ```
+//!
+//! Terms are combined with an implicit AND. `OR` binds looser than AND,
+//! `NOT`/`-` negates the following term or group, and parentheses group.
+//! Field scopes are `book:`, `heading:`, `testament:` and `chapter:`
+//! (`chapter:5`, `chapter:10..20`, `chapter:..3`, `chapter:40..`).
+//! Text terms are stemmed with the search analyzer, so `heading:parables`
+//! matches "The Parable of the Sower".
+//!
+//! Queries are limited to [`MAX_LENGTH`] characters, [`MAX_TOKENS`] tokens and
+//! [`MAX_DEPTH`] nested parentheses, which keeps parsing and evaluation from
+//! recursing without bound.
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::search::{self, Analyzer};
+use crate::{negotiate, AppState, Book, Testament, PLACEHOLDER_HEADING};
+
+/// Longest query accepted, in characters.
+pub const MAX_LENGTH: usize = 1000;
+/// Most terms, operators and parentheses in a query.
+pub const MAX_TOKENS: usize = 100;
+/// Deepest nesting of parenthesised groups.
+pub const MAX_DEPTH: usize = 32;
+
+/// A syntax or validation error, reported with the character offset it occurred at.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueryError {
+    pub code: &'static str,
+    pub message: String,
+    pub position: usize,
+}
+
+impl QueryError {
+    fn new(code: &'static str, position: usize, message: impl Into<String>) -> Self {
+        QueryError {
+            code,
+            message: message.into(),
+            position,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum TokenKind {
+    LParen,
+    RParen,
+    Minus,
+    Field(String),
+    Word(String),
+    Phrase(String),
+}
+
+#[derive(Debug, Clone)]
+struct Token {
+    kind: TokenKind,
+    position: usize,
+}
+
+fn lex(input: &str) -> std::result::Result<Vec<Token>, QueryError> {
+    let chars: Vec<char> = input.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+
+        if c.is_whitespace() {
+            i += 1;
+        } else if c == '(' {
+            tokens.push(Token {
+                kind: TokenKind::LParen,
+                position: start,
+            });
+            i += 1;
+        } else if c == ')' {
+            tokens.push(Token {
+                kind: TokenKind::RParen,
+                position: start,
+            });
+            i += 1;
+        } else if c == '-' && chars.get(i + 1).is_some_and(|next| !next.is_whitespace()) {
+            tokens.push(Token {
+                kind: TokenKind::Minus,
+                position: start,
+            });
+            i += 1;
+        } else if c == '"' {
+            i += 1;
+            let mut phrase = String::new();
+            while i < chars.len() && chars[i] != '"' {
+                phrase.push(chars[i]);
+                i += 1;
+            }
+            if i == chars.len() {
+                return Err(QueryError::new(
+                    "unterminated_phrase",
+                    start,
+                    "Phrase is missing its closing quote",
+                ));
+            }
+            i += 1;
+            tokens.push(Token {
+                kind: TokenKind::Phrase(phrase),
+                position: start,
+            });
+        } else {
+            let mut word = String::new();
+            while i < chars.len() && !chars[i].is_whitespace() && !"()\"".contains(chars[i]) {
+                if chars[i] == ':' && !word.is_empty() && word.chars().all(char::is_alphabetic) {
+                    break;
+                }
+                word.push(chars[i]);
+                i += 1;
+            }
+            if chars.get(i) == Some(&':') {
+                i += 1;
+                tokens.push(Token {
+                    kind: TokenKind::Field(word.to_lowercase()),
+                    position: start,
+                });
+            } else {
+                tokens.push(Token {
+                    kind: TokenKind::Word(word),
+                    position: start,
+                });
+            }
+        }
+    }
+
+    Ok(tokens)
+}
+
+#[derive(Debug)]
+enum Predicate {
+    /// Stemmed terms that must all appear in the heading (or book name, if `any_field`).
+    Terms {
+        stems: Vec<String>,
+        any_field: bool,
+    },
+    /// Stemmed terms that must appear consecutively in the heading.
+    Phrase(Vec<String>),
+    Book(String),
+    Testament(Testament),
+    Chapter {
+        from: u32,
+        to: u32,
+    },
+    /// A term made only of stop words; it constrains nothing.
+    Always,
+}
+
+#[derive(Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Match(Predicate),
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    end: usize,
+    /// Open parentheses around the current position.
+    depth: usize,
+    analyzer: &'a Analyzer,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_kind(&self) -> Option<&TokenKind> {
+        self.peek().map(|t| &t.kind)
+    }
+
+    fn peek_keyword(&self, keyword: &str) -> bool {
+        matches!(self.peek_kind(), Some(TokenKind::Word(w)) if w == keyword)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn position(&self) -> usize {
+        self.peek().map_or(self.end, |t| t.position)
+    }
+
+    fn parse_or(&mut self) -> std::result::Result<Expr, QueryError> {
+        let mut left = self.parse_and()?;
+        while self.peek_keyword("OR") {
+            self.next();
+            let right = self.parse_and()?;
+            left = Expr::Or(Box::new(left), Box::new(right));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> std::result::Result<Expr, QueryError> {
+        let mut left = self.parse_unary()?;
+        loop {
+            match self.peek_kind() {
+                None | Some(TokenKind::RParen) => break,
+                _ if self.peek_keyword("OR") => break,
+                _ => {
+                    if self.peek_keyword("AND") {
+                        self.next();
+                    }
+                    let right = self.parse_unary()?;
+                    left = Expr::And(Box::new(left), Box::new(right));
+                }
+            }
+        }
+        Ok(left)
+    }
+
+    /// A term or group with any number of `NOT`/`-` prefixes; pairs of
+    /// negations cancel out.
+    fn parse_unary(&mut self) -> std::result::Result<Expr, QueryError> {
+        let mut negated = false;
+        while self.peek_kind() == Some(&TokenKind::Minus) || self.peek_keyword("NOT") {
+            self.next();
+            negated = !negated;
+        }
+        let expr = self.parse_primary()?;
+        Ok(if negated {
+            Expr::Not(Box::new(expr))
+        } else {
+            expr
+        })
+    }
+
+    fn parse_primary(&mut self) -> std::result::Result<Expr, QueryError> {
+        let position = self.position();
+        let Some(token) = self.next() else {
+            return Err(QueryError::new(
+                "unexpected_end",
+                position,
+                "Query ended where a term was expected",
+            ));
+        };
+
+        match token.kind {
+            TokenKind::LParen => {
+                if self.depth == MAX_DEPTH {
+                    return Err(QueryError::new(
+                        "too_deep",
+                        token.position,
+                        format!("Groups are nested more than {} deep", MAX_DEPTH),
+                    ));
+                }
+                self.depth += 1;
+                let inner = self.parse_or()?;
+                self.depth -= 1;
+                match self.next().map(|t| t.kind) {
+                    Some(TokenKind::RParen) => Ok(inner),
+                    _ => Err(QueryError::new(
+                        "unbalanced_parenthesis",
+                        token.position,
+                        "Opening parenthesis is never closed",
+                    )),
+                }
+            }
+            TokenKind::RParen => Err(QueryError::new(
+                "unbalanced_parenthesis",
+                token.position,
+                "Closing parenthesis without a matching opening one",
+            )),
+            TokenKind::Minus => Err(QueryError::new(
+                "unexpected_token",
+                token.position,
+                "Expected a term after '-'",
+            )),
+            TokenKind::Word(word) if word == "AND" || word == "OR" => Err(QueryError::new(
+                "unexpected_token",
+                token.position,
+                format!("Expected a term before '{}'", word),
+            )),
+            TokenKind::Word(word) => Ok(Expr::Match(self.text_predicate(&word, false, true))),
+            TokenKind::Phrase(phrase) => Ok(Expr::Match(self.text_predicate(&phrase, true, true))),
+            TokenKind::Field(field) => self.parse_field(field, token.position),
+        }
+    }
+
+    fn parse_field(
+        &mut self,
+        field: String,
+        position: usize,
+    ) -> std::result::Result<Expr, QueryError> {
+        let value_position = self.position();
+        let (value, phrase) = match self.next().map(|t| t.kind) {
+            Some(TokenKind::Word(w)) => (w, false),
+            Some(TokenKind::Phrase(p)) => (p, true),
+            _ => {
+                return Err(QueryError::new(
+                    "missing_value",
+                    value_position,
+                    format!("Field '{}:' needs a value", field),
+                ))
+            }
+        };
+
+        let predicate = match field.as_str() {
+            "heading" => self.text_predicate(&value, phrase, false),
+            "book" => Predicate::Book(normalize_book_name(&value)),
+            "testament" => match value.to_lowercase().as_str() {
+                "old" | "ot" => Predicate::Testament(Testament::Old),
+                "new" | "nt" => Predicate::Testament(Testament::New),
+                _ => {
+                    return Err(QueryError::new(
+                        "invalid_value",
+                        value_position,
+                        format!("Unknown testament '{}', expected 'old' or 'new'", value),
+                    ))
+                }
+            },
+            "chapter" => parse_chapter_range(&value).ok_or_else(|| {
+                QueryError::new(
+                    "invalid_value",
+                    value_position,
+                    format!(
+                        "Invalid chapter '{}', expected e.g. 5, 10..20, ..3 or 40..",
+                        value
+                    ),
+                )
+            })?,
+            _ => {
+                return Err(QueryError::new(
+                    "unknown_field",
+                    position,
+                    format!(
+                        "Unknown field '{}', expected book, heading, testament or chapter",
+                        field
+                    ),
+                ))
+            }
+        };
+
+        Ok(Expr::Match(predicate))
+    }
+
+    fn text_predicate(&self, text: &str, phrase: bool, any_field: bool) -> Predicate {
+        let stems = self.analyzer.analyze(text);
+        if stems.is_empty() {
+            Predicate::Always
+        } else if phrase && stems.len() > 1 {
+            Predicate::Phrase(stems)
+        } else {
+            Predicate::Terms { stems, any_field }
+        }
+    }
+}
+
+fn parse_chapter_range(value: &str) -> Option<Predicate> {
+    let (from, to) = match value.split_once("..") {
+        Some((from, to)) => {
+            let from = if from.is_empty() {
+                1
+            } else {
+                from.parse().ok()?
+            };
+            let to = if to.is_empty() {
+                u32::MAX
+            } else {
+                to.parse().ok()?
+            };
+            (from, to)
+        }
+        None => {
+            let chapter = value.parse().ok()?;
+            (chapter, chapter)
+        }
+    };
+    (from <= to).then_some(Predicate::Chapter { from, to })
+}
+
+fn normalize_book_name(name: &str) -> String {
+    search::fold(name)
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+/// A parsed query, ready to be evaluated against every section.
+pub struct Query {
+    expr: Expr,
+}
+
+impl Query {
+    pub fn parse(input: &str, analyzer: &Analyzer) -> std::result::Result<Self, QueryError> {
+        let end = input.chars().count();
+        if end > MAX_LENGTH {
+            return Err(QueryError::new(
+                "too_long",
+                MAX_LENGTH,
+                format!("Query is longer than {} characters", MAX_LENGTH),
+            ));
+        }
+        let tokens = lex(input)?;
+        if tokens.is_empty() {
+            return Err(QueryError::new("empty_query", 0, "Query is empty"));
+        }
+        if let Some(token) = tokens.get(MAX_TOKENS) {
+            return Err(QueryError::new(
+                "too_many_terms",
+                token.position,
+                format!("Query has more than {} terms and operators", MAX_TOKENS),
+            ));
+        }
+
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            end,
+            depth: 0,
+            analyzer,
+        };
+        let expr = parser.parse_or()?;
+        if let Some(token) = parser.peek() {
+            return Err(QueryError::new(
+                "unbalanced_parenthesis",
+                token.position,
+                "Closing parenthesis without a matching opening one",
+            ));
+        }
+
+        Ok(Query { expr })
+    }
+
+    /// Returns every section the query matches, in canonical order.
+    pub fn evaluate(&self, books: &[Book], analyzer: &Analyzer) -> Vec<QueryMatch> {
+        let mut matches = Vec::new();
+
+        for book in books {
+            let book_name = normalize_book_name(&book.name);
+            let book_stems = analyzer.analyze(&book.name);
+
+            for chapter in &book.chapters {
+                for section in &chapter.sections {
+                    let heading_stems = if section.heading == PLACEHOLDER_HEADING {
+                        Vec::new()
+                    } else {
+                        analyzer.analyze(&section.heading)
+                    };
+                    let candidate = Candidate {
+                        book,
+                        book_name: &book_name,
+                        book_stems: &book_stems,
+                        chapter: chapter.chapter_number,
+                        heading_stems: &heading_stems,
+                    };
+
+                    if candidate.matches(&self.expr) {
+                        matches.push(QueryMatch {
+                            book: book.name.clone(),
+                            testament: book.testament.clone(),
+                            chapter: chapter.chapter_number,
+                            heading: section.heading.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        matches
+    }
+}
+
+struct Candidate<'a> {
+    book: &'a Book,
+    book_name: &'a str,
+    book_stems: &'a [String],
+    chapter: u32,
+    heading_stems: &'a [String],
+}
+
+impl Candidate<'_> {
+    fn matches(&self, expr: &Expr) -> bool {
+        match expr {
+            Expr::And(left, right) => self.matches(left) && self.matches(right),
+            Expr::Or(left, right) => self.matches(left) || self.matches(right),
+            Expr::Not(inner) => !self.matches(inner),
+            Expr::Match(predicate) => self.matches_predicate(predicate),
+        }
+    }
+
+    fn matches_predicate(&self, predicate: &Predicate) -> bool {
+        match predicate {
+            Predicate::Terms { stems, any_field } => stems.iter().all(|stem| {
+                self.heading_stems.contains(stem) || (*any_field && self.book_stems.contains(stem))
+            }),
+            Predicate::Phrase(stems) => self
+                .heading_stems
+                .windows(stems.len())
+                .any(|window| window == stems.as_slice()),
+            Predicate::Book(name) => self.book_name == name,
+            Predicate::Testament(testament) => self.book.testament == *testament,
+            Predicate::Chapter { from, to } => (*from..=*to).contains(&self.chapter),
+            Predicate::Always => true,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QueryMatch {
+    pub book: String,
+    pub testament: Testament,
+    pub chapter: u32,
+    pub heading: String,
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct QueryParams {
+    /// The query, e.g. `book:genesis AND (flood OR ark)`.
+    q: String,
+    /// Most results returned; 100 by default.
+    limit: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct QueryResponse<'a> {
+    query: &'a str,
+    total: usize,
+    results: Vec<QueryMatch>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct QueryErrorResponse<'a> {
+    query: &'a str,
+    error: QueryError,
+}
+
+/// Boolean, field-scoped heading queries such as `book:genesis AND (flood OR ark)`.
+#[utoipa::path(
+    get,
+    path = "/api/query",
+    tag = "search",
+    params(QueryParams),
+    responses(
+        (status = 200, description = "Headings matching the query, in canonical order", content((QueryResponse = "application/json"), (QueryResponse = "application/cbor"), (QueryResponse = "application/msgpack"))),
+        (status = 400, description = "The query could not be parsed", body = QueryErrorResponse),
+        (status = 406, description = "No supported format is acceptable", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_query(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<QueryParams>,
+) -> Result<HttpResponse> {
+    let analyzer = data.search.analyzer();
+
+    let query = match Query::parse(&params.q, analyzer) {
+        Ok(query) => query,
+        Err(error) => {
+            return Ok(HttpResponse::BadRequest().json(QueryErrorResponse {
+                query: &params.q,
+                error,
+            }))
+        }
+    };
+
+    let mut results = query.evaluate(&data.books, analyzer);
+    let total = results.len();
+    results.truncate(params.limit.unwrap_or(100));
+
+    Ok(negotiate::respond(
+        &req,
+        &QueryResponse {
+            query: &params.q,
+            total,
+            results,
+        },
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::get_bible_data;
+
+    fn parse(input: &str) -> std::result::Result<Query, QueryError> {
+        Query::parse(input, &Analyzer::new(""))
+    }
+
+    fn error(input: &str) -> QueryError {
+        parse(input).err().expect("query should not parse")
+    }
+
+    fn run(input: &str) -> Vec<QueryMatch> {
+        let analyzer = Analyzer::new("");
+        Query::parse(input, &analyzer)
+            .expect("query should parse")
+            .evaluate(&get_bible_data(), &analyzer)
+    }
+
+    #[test]
+    fn fields_and_operators() {
+        let results = run("book:genesis AND (creation OR cain)");
+        assert!(!results.is_empty());
+        assert!(results.iter().all(|m| m.book == "Genesis"));
+
+        let results = run("testament:new chapter:1..2 -book:Matthew");
+        assert!(!results.is_empty());
+        assert!(results
+            .iter()
+            .all(|m| m.testament == Testament::New && m.chapter <= 2 && m.book != "Matthew"));
+    }
+
+    #[test]
+    fn repeated_negations_cancel() {
+        assert_eq!(run("NOT -book:Genesis").len(), run("book:Genesis").len());
+        assert_eq!(run("NOT book:Genesis").len(), run("-book:Genesis").len());
+    }
+
+    #[test]
+    fn syntax_errors_have_positions() {
+        let err = error("(faith OR grace");
+        assert_eq!((err.code, err.position), ("unbalanced_parenthesis", 0));
+        let err = error("faith)");
+        assert_eq!((err.code, err.position), ("unbalanced_parenthesis", 5));
+        let err = error("\"open phrase");
+        assert_eq!(err.code, "unterminated_phrase");
+        let err = error("verse:3");
+        assert_eq!(err.code, "unknown_field");
+        let err = error("chapter:9..2");
+        assert_eq!((err.code, err.position), ("invalid_value", 8));
+        assert_eq!(error("   ").code, "empty_query");
+        assert_eq!(error("faith AND").code, "unexpected_end");
+    }
+
+    #[test]
+    fn nesting_is_limited() {
+        let ok = format!("{}faith{}", "(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert!(parse(&ok).is_ok());
+
+        let err = error(&format!("{}faith", "(".repeat(MAX_DEPTH + 1)));
+        assert_eq!((err.code, err.position), ("too_deep", MAX_DEPTH));
+    }
+
+    #[test]
+    fn length_and_token_count_are_limited() {
+        let err = error(&"(".repeat(2000));
+        assert_eq!(err.code, "too_long");
+
+        let err = error(&"a ".repeat(MAX_TOKENS + 1));
+        assert_eq!((err.code, err.position), ("too_many_terms", 2 * MAX_TOKENS));
+        assert!(parse(&"a ".repeat(MAX_TOKENS)).is_ok());
+    }
+
+    #[test]
+    fn long_negation_chains_do_not_recurse() {
+        let query = format!("{}faith", "-".repeat(MAX_LENGTH - 5));
+        assert_eq!(error(&query).code, "too_many_terms");
+        let query = format!("{}faith", "NOT ".repeat(MAX_TOKENS - 1));
+        assert!(parse(&query).is_ok());
+    }
+}
@@ -0,0 +1,712 @@
+//! Multiple-choice quizzes generated from the loaded headings: which book or
+//! chapter a heading belongs to, and which of two headings comes first.
+//!
+//! Answers stay on the server until the player responds, and scores are kept
+//! per browser session (an HTTP-only `quiz` cookie, in memory only).
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+
+use actix_web::cookie::{Cookie, SameSite};
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use rand::rngs::OsRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::export::{headings, Selection};
+use crate::{layout, AppState, Book};
+
+const QUIZ_COOKIE: &str = "quiz";
+/// Unanswered questions remembered per session; older ones expire.
+const MAX_PENDING: usize = 20;
+/// Sessions kept in memory; the least recently active are dropped first.
+const MAX_SESSIONS: usize = 10_000;
+/// Wrong answers offered alongside the right one.
+const DISTRACTORS: usize = 3;
+/// Random draws tried before concluding a selection cannot make a question.
+const ATTEMPTS: usize = 64;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum QuestionKind {
+    /// Which book a heading is in.
+    Book,
+    /// Which chapter of its book a heading is in.
+    Chapter,
+    /// Which of two headings from the same book comes first.
+    Order,
+}
+
+impl QuestionKind {
+    const ALL: [QuestionKind; 3] = [
+        QuestionKind::Book,
+        QuestionKind::Chapter,
+        QuestionKind::Order,
+    ];
+
+    fn parse(name: &str) -> Option<QuestionKind> {
+        match name.to_ascii_lowercase().as_str() {
+            "book" => Some(QuestionKind::Book),
+            "chapter" => Some(QuestionKind::Chapter),
+            "order" => Some(QuestionKind::Order),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Question {
+    pub id: u64,
+    pub kind: QuestionKind,
+    pub prompt: String,
+    pub choices: Vec<String>,
+    #[serde(skip)]
+    answer: usize,
+    #[serde(skip)]
+    explanation: String,
+}
+
+/// A heading with its place in the selection.
+struct Entry<'a> {
+    book: &'a Book,
+    chapter: u32,
+    heading: &'a str,
+}
+
+/// Draws a random question of `kind` (any kind when `None`) from the
+/// selection, with wrong choices taken from `books`.
+fn generate(selection: &Selection, books: &[Book], kind: Option<QuestionKind>) -> Option<Question> {
+    let entries: Vec<Entry> = selection
+        .books
+        .iter()
+        .flat_map(|(book, chapters)| {
+            chapters.iter().flat_map(move |chapter| {
+                headings(chapter).map(move |heading| Entry {
+                    book,
+                    chapter: chapter.chapter_number,
+                    heading,
+                })
+            })
+        })
+        .collect();
+    if entries.is_empty() {
+        return None;
+    }
+
+    let mut rng = rand::thread_rng();
+    (0..ATTEMPTS).find_map(|_| {
+        let kind = kind.unwrap_or_else(|| QuestionKind::ALL[rng.gen_range(0..3)]);
+        let entry = entries.choose(&mut rng)?;
+        match kind {
+            QuestionKind::Book => book_question(entry, books, &mut rng),
+            QuestionKind::Chapter => chapter_question(entry, &mut rng),
+            QuestionKind::Order => order_question(entry, &entries, &mut rng),
+        }
+    })
+}
+
+fn contains_heading(book: &Book, heading: &str) -> bool {
+    book.chapters
+        .iter()
+        .any(|chapter| headings(chapter).any(|h| h == heading))
+}
+
+fn book_question(entry: &Entry, books: &[Book], rng: &mut impl Rng) -> Option<Question> {
+    // Books from the same testament make for fairer distractors; any book that
+    // also uses the heading would be a second right answer.
+    let others: Vec<&Book> = books
+        .iter()
+        .filter(|book| book.testament == entry.book.testament)
+        .filter(|book| !contains_heading(book, entry.heading))
+        .collect();
+    let mut choices: Vec<&str> = others
+        .choose_multiple(rng, DISTRACTORS)
+        .map(|book| book.name.as_str())
+        .collect();
+    if choices.is_empty() {
+        return None;
+    }
+    choices.push(&entry.book.name);
+    choices.shuffle(rng);
+
+    Some(Question {
+        id: 0,
+        kind: QuestionKind::Book,
+        prompt: format!("Which book tells of '{}'?", entry.heading),
+        answer: choices.iter().position(|c| *c == entry.book.name)?,
+        choices: choices.into_iter().map(str::to_string).collect(),
+        explanation: format!(
+            "'{}' is in {} {}.",
+            entry.heading, entry.book.name, entry.chapter
+        ),
+    })
+}
+
+fn chapter_question(entry: &Entry, rng: &mut impl Rng) -> Option<Question> {
+    let others: Vec<u32> = entry
+        .book
+        .chapters
+        .iter()
+        .filter(|chapter| !headings(chapter).any(|h| h == entry.heading))
+        .map(|chapter| chapter.chapter_number)
+        .collect();
+    let mut numbers: Vec<u32> = others.choose_multiple(rng, DISTRACTORS).copied().collect();
+    if numbers.is_empty() {
+        return None;
+    }
+    numbers.push(entry.chapter);
+    numbers.sort_unstable();
+
+    Some(Question {
+        id: 0,
+        kind: QuestionKind::Chapter,
+        prompt: format!(
+            "In which chapter of {} is '{}'?",
+            entry.book.name, entry.heading
+        ),
+        answer: numbers.iter().position(|n| *n == entry.chapter)?,
+        choices: numbers
+            .iter()
+            .map(|n| format!("{} {}", entry.book.name, n))
+            .collect(),
+        explanation: format!(
+            "'{}' is in {} {}.",
+            entry.heading, entry.book.name, entry.chapter
+        ),
+    })
+}
+
+fn order_question(entry: &Entry, entries: &[Entry], rng: &mut impl Rng) -> Option<Question> {
+    // Only headings used once in the book have an unambiguous position.
+    let unique = |heading: &str| {
+        entry
+            .book
+            .chapters
+            .iter()
+            .flat_map(headings)
+            .filter(|h| *h == heading)
+            .count()
+            == 1
+    };
+    if !unique(entry.heading) {
+        return None;
+    }
+    let others: Vec<&Entry> = entries
+        .iter()
+        .filter(|e| e.book.name == entry.book.name && e.heading != entry.heading)
+        .filter(|e| unique(e.heading))
+        .collect();
+    let other = *others.choose(rng)?;
+
+    let position = |target: &Entry| {
+        entries
+            .iter()
+            .position(|e| e.book.name == target.book.name && e.heading == target.heading)
+    };
+    let (first, second) = if position(entry)? < position(other)? {
+        (entry, other)
+    } else {
+        (other, entry)
+    };
+    let mut choices = [first.heading, second.heading];
+    choices.shuffle(rng);
+
+    Some(Question {
+        id: 0,
+        kind: QuestionKind::Order,
+        prompt: format!("Which comes first in {}?", entry.book.name),
+        answer: choices.iter().position(|c| *c == first.heading)?,
+        choices: choices.iter().map(|c| c.to_string()).collect(),
+        explanation: format!(
+            "'{}' is in {} {}; '{}' is in {} {}.",
+            first.heading,
+            first.book.name,
+            first.chapter,
+            second.heading,
+            second.book.name,
+            second.chapter
+        ),
+    })
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, ToSchema)]
+pub struct Tally {
+    pub answered: u32,
+    pub correct: u32,
+}
+
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct Score {
+    pub answered: u32,
+    pub correct: u32,
+    pub streak: u32,
+    pub best_streak: u32,
+    pub book: Tally,
+    pub chapter: Tally,
+    pub order: Tally,
+}
+
+impl Score {
+    fn record(&mut self, kind: QuestionKind, correct: bool) {
+        let tally = match kind {
+            QuestionKind::Book => &mut self.book,
+            QuestionKind::Chapter => &mut self.chapter,
+            QuestionKind::Order => &mut self.order,
+        };
+        tally.answered += 1;
+        tally.correct += correct as u32;
+        self.answered += 1;
+        if correct {
+            self.correct += 1;
+            self.streak += 1;
+            self.best_streak = self.best_streak.max(self.streak);
+        } else {
+            self.streak = 0;
+        }
+    }
+}
+
+#[derive(Default)]
+struct Session {
+    pending: VecDeque<Question>,
+    score: Score,
+    last_seen: u64,
+}
+
+/// In-memory quiz sessions keyed by the `quiz` cookie.
+#[derive(Default)]
+pub struct QuizSessions {
+    sessions: Mutex<HashMap<String, Session>>,
+    next_id: AtomicU64,
+}
+
+impl QuizSessions {
+    pub fn new() -> Self {
+        QuizSessions::default()
+    }
+
+    fn tick(&self) -> u64 {
+        self.next_id.fetch_add(1, Ordering::Relaxed) + 1
+    }
+
+    /// Remembers `question` for the session `token` (a new session when
+    /// `None`) and returns the token with the question's id filled in.
+    fn ask(&self, token: Option<String>, mut question: Question) -> (String, Question) {
+        question.id = self.tick();
+        let mut sessions = self.sessions.lock().unwrap();
+        let token = match token.filter(|t| sessions.contains_key(t)) {
+            Some(token) => token,
+            None => {
+                if sessions.len() >= MAX_SESSIONS {
+                    let stalest = sessions
+                        .iter()
+                        .min_by_key(|(_, s)| s.last_seen)
+                        .map(|(t, _)| t.clone());
+                    if let Some(stalest) = stalest {
+                        sessions.remove(&stalest);
+                    }
+                }
+                let mut bytes = [0u8; 16];
+                OsRng.fill_bytes(&mut bytes);
+                bytes.iter().map(|b| format!("{:02x}", b)).collect()
+            }
+        };
+
+        let session = sessions.entry(token.clone()).or_default();
+        session.last_seen = question.id;
+        if session.pending.len() >= MAX_PENDING {
+            session.pending.pop_front();
+        }
+        session.pending.push_back(question.clone());
+        (token, question)
+    }
+
+    /// Scores `choice` for a pending question, which is then forgotten.
+    fn answer(
+        &self,
+        token: &str,
+        id: u64,
+        choice: usize,
+    ) -> Result<(Question, Score), HttpResponse> {
+        let last_seen = self.tick();
+        let mut sessions = self.sessions.lock().unwrap();
+        let unknown = || {
+            HttpResponse::NotFound()
+                .json(json!({ "error": "Unknown or already answered question" }))
+        };
+        let session = sessions.get_mut(token).ok_or_else(unknown)?;
+        let index = session
+            .pending
+            .iter()
+            .position(|q| q.id == id)
+            .ok_or_else(unknown)?;
+        if choice >= session.pending[index].choices.len() {
+            return Err(HttpResponse::BadRequest().json(json!({ "error": "No such choice" })));
+        }
+
+        let question = session.pending.remove(index).expect("position is in range");
+        session
+            .score
+            .record(question.kind, choice == question.answer);
+        session.last_seen = last_seen;
+        Ok((question, session.score.clone()))
+    }
+
+    fn score(&self, token: Option<&str>) -> Score {
+        let sessions = self.sessions.lock().unwrap();
+        token
+            .and_then(|t| sessions.get(t))
+            .map(|s| s.score.clone())
+            .unwrap_or_default()
+    }
+
+    fn reset(&self, token: Option<&str>) {
+        let mut sessions = self.sessions.lock().unwrap();
+        if let Some(session) = token.and_then(|t| sessions.get_mut(t)) {
+            session.score = Score::default();
+        }
+    }
+}
+
+fn session_token(req: &HttpRequest) -> Option<String> {
+    req.cookie(QUIZ_COOKIE).map(|c| c.value().to_string())
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct QuestionParams {
+    /// `book`, `chapter` or `order`; a random kind when absent.
+    kind: Option<String>,
+    /// References limiting the quiz, e.g. "Genesis;Exodus"; the whole Bible when absent.
+    #[serde(default)]
+    books: String,
+}
+
+/// A random multiple-choice question about the headings.
+#[utoipa::path(
+    get,
+    path = "/api/quiz/question",
+    tag = "study",
+    params(QuestionParams),
+    responses(
+        (status = 200, description = "A question; sets the `quiz` session cookie", body = Question),
+        (status = 400, description = "Bad kind or references", body = crate::openapi::ErrorBody),
+        (status = 404, description = "The selection is too small for a question", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_question(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<QuestionParams>,
+) -> Result<HttpResponse> {
+    let kind = match params.kind.as_deref().filter(|k| !k.is_empty()) {
+        None => None,
+        Some(name) => match QuestionKind::parse(name) {
+            Some(kind) => Some(kind),
+            None => {
+                return Ok(HttpResponse::BadRequest()
+                    .json(json!({ "error": "kind must be book, chapter or order" })));
+            }
+        },
+    };
+    let selection = match Selection::parse(&data.books, &params.books) {
+        Ok(selection) => selection,
+        Err(err) => return Ok(HttpResponse::BadRequest().json(json!({ "error": err }))),
+    };
+    let Some(question) = generate(&selection, &data.books, kind) else {
+        return Ok(HttpResponse::NotFound().json(json!({
+            "error": format!("No questions can be made from {}", selection.title)
+        })));
+    };
+
+    let (token, question) = data.quiz.ask(session_token(&req), question);
+    let mut response = HttpResponse::Ok().json(&question);
+    let cookie = Cookie::build(QUIZ_COOKIE, token)
+        .path("/")
+        .http_only(true)
+        .same_site(SameSite::Lax)
+        .finish();
+    // A freshly built cookie always forms a valid header value.
+    let _ = response.add_cookie(&cookie);
+    Ok(response)
+}
+
+#[derive(Deserialize, ToSchema)]
+pub struct AnswerRequest {
+    id: u64,
+    choice: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+struct AnswerResult {
+    correct: bool,
+    /// Index of the right choice.
+    answer: usize,
+    explanation: String,
+    score: Score,
+}
+
+/// Answers a question asked in this quiz session.
+#[utoipa::path(
+    post,
+    path = "/api/quiz/answer",
+    tag = "study",
+    request_body = AnswerRequest,
+    responses(
+        (status = 200, description = "Whether the choice was right, and the session's score", body = AnswerResult),
+        (status = 400, description = "No such choice", body = crate::openapi::ErrorBody),
+        (status = 404, description = "Unknown or already answered question", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_answer(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    body: web::Json<AnswerRequest>,
+) -> Result<HttpResponse> {
+    let token = session_token(&req).unwrap_or_default();
+    match data.quiz.answer(&token, body.id, body.choice) {
+        Ok((question, score)) => Ok(HttpResponse::Ok().json(AnswerResult {
+            correct: body.choice == question.answer,
+            answer: question.answer,
+            explanation: question.explanation,
+            score,
+        })),
+        Err(response) => Ok(response),
+    }
+}
+
+/// The quiz session's score.
+#[utoipa::path(
+    get,
+    path = "/api/quiz/score",
+    tag = "study",
+    responses((status = 200, description = "The session's score", body = Score))
+)]
+pub async fn api_score(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok().json(data.quiz.score(session_token(&req).as_deref())))
+}
+
+/// Starts the quiz session's score over.
+#[utoipa::path(
+    post,
+    path = "/api/quiz/reset",
+    tag = "study",
+    responses((status = 200, description = "The cleared score", body = Score))
+)]
+pub async fn api_reset(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    let token = session_token(&req);
+    data.quiz.reset(token.as_deref());
+    Ok(HttpResponse::Ok().json(data.quiz.score(token.as_deref())))
+}
+
+pub async fn quiz_page() -> Result<HttpResponse> {
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .body(layout::page("Heading Quiz", STYLES, BODY, SCRIPT)))
+}
+
+const BODY: &str = r#"
+        <section class="card quiz-settings">
+            <label>Questions
+                <select id="quiz-kind">
+                    <option value="">Mixed</option>
+                    <option value="book">Which book?</option>
+                    <option value="chapter">Which chapter?</option>
+                    <option value="order">Which comes first?</option>
+                </select>
+            </label>
+            <label>Books
+                <input id="quiz-books" type="text" placeholder="Whole Bible, or e.g. Genesis; Exodus">
+            </label>
+            <button type="button" onclick="nextQuestion()">New question</button>
+        </section>
+
+        <section class="card quiz-question">
+            <h2 id="quiz-prompt">Loading…</h2>
+            <div id="quiz-choices" class="quiz-choices"></div>
+            <p id="quiz-feedback" class="quiz-feedback" aria-live="polite"></p>
+            <button type="button" id="quiz-next" onclick="nextQuestion()" hidden>Next question →</button>
+        </section>
+
+        <section class="card quiz-score">
+            <p><strong id="score-correct">0</strong> of <strong id="score-answered">0</strong> correct
+                · streak <strong id="score-streak">0</strong>
+                · best <strong id="score-best">0</strong></p>
+            <button type="button" class="link-button" onclick="resetScore()">Reset score</button>
+        </section>
+"#;
+
+const STYLES: &str = r#"
+        .quiz-settings {
+            display: flex;
+            flex-wrap: wrap;
+            gap: 1rem;
+            align-items: flex-end;
+        }
+
+        .quiz-settings label {
+            display: flex;
+            flex-direction: column;
+            gap: 0.3rem;
+            font-size: 0.85rem;
+            font-weight: 600;
+            color: #4a5568;
+        }
+
+        .quiz-settings input,
+        .quiz-settings select {
+            padding: 0.6rem 0.8rem;
+            border: 2px solid #e2e8f0;
+            border-radius: 8px;
+            font: inherit;
+            font-weight: 400;
+            min-width: 220px;
+        }
+
+        button {
+            padding: 0.6rem 1.2rem;
+            border: none;
+            border-radius: 8px;
+            background: #667eea;
+            color: white;
+            font: inherit;
+            font-weight: 500;
+            cursor: pointer;
+        }
+
+        button:hover {
+            background: #5a67d8;
+        }
+
+        .quiz-choices {
+            display: grid;
+            gap: 0.75rem;
+        }
+
+        .quiz-choices button {
+            text-align: start;
+            background: #f7fafc;
+            color: #2d3748;
+            border: 2px solid #e2e8f0;
+        }
+
+        .quiz-choices button:hover:enabled {
+            border-color: #667eea;
+        }
+
+        .quiz-choices button.right {
+            background: #f0fff4;
+            border-color: #48bb78;
+        }
+
+        .quiz-choices button.wrong {
+            background: #fff5f5;
+            border-color: #f56565;
+        }
+
+        .quiz-feedback {
+            margin: 1rem 0;
+            color: #4a5568;
+            min-height: 1.5em;
+        }
+
+        .quiz-score {
+            display: flex;
+            justify-content: space-between;
+            align-items: center;
+            flex-wrap: wrap;
+            gap: 1rem;
+        }
+
+        .link-button {
+            background: none;
+            color: #667eea;
+            padding: 0;
+        }
+
+        .link-button:hover {
+            background: none;
+            text-decoration: underline;
+        }
+"#;
+
+const SCRIPT: &str = r#"    <script>
+        let current = null;
+
+        function showScore(score) {
+            document.getElementById('score-correct').textContent = score.correct;
+            document.getElementById('score-answered').textContent = score.answered;
+            document.getElementById('score-streak').textContent = score.streak;
+            document.getElementById('score-best').textContent = score.best_streak;
+        }
+
+        function nextQuestion() {
+            const params = new URLSearchParams({
+                kind: document.getElementById('quiz-kind').value,
+                books: document.getElementById('quiz-books').value
+            });
+            const prompt = document.getElementById('quiz-prompt');
+            const choices = document.getElementById('quiz-choices');
+            document.getElementById('quiz-feedback').textContent = '';
+            document.getElementById('quiz-next').hidden = true;
+            choices.replaceChildren();
+
+            fetch('/api/quiz/question?' + params)
+                .then(response => response.json().then(body => response.ok ? body : Promise.reject(body)))
+                .then(question => {
+                    current = question;
+                    prompt.textContent = question.prompt;
+                    question.choices.forEach((choice, index) => {
+                        const button = document.createElement('button');
+                        button.type = 'button';
+                        button.textContent = choice;
+                        button.onclick = () => answer(index);
+                        choices.appendChild(button);
+                    });
+                })
+                .catch(error => {
+                    current = null;
+                    prompt.textContent = error && error.error ? error.error : 'Something went wrong';
+                });
+        }
+
+        function answer(choice) {
+            const buttons = document.querySelectorAll('#quiz-choices button');
+            buttons.forEach(button => button.disabled = true);
+            fetch('/api/quiz/answer', {
+                method: 'POST',
+                headers: { 'Content-Type': 'application/json' },
+                body: JSON.stringify({ id: current.id, choice: choice })
+            })
+                .then(response => response.json().then(body => response.ok ? body : Promise.reject(body)))
+                .then(result => {
+                    buttons[result.answer].classList.add('right');
+                    if (!result.correct) {
+                        buttons[choice].classList.add('wrong');
+                    }
+                    document.getElementById('quiz-feedback').textContent =
+                        (result.correct ? 'Correct! ' : 'Not quite. ') + result.explanation;
+                    document.getElementById('quiz-next').hidden = false;
+                    showScore(result.score);
+                })
+                .catch(error => {
+                    document.getElementById('quiz-feedback').textContent =
+                        error && error.error ? error.error : 'Something went wrong';
+                    document.getElementById('quiz-next').hidden = false;
+                });
+        }
+
+        function resetScore() {
+            fetch('/api/quiz/reset', { method: 'POST' })
+                .then(response => response.json())
+                .then(showScore);
+        }
+
+        fetch('/api/quiz/score').then(response => response.json()).then(showScore);
+        nextQuestion();
+    </script>
+"#;
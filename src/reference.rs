@@ -0,0 +1,251 @@
+//! Parsing of scripture references such as "Genesis", "Gen 1-3", "1 Jn 3:16"
+//! and "Matthew 1:22–23", plus the verse ranges embedded in headings.
+
+use std::fmt;
+
+use crate::canon::{self, BookInfo};
+
+/// A book, optionally narrowed to a chapter range and, within a single
+/// chapter, a verse range.
+#[derive(Debug, Clone, Copy)]
+pub struct Passage {
+    pub book: &'static BookInfo,
+    /// Inclusive chapter range; `None` means the whole book.
+    pub chapters: Option<(u32, u32)>,
+    /// Inclusive verse range within `chapters.0`; only set for single-chapter passages.
+    pub verses: Option<(u32, u32)>,
+}
+
+impl Passage {
+    pub fn parse(text: &str) -> Option<Self> {
+        let text = text.trim();
+        if let Some(book) = canon::lookup(text) {
+            return Some(Passage {
+                book,
+                chapters: None,
+                verses: None,
+            });
+        }
+
+        let split = text.rfind(char::is_whitespace)?;
+        let (book, numbers) = text.split_at(split);
+        let book = canon::lookup(book)?;
+        let (chapters, verses) = parse_numbers(numbers.trim())?;
+
+        Some(Passage {
+            book,
+            chapters: Some(chapters),
+            verses,
+        })
+    }
+
+    /// The first chapter of the passage, or 1 for a whole book.
+    pub fn first_chapter(&self) -> u32 {
+        self.chapters.map_or(1, |(start, _)| start)
+    }
+
+    /// Whether the passage covers any part of a chapter.
+    pub fn includes_chapter(&self, chapter: u32) -> bool {
+        self.chapters
+            .is_none_or(|(start, end)| (start..=end).contains(&chapter))
+    }
+}
+
+impl fmt::Display for Passage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.book.name)?;
+        match (self.chapters, self.verses) {
+            (None, _) => Ok(()),
+            (Some((chapter, _)), Some((start, end))) if start == end => {
+                write!(f, " {}:{}", chapter, start)
+            }
+            (Some((chapter, _)), Some((start, end))) => {
+                write!(f, " {}:{}–{}", chapter, start, end)
+            }
+            (Some((start, end)), None) if start == end => write!(f, " {}", start),
+            (Some((start, end)), None) => write!(f, " {}–{}", start, end),
+        }
+    }
+}
+
+type Range = (u32, u32);
+
+/// Parses "3", "1-3", "3:16", "1:2-6a" or "1:6b-2:11".
+fn parse_numbers(text: &str) -> Option<(Range, Option<Range>)> {
+    let (start, end) = match text.split_once(['-', '–']) {
+        Some((start, end)) => (start, Some(end)),
+        None => (text, None),
+    };
+    let (chapter, verse) = parse_point(start)?;
+
+    let parsed = match (verse, end) {
+        (None, None) => ((chapter, chapter), None),
+        (Some(verse), None) => ((chapter, chapter), Some((verse, verse))),
+        (verse, Some(end)) if end.contains(':') => {
+            let (end_chapter, end_verse) = parse_point(end)?;
+            match (verse, end_verse) {
+                (Some(verse), Some(end_verse)) if end_chapter == chapter => {
+                    ((chapter, chapter), Some((verse, end_verse)))
+                }
+                _ => ((chapter, end_chapter), None),
+            }
+        }
+        (Some(verse), Some(end)) => ((chapter, chapter), Some((verse, leading_number(end)?))),
+        (None, Some(end)) => ((chapter, end.trim().parse().ok()?), None),
+    };
+
+    let ((first, last), verses) = parsed;
+    if first == 0 || first > last || verses.is_some_and(|(s, e)| s > e) {
+        return None;
+    }
+    Some(parsed)
+}
+
+/// Parses "3" or "3:16" into a chapter and optional verse.
+fn parse_point(text: &str) -> Option<(u32, Option<u32>)> {
+    match text.split_once(':') {
+        Some((chapter, verse)) => {
+            Some((chapter.trim().parse().ok()?, Some(leading_number(verse)?)))
+        }
+        None => Some((text.trim().parse().ok()?, None)),
+    }
+}
+
+/// Parses the digits at the start of a verse number, ignoring part letters ("6a").
+fn leading_number(text: &str) -> Option<u32> {
+    let digits: String = text
+        .trim()
+        .chars()
+        .take_while(char::is_ascii_digit)
+        .collect();
+    digits.parse().ok()
+}
+
+/// Parses a trailing verse range such as "(1:2–6a)" or "(2:12)" from a heading.
+/// Ranges that run into a later chapter extend to the end of this one.
+pub fn heading_range(heading: &str, chapter: u32) -> Option<(u32, u32)> {
+    let inner = heading.trim_end().strip_suffix(')')?;
+    let inner = &inner[inner.rfind('(')? + 1..];
+    let (start, end) = match inner.split_once(['–', '-']) {
+        Some((start, end)) => (start, Some(end)),
+        None => (inner, None),
+    };
+
+    let (start_chapter, start_verse) = start.split_once(':')?;
+    if start_chapter.trim().parse::<u32>().ok()? != chapter {
+        return None;
+    }
+    let start_verse = leading_number(start_verse)?;
+
+    let end_verse = match end {
+        None => start_verse,
+        Some(end) => match end.split_once(':') {
+            Some((end_chapter, end_verse)) => {
+                if end_chapter.trim().parse::<u32>().ok()? > chapter {
+                    u32::MAX
+                } else {
+                    leading_number(end_verse)?
+                }
+            }
+            None => leading_number(end)?,
+        },
+    };
+
+    (start_verse <= end_verse).then_some((start_verse, end_verse))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(text: &str) -> Option<(&'static str, Option<Range>, Option<Range>)> {
+        Passage::parse(text).map(|p| (p.book.name, p.chapters, p.verses))
+    }
+
+    #[test]
+    fn whole_books_and_chapters() {
+        assert_eq!(parse("Genesis"), Some(("Genesis", None, None)));
+        assert_eq!(parse(" 1 Jn "), Some(("1 John", None, None)));
+        assert_eq!(parse("Gen 3"), Some(("Genesis", Some((3, 3)), None)));
+        assert_eq!(parse("Gen 1-3"), Some(("Genesis", Some((1, 3)), None)));
+        assert_eq!(
+            parse("Song of Solomon 2–4"),
+            Some(("Song of Solomon", Some((2, 4)), None))
+        );
+    }
+
+    #[test]
+    fn verses_and_cross_chapter_ranges() {
+        assert_eq!(
+            parse("1 Jn 3:16"),
+            Some(("1 John", Some((3, 3)), Some((16, 16))))
+        );
+        assert_eq!(
+            parse("Matthew 1:22–23"),
+            Some(("Matthew", Some((1, 1)), Some((22, 23))))
+        );
+        assert_eq!(
+            parse("Gen 1:2-6a"),
+            Some(("Genesis", Some((1, 1)), Some((2, 6))))
+        );
+        assert_eq!(
+            parse("Gen 1:3-1:5"),
+            Some(("Genesis", Some((1, 1)), Some((3, 5))))
+        );
+        assert_eq!(
+            parse("Gen 1:6b-2:11"),
+            Some(("Genesis", Some((1, 2)), None))
+        );
+    }
+
+    #[test]
+    fn malformed_references_are_rejected() {
+        for text in [
+            "",
+            "Hezekiah 3",
+            "Gen 0",
+            "Gen 3-1",
+            "Gen 1:5-3",
+            "Gen x",
+            "Gen 99999999999",
+        ] {
+            assert!(
+                Passage::parse(text).is_none(),
+                "{:?} should not parse",
+                text
+            );
+        }
+    }
+
+    #[test]
+    fn passages_display_with_en_dashes() {
+        let show = |text| Passage::parse(text).unwrap().to_string();
+        assert_eq!(show("gen"), "Genesis");
+        assert_eq!(show("Gen 1-3"), "Genesis 1–3");
+        assert_eq!(show("Gen 2"), "Genesis 2");
+        assert_eq!(show("Jn 3:16"), "John 3:16");
+        assert_eq!(show("Jn 3:16-18"), "John 3:16–18");
+    }
+
+    #[test]
+    fn chapter_membership() {
+        let passage = Passage::parse("Gen 2-4").unwrap();
+        assert!(!passage.includes_chapter(1));
+        assert!(passage.includes_chapter(4));
+        assert_eq!(passage.first_chapter(), 2);
+        assert!(Passage::parse("Gen").unwrap().includes_chapter(50));
+    }
+
+    #[test]
+    fn heading_ranges() {
+        assert_eq!(heading_range("The Creation (1:2–6a)", 1), Some((2, 6)));
+        assert_eq!(heading_range("Jesus Is Born (2:12)", 2), Some((12, 12)));
+        assert_eq!(
+            heading_range("Into the Next (1:6b-2:11)", 1),
+            Some((6, u32::MAX))
+        );
+        assert_eq!(heading_range("Wrong Chapter (3:1-4)", 1), None);
+        assert_eq!(heading_range("No Range", 1), None);
+        assert_eq!(heading_range("Backwards (1:9-2)", 1), None);
+    }
+}
@@ -0,0 +1,662 @@
+//! Linguistic search over section headings.
+//!
+//! Headings are run through a small analysis pipeline (tokenize, fold case and
+//! diacritics, drop stop words, Porter-stem) and stored in an inverted index. Queries go
+//! through the same pipeline and are expanded with a synonym thesaurus, so
+//! "parables" finds "The Parable of the Sower" and "resurrection" finds
+//! "He Is Risen".
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::config::DataPath;
+use crate::{negotiate, translations, AppState, Book, Testament, PLACEHOLDER_HEADING};
+
+const DEFAULT_SYNONYMS: &str = include_str!("../data/synonyms.txt");
+
+const STOP_WORDS: &[&str] = &[
+    "a", "about", "all", "am", "an", "and", "are", "as", "at", "be", "been", "but", "by", "for",
+    "from", "had", "has", "have", "he", "her", "him", "his", "i", "in", "into", "is", "it", "its",
+    "me", "my", "of", "on", "or", "our", "she", "that", "the", "their", "them", "then", "there",
+    "they", "this", "to", "up", "us", "was", "we", "were", "what", "when", "which", "who", "will",
+    "with", "you", "your",
+];
+
+/// Weight of a query term that matched through the thesaurus rather than directly.
+const SYNONYM_WEIGHT: f64 = 0.5;
+/// Weight of a match on the book name rather than the heading itself.
+const BOOK_FIELD_WEIGHT: f64 = 0.5;
+
+/// Arabic tatweel, a stretching stroke with no meaning of its own.
+const TATWEEL: char = '\u{0640}';
+
+/// Normalizes text for matching: canonical composition (NFC) with every
+/// combining mark removed, which folds Latin accents ("Éxodo" → "exodo"),
+/// strips Hebrew niqqud and cantillation and Arabic harakat, then lowercases.
+/// The page script's `fold` mirrors this for client-side filtering.
+pub fn fold(text: &str) -> String {
+    text.nfd()
+        .filter(|c| !is_combining_mark(*c) && *c != TATWEEL)
+        .nfc()
+        .collect::<String>()
+        .to_lowercase()
+}
+
+/// Splits text into folded word tokens, dropping possessive suffixes.
+pub fn tokenize(text: &str) -> Vec<String> {
+    fold(text)
+        .split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '’'))
+        .map(|word| {
+            let word = word.trim_matches(|c| c == '\'' || c == '’');
+            let word = word
+                .strip_suffix("'s")
+                .or_else(|| word.strip_suffix("’s"))
+                .unwrap_or(word);
+            word.replace(['\'', '’'], "")
+        })
+        .filter(|word| !word.is_empty())
+        .collect()
+}
+
+pub fn is_stop_word(token: &str) -> bool {
+    STOP_WORDS.contains(&token)
+}
+
+/// The tokenization pipeline shared by indexing and querying.
+#[derive(Clone)]
+pub struct Analyzer {
+    synonyms: HashMap<String, Vec<String>>,
+}
+
+impl Analyzer {
+    /// Builds an analyzer from a thesaurus in the `data/synonyms.txt` format.
+    pub fn new(thesaurus: &str) -> Self {
+        let mut synonyms: HashMap<String, Vec<String>> = HashMap::new();
+
+        for line in thesaurus.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut group: Vec<String> = Vec::new();
+            for word in line.split(',') {
+                for token in tokenize(word) {
+                    let stemmed = stem(&token);
+                    if !group.contains(&stemmed) {
+                        group.push(stemmed);
+                    }
+                }
+            }
+
+            for member in &group {
+                let entry = synonyms.entry(member.clone()).or_default();
+                for other in &group {
+                    if other != member && !entry.contains(other) {
+                        entry.push(other.clone());
+                    }
+                }
+            }
+        }
+
+        Analyzer { synonyms }
+    }
+
+    /// Loads the thesaurus from `path`, falling back to the bundled file.
+    pub fn load(path: &DataPath) -> Self {
+        match path.read("synonyms") {
+            Some(thesaurus) => Analyzer::new(&thesaurus),
+            None => Analyzer::new(DEFAULT_SYNONYMS),
+        }
+    }
+
+    /// Tokenizes, removes stop words and stems.
+    pub fn analyze(&self, text: &str) -> Vec<String> {
+        tokenize(text)
+            .into_iter()
+            .filter(|token| !is_stop_word(token))
+            .map(|token| stem(&token))
+            .collect()
+    }
+
+    /// Returns the term itself plus its synonyms, each with a match weight.
+    pub fn expand(&self, term: &str) -> Vec<(String, f64)> {
+        let mut expanded = vec![(term.to_string(), 1.0)];
+        if let Some(synonyms) = self.synonyms.get(term) {
+            expanded.extend(synonyms.iter().map(|s| (s.clone(), SYNONYM_WEIGHT)));
+        }
+        expanded
+    }
+}
+
+struct Document {
+    book: String,
+    testament: Testament,
+    chapter: u32,
+    heading: String,
+    heading_terms: HashSet<String>,
+    book_terms: HashSet<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchHit {
+    pub book: String,
+    pub testament: Testament,
+    pub chapter: u32,
+    pub heading: String,
+    pub score: f64,
+}
+
+/// Inverted index over every section heading of every book.
+pub struct SearchIndex {
+    analyzer: Analyzer,
+    documents: Vec<Document>,
+    postings: HashMap<String, Vec<usize>>,
+}
+
+impl SearchIndex {
+    pub fn build(books: &[Book], analyzer: Analyzer) -> Self {
+        let mut documents = Vec::new();
+        let mut postings: HashMap<String, Vec<usize>> = HashMap::new();
+
+        for book in books {
+            let book_terms: HashSet<String> = analyzer.analyze(&book.name).into_iter().collect();
+
+            for chapter in &book.chapters {
+                for section in &chapter.sections {
+                    // Placeholders are still findable by book name, but their
+                    // generic text should not match every "section" query.
+                    let heading_terms: HashSet<String> = if section.heading == PLACEHOLDER_HEADING {
+                        HashSet::new()
+                    } else {
+                        analyzer.analyze(&section.heading).into_iter().collect()
+                    };
+
+                    let id = documents.len();
+                    for term in heading_terms.union(&book_terms) {
+                        postings.entry(term.clone()).or_default().push(id);
+                    }
+
+                    documents.push(Document {
+                        book: book.name.clone(),
+                        testament: book.testament.clone(),
+                        chapter: chapter.chapter_number,
+                        heading: section.heading.clone(),
+                        heading_terms,
+                        book_terms: book_terms.clone(),
+                    });
+                }
+            }
+        }
+
+        SearchIndex {
+            analyzer,
+            documents,
+            postings,
+        }
+    }
+
+    pub fn analyzer(&self) -> &Analyzer {
+        &self.analyzer
+    }
+
+    fn idf(&self, term: &str) -> f64 {
+        let df = self.postings.get(term).map_or(0, Vec::len) as f64;
+        (1.0 + self.documents.len() as f64 / (1.0 + df)).ln()
+    }
+
+    /// Returns sections matching every query term, best matches first.
+    pub fn search(&self, query: &str) -> Vec<SearchHit> {
+        let terms = self.analyzer.analyze(query);
+        if terms.is_empty() {
+            return Vec::new();
+        }
+
+        let mut scores: HashMap<usize, f64> = HashMap::new();
+        for (position, term) in terms.iter().enumerate() {
+            let mut term_scores: HashMap<usize, f64> = HashMap::new();
+
+            for (candidate, weight) in self.analyzer.expand(term) {
+                let Some(ids) = self.postings.get(&candidate) else {
+                    continue;
+                };
+                let idf = self.idf(&candidate);
+                for &id in ids {
+                    let document = &self.documents[id];
+                    let field_weight = if document.heading_terms.contains(&candidate) {
+                        1.0
+                    } else if document.book_terms.contains(&candidate) {
+                        BOOK_FIELD_WEIGHT
+                    } else {
+                        continue;
+                    };
+                    let score = weight * field_weight * idf;
+                    let best = term_scores.entry(id).or_insert(0.0);
+                    if score > *best {
+                        *best = score;
+                    }
+                }
+            }
+
+            // Every query term must match: intersect with what earlier terms matched.
+            if position == 0 {
+                scores = term_scores;
+            } else {
+                scores = scores
+                    .into_iter()
+                    .filter_map(|(id, score)| term_scores.get(&id).map(|s| (id, score + s)))
+                    .collect();
+            }
+            if scores.is_empty() {
+                break;
+            }
+        }
+
+        let mut ranked: Vec<(usize, f64)> = scores.into_iter().collect();
+        ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(&b.0)));
+
+        ranked
+            .into_iter()
+            .map(|(id, score)| {
+                let document = &self.documents[id];
+                SearchHit {
+                    book: document.book.clone(),
+                    testament: document.testament.clone(),
+                    chapter: document.chapter,
+                    heading: document.heading.clone(),
+                    score: (score * 1000.0).round() / 1000.0,
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchParams {
+    /// Words to search for.
+    q: String,
+    /// Most results returned; 50 by default.
+    limit: Option<usize>,
+}
+
+#[derive(Serialize, ToSchema)]
+struct SearchResponse<'a> {
+    query: &'a str,
+    total: usize,
+    results: Vec<SearchHit>,
+}
+
+/// Ranked section headings matching every search term.
+#[utoipa::path(
+    get,
+    path = "/api/search",
+    tag = "search",
+    params(SearchParams, ("headings" = Option<String>, Query, description = "Heading set to search")),
+    responses(
+        (status = 200, description = "Matching headings, best first", content((SearchResponse = "application/json"), (SearchResponse = "application/cbor"), (SearchResponse = "application/msgpack"))),
+        (status = 406, description = "No supported format is acceptable", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_search(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<SearchParams>,
+) -> Result<HttpResponse> {
+    // `?headings=` (or the page's remembered choice) searches another heading set.
+    let index =
+        translations::requested(&req, &data.translations).map_or(&*data.search, |set| &set.search);
+    let mut results = index.search(&params.q);
+    let total = results.len();
+    results.truncate(params.limit.unwrap_or(50));
+
+    Ok(negotiate::respond(
+        &req,
+        &SearchResponse {
+            query: &params.q,
+            total,
+            results,
+        },
+    ))
+}
+
+// ---------------------------------------------------------------------------
+// Porter stemmer (M. F. Porter, "An algorithm for suffix stripping", 1980)
+// ---------------------------------------------------------------------------
+
+/// Reduces an English word to its Porter stem. Non-ASCII words are returned unchanged.
+pub fn stem(word: &str) -> String {
+    if word.len() <= 2 || !word.is_ascii() {
+        return word.to_string();
+    }
+
+    let mut w: Vec<u8> = word.as_bytes().to_vec();
+    step1a(&mut w);
+    step1b(&mut w);
+    step1c(&mut w);
+    step2(&mut w);
+    step3(&mut w);
+    step4(&mut w);
+    step5(&mut w);
+
+    String::from_utf8(w).unwrap_or_else(|_| word.to_string())
+}
+
+fn is_consonant(w: &[u8], i: usize) -> bool {
+    match w[i] {
+        b'a' | b'e' | b'i' | b'o' | b'u' => false,
+        b'y' => i == 0 || !is_consonant(w, i - 1),
+        _ => true,
+    }
+}
+
+/// The number of vowel-consonant sequences in `w`, Porter's `m`.
+fn measure(w: &[u8]) -> usize {
+    let n = w.len();
+    let mut i = 0;
+    let mut m = 0;
+
+    while i < n && is_consonant(w, i) {
+        i += 1;
+    }
+    loop {
+        while i < n && !is_consonant(w, i) {
+            i += 1;
+        }
+        if i >= n {
+            return m;
+        }
+        while i < n && is_consonant(w, i) {
+            i += 1;
+        }
+        m += 1;
+        if i >= n {
+            return m;
+        }
+    }
+}
+
+fn has_vowel(w: &[u8]) -> bool {
+    (0..w.len()).any(|i| !is_consonant(w, i))
+}
+
+fn ends_double_consonant(w: &[u8]) -> bool {
+    let n = w.len();
+    n >= 2 && w[n - 1] == w[n - 2] && is_consonant(w, n - 1)
+}
+
+/// consonant-vowel-consonant ending where the last consonant is not w, x or y.
+fn ends_cvc(w: &[u8]) -> bool {
+    let n = w.len();
+    n >= 3
+        && is_consonant(w, n - 3)
+        && !is_consonant(w, n - 2)
+        && is_consonant(w, n - 1)
+        && !matches!(w[n - 1], b'w' | b'x' | b'y')
+}
+
+fn replace_suffix(w: &mut Vec<u8>, suffix: &str, replacement: &str) {
+    w.truncate(w.len() - suffix.len());
+    w.extend_from_slice(replacement.as_bytes());
+}
+
+/// Applies the first rule whose suffix matches, if the remaining stem has `m > min_measure`.
+fn apply_rules(w: &mut Vec<u8>, rules: &[(&str, &str)], min_measure: usize) {
+    for (suffix, replacement) in rules {
+        if w.ends_with(suffix.as_bytes()) {
+            if measure(&w[..w.len() - suffix.len()]) > min_measure {
+                replace_suffix(w, suffix, replacement);
+            }
+            return;
+        }
+    }
+}
+
+fn step1a(w: &mut Vec<u8>) {
+    if w.ends_with(b"sses") {
+        replace_suffix(w, "sses", "ss");
+    } else if w.ends_with(b"ies") {
+        replace_suffix(w, "ies", "i");
+    } else if !w.ends_with(b"ss") && w.ends_with(b"s") {
+        w.pop();
+    }
+}
+
+fn step1b(w: &mut Vec<u8>) {
+    if w.ends_with(b"eed") {
+        if measure(&w[..w.len() - 3]) > 0 {
+            w.pop();
+        }
+        return;
+    }
+
+    let suffix_len = if w.ends_with(b"ed") {
+        2
+    } else if w.ends_with(b"ing") {
+        3
+    } else {
+        return;
+    };
+    if !has_vowel(&w[..w.len() - suffix_len]) {
+        return;
+    }
+    w.truncate(w.len() - suffix_len);
+
+    if w.ends_with(b"at") || w.ends_with(b"bl") || w.ends_with(b"iz") {
+        w.push(b'e');
+    } else if ends_double_consonant(w) && !matches!(w[w.len() - 1], b'l' | b's' | b'z') {
+        w.pop();
+    } else if measure(w) == 1 && ends_cvc(w) {
+        w.push(b'e');
+    }
+}
+
+fn step1c(w: &mut [u8]) {
+    let n = w.len();
+    if w[n - 1] == b'y' && has_vowel(&w[..n - 1]) {
+        w[n - 1] = b'i';
+    }
+}
+
+fn step2(w: &mut Vec<u8>) {
+    const RULES: &[(&str, &str)] = &[
+        ("ational", "ate"),
+        ("tional", "tion"),
+        ("enci", "ence"),
+        ("anci", "ance"),
+        ("izer", "ize"),
+        ("abli", "able"),
+        ("alli", "al"),
+        ("entli", "ent"),
+        ("eli", "e"),
+        ("ousli", "ous"),
+        ("ization", "ize"),
+        ("ation", "ate"),
+        ("ator", "ate"),
+        ("alism", "al"),
+        ("iveness", "ive"),
+        ("fulness", "ful"),
+        ("ousness", "ous"),
+        ("aliti", "al"),
+        ("iviti", "ive"),
+        ("biliti", "ble"),
+    ];
+    apply_rules(w, RULES, 0);
+}
+
+fn step3(w: &mut Vec<u8>) {
+    const RULES: &[(&str, &str)] = &[
+        ("icate", "ic"),
+        ("ative", ""),
+        ("alize", "al"),
+        ("iciti", "ic"),
+        ("ical", "ic"),
+        ("ful", ""),
+        ("ness", ""),
+    ];
+    apply_rules(w, RULES, 0);
+}
+
+fn step4(w: &mut Vec<u8>) {
+    const SUFFIXES: &[&str] = &[
+        "al", "ance", "ence", "er", "ic", "able", "ible", "ant", "ement", "ment", "ent", "ion",
+        "ou", "ism", "ate", "iti", "ous", "ive", "ize",
+    ];
+
+    // Prefer the longest matching suffix, e.g. "ement" over "ment" over "ent".
+    let Some(suffix) = SUFFIXES
+        .iter()
+        .filter(|suffix| w.ends_with(suffix.as_bytes()))
+        .max_by_key(|suffix| suffix.len())
+    else {
+        return;
+    };
+
+    let stem = &w[..w.len() - suffix.len()];
+    if measure(stem) <= 1 {
+        return;
+    }
+    if *suffix == "ion" && !matches!(stem.last(), Some(b's') | Some(b't')) {
+        return;
+    }
+    w.truncate(stem.len());
+}
+
+fn step5(w: &mut Vec<u8>) {
+    if w.ends_with(b"e") {
+        let stem = &w[..w.len() - 1];
+        let m = measure(stem);
+        if m > 1 || (m == 1 && !ends_cvc(stem)) {
+            w.pop();
+        }
+    }
+
+    if measure(w) > 1 && w.ends_with(b"ll") {
+        w.pop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Chapter, SectionHeading};
+
+    fn book(name: &str, testament: Testament, headings: &[&str]) -> Book {
+        Book {
+            name: name.to_string(),
+            testament,
+            chapters: vec![Chapter {
+                chapter_number: 1,
+                sections: headings
+                    .iter()
+                    .map(|heading| SectionHeading {
+                        heading: heading.to_string(),
+                    })
+                    .collect(),
+            }],
+        }
+    }
+
+    fn index(thesaurus: &str) -> SearchIndex {
+        let books = [
+            book(
+                "Matthew",
+                Testament::New,
+                &["The Parable of the Sower", "He Is Risen"],
+            ),
+            book("Luke", Testament::New, &["The Parable of the Lost Sheep"]),
+            book("Genesis", Testament::Old, &[PLACEHOLDER_HEADING]),
+        ];
+        SearchIndex::build(&books, Analyzer::new(thesaurus))
+    }
+
+    fn headings(hits: &[SearchHit]) -> Vec<&str> {
+        hits.iter().map(|hit| hit.heading.as_str()).collect()
+    }
+
+    #[test]
+    fn porter_stems() {
+        for (word, expected) in [
+            ("caresses", "caress"),
+            ("ponies", "poni"),
+            ("ties", "ti"),
+            ("cats", "cat"),
+            ("feed", "feed"),
+            ("agreed", "agre"),
+            ("plastered", "plaster"),
+            ("motoring", "motor"),
+            ("sing", "sing"),
+            ("conflated", "conflat"),
+            ("troubled", "troubl"),
+            ("sized", "size"),
+            ("hopping", "hop"),
+            ("falling", "fall"),
+            ("filing", "file"),
+            ("happy", "happi"),
+            ("relational", "relat"),
+            ("hopefulness", "hope"),
+            ("parables", "parabl"),
+            ("parable", "parabl"),
+            ("resurrection", "resurrect"),
+        ] {
+            assert_eq!(stem(word), expected, "stem of {}", word);
+        }
+    }
+
+    #[test]
+    fn short_and_non_ascii_words_are_kept() {
+        assert_eq!(stem("is"), "is");
+        assert_eq!(stem("señor"), "señor");
+    }
+
+    #[test]
+    fn folding_and_tokenizing() {
+        assert_eq!(fold("Éxodo"), "exodo");
+        assert_eq!(fold("בְּרֵאשִׁית"), "בראשית");
+        assert_eq!(
+            tokenize("God’s Covenant with Noah's Sons"),
+            ["god", "covenant", "with", "noah", "sons"]
+        );
+        assert_eq!(
+            Analyzer::new("").analyze("The Parables of the Kingdom"),
+            ["parabl", "kingdom"]
+        );
+    }
+
+    #[test]
+    fn every_term_must_match() {
+        let index = index("");
+        assert_eq!(
+            headings(&index.search("parables")),
+            ["The Parable of the Sower", "The Parable of the Lost Sheep"]
+        );
+        assert_eq!(
+            headings(&index.search("parable sheep")),
+            ["The Parable of the Lost Sheep"]
+        );
+        assert!(index.search("parable goat").is_empty());
+        assert!(index.search("the of").is_empty());
+    }
+
+    #[test]
+    fn synonyms_match_with_less_weight() {
+        let index = index("resurrection, risen\n# comment\n");
+        let hits = index.search("resurrection");
+        assert_eq!(headings(&hits), ["He Is Risen"]);
+        let direct = index.search("risen");
+        assert!(direct[0].score > hits[0].score);
+    }
+
+    #[test]
+    fn book_names_match_placeholders_but_section_does_not() {
+        let index = index("");
+        assert_eq!(headings(&index.search("genesis")), [PLACEHOLDER_HEADING]);
+        assert!(index.search("section headings").is_empty());
+        let hits = index.search("matthew");
+        assert!(hits.iter().all(|hit| hit.book == "Matthew"));
+    }
+}
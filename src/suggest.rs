@@ -0,0 +1,219 @@
+//! Search-as-you-type suggestions backed by a prefix trie.
+//!
+//! The trie is built once at startup from book names, their abbreviations and
+//! every section heading. Headings are also reachable from each word after the
+//! first, so typing "sower" suggests "The Parable of the Sower".
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::{canon, negotiate, search, AppState, Book, PLACEHOLDER_HEADING};
+
+const DEFAULT_LIMIT: usize = 8;
+const MAX_LIMIT: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum SuggestionKind {
+    Book,
+    Abbreviation,
+    Heading,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Suggestion {
+    pub text: String,
+    pub kind: SuggestionKind,
+    pub book: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub chapter: Option<u32>,
+}
+
+/// A suggestion reachable from a trie node. Keys that start at the beginning
+/// of the suggestion text rank ahead of keys that start mid-title.
+#[derive(Clone, Copy)]
+struct Entry {
+    suggestion: usize,
+    from_start: bool,
+}
+
+#[derive(Default)]
+struct TrieNode {
+    children: BTreeMap<char, TrieNode>,
+    entries: Vec<Entry>,
+}
+
+pub struct SuggestionTrie {
+    root: TrieNode,
+    suggestions: Vec<Suggestion>,
+}
+
+fn normalize(text: &str) -> String {
+    search::fold(text)
+}
+
+impl SuggestionTrie {
+    pub fn build(books: &[Book]) -> Self {
+        let mut trie = SuggestionTrie {
+            root: TrieNode::default(),
+            suggestions: Vec::new(),
+        };
+
+        for book in books {
+            trie.insert(
+                Suggestion {
+                    text: book.name.clone(),
+                    kind: SuggestionKind::Book,
+                    book: book.name.clone(),
+                    chapter: None,
+                },
+                false,
+            );
+
+            if let Some(info) = canon::info(&book.name) {
+                for abbreviation in info.abbreviations {
+                    trie.insert(
+                        Suggestion {
+                            text: abbreviation.to_string(),
+                            kind: SuggestionKind::Abbreviation,
+                            book: book.name.clone(),
+                            chapter: None,
+                        },
+                        false,
+                    );
+                }
+            }
+
+            for chapter in &book.chapters {
+                for section in &chapter.sections {
+                    if section.heading == PLACEHOLDER_HEADING {
+                        continue;
+                    }
+                    trie.insert(
+                        Suggestion {
+                            text: section.heading.clone(),
+                            kind: SuggestionKind::Heading,
+                            book: book.name.clone(),
+                            chapter: Some(chapter.chapter_number),
+                        },
+                        true,
+                    );
+                }
+            }
+        }
+
+        trie
+    }
+
+    fn insert(&mut self, suggestion: Suggestion, every_word: bool) {
+        let id = self.suggestions.len();
+        let key = normalize(&suggestion.text);
+        self.suggestions.push(suggestion);
+
+        let mut starts = vec![0];
+        if every_word {
+            starts.extend(
+                key.char_indices()
+                    .filter(|&(_, c)| c == ' ')
+                    .map(|(i, _)| i + 1)
+                    .filter(|&i| i < key.len()),
+            );
+        }
+
+        for start in starts {
+            let mut node = &mut self.root;
+            for c in key[start..].chars() {
+                node = node.children.entry(c).or_default();
+            }
+            node.entries.push(Entry {
+                suggestion: id,
+                from_start: start == 0,
+            });
+        }
+    }
+
+    /// Returns up to `limit` completions for `prefix`: books first, then
+    /// abbreviations, then headings, preferring shorter and title-initial matches.
+    pub fn complete(&self, prefix: &str, limit: usize) -> Vec<&Suggestion> {
+        let prefix = normalize(prefix.trim_start());
+        if prefix.is_empty() {
+            return Vec::new();
+        }
+
+        let mut node = &self.root;
+        for c in prefix.chars() {
+            match node.children.get(&c) {
+                Some(child) => node = child,
+                None => return Vec::new(),
+            }
+        }
+
+        let mut found: Vec<Entry> = Vec::new();
+        collect(node, &mut found);
+
+        found.sort_by_key(|entry| {
+            let suggestion = &self.suggestions[entry.suggestion];
+            (
+                suggestion.kind,
+                !entry.from_start,
+                suggestion.text.len(),
+                entry.suggestion,
+            )
+        });
+
+        let mut seen = vec![false; self.suggestions.len()];
+        let mut completions = Vec::new();
+        for entry in found {
+            if std::mem::replace(&mut seen[entry.suggestion], true) {
+                continue;
+            }
+            completions.push(&self.suggestions[entry.suggestion]);
+            if completions.len() == limit {
+                break;
+            }
+        }
+        completions
+    }
+}
+
+fn collect(node: &TrieNode, found: &mut Vec<Entry>) {
+    found.extend_from_slice(&node.entries);
+    for child in node.children.values() {
+        collect(child, found);
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SuggestParams {
+    /// What has been typed so far.
+    #[serde(default)]
+    prefix: String,
+    /// Most completions returned: 8 by default, at most 50.
+    limit: Option<usize>,
+}
+
+/// Search-as-you-type completions from book names, abbreviations and headings.
+#[utoipa::path(
+    get,
+    path = "/api/suggest",
+    tag = "search",
+    params(SuggestParams),
+    responses(
+        (status = 200, description = "Completions, best first", content(([Suggestion] = "application/json"), ([Suggestion] = "application/cbor"), ([Suggestion] = "application/msgpack"))),
+        (status = 406, description = "No supported format is acceptable", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_suggest(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<SuggestParams>,
+) -> Result<HttpResponse> {
+    let limit = params.limit.unwrap_or(DEFAULT_LIMIT).clamp(1, MAX_LIMIT);
+    Ok(negotiate::respond(
+        &req,
+        &data.suggestions.complete(&params.prefix, limit),
+    ))
+}
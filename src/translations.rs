@@ -0,0 +1,329 @@
+//! Heading sets in other languages, each with its language and text
+//! direction, so Hebrew or Arabic headings render right-to-left beside the
+//! English ones.
+//!
+//! Sets are JSON files in the data directory's `headings/` folder (or
+//! `HEADINGS_DIR`):
+//!
+//! ```json
+//! {"id": "he", "name": "עברית", "language": "he", "direction": "rtl",
+//!  "books": [{"name": "Genesis", "chapters": [
+//!      {"chapter_number": 1, "sections": [{"heading": "בְּרִיאַת הָעוֹלָם"}]}]}]}
+//! ```
+//!
+//! Book names may be any name or abbreviation `canon::lookup` knows. Chapters a
+//! set leaves out show the placeholder heading. Text is stored in NFC.
+
+use std::collections::HashMap;
+
+use actix_web::cookie::{time::Duration, Cookie, SameSite};
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+use unicode_normalization::UnicodeNormalization;
+use utoipa::ToSchema;
+
+use crate::config::DataPath;
+use crate::negotiate::Precomputed;
+use crate::search::{Analyzer, SearchIndex};
+use crate::{canon, AppState, Book, Chapter, SectionHeading, PLACEHOLDER_HEADING};
+
+const HEADINGS_COOKIE: &str = "headings";
+const HEADINGS_COOKIE_DAYS: i64 = 365;
+
+/// Languages written right to left, used when a set does not say.
+const RTL_LANGUAGES: &[&str] = &["ar", "arc", "dv", "fa", "he", "ps", "syr", "ur", "yi"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Direction::Ltr => "ltr",
+            Direction::Rtl => "rtl",
+        }
+    }
+
+    fn for_language(language: &str) -> Direction {
+        let primary = language
+            .split('-')
+            .next()
+            .unwrap_or("")
+            .to_ascii_lowercase();
+        if RTL_LANGUAGES.contains(&primary.as_str()) {
+            Direction::Rtl
+        } else {
+            Direction::Ltr
+        }
+    }
+}
+
+/// What a heading set is written in.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SetInfo {
+    pub id: String,
+    pub name: String,
+    /// BCP 47 language tag, used for `lang` attributes.
+    pub language: String,
+    pub direction: Direction,
+}
+
+impl SetInfo {
+    /// The bundled English headings.
+    pub fn base() -> SetInfo {
+        SetInfo {
+            id: "en".to_string(),
+            name: "English".to_string(),
+            language: "en".to_string(),
+            direction: Direction::Ltr,
+        }
+    }
+}
+
+/// A loaded heading set with its own search index.
+pub struct HeadingSet {
+    pub info: SetInfo,
+    pub books: Vec<Book>,
+    pub search: SearchIndex,
+    /// Chapters with at least one heading from the set.
+    pub chapters: usize,
+    /// The set's `/api/books` body, precomputed like the English one.
+    pub body: Precomputed,
+}
+
+#[derive(Deserialize)]
+struct SetFile {
+    id: String,
+    name: String,
+    language: String,
+    direction: Option<Direction>,
+    books: Vec<BookFile>,
+}
+
+#[derive(Deserialize)]
+struct BookFile {
+    name: String,
+    chapters: Vec<Chapter>,
+}
+
+impl HeadingSet {
+    /// Builds a set from its JSON source, laid over the shape of `base`.
+    fn parse(
+        source: &str,
+        base: &[Book],
+        analyzer: &Analyzer,
+    ) -> std::result::Result<HeadingSet, String> {
+        let file: SetFile = serde_json::from_str(source).map_err(|err| err.to_string())?;
+        if file.id.is_empty() || file.id == SetInfo::base().id {
+            return Err(format!("'{}' cannot be used as a set id", file.id));
+        }
+
+        let mut headings: HashMap<(&str, u32), Vec<SectionHeading>> = HashMap::new();
+        for book in &file.books {
+            let info =
+                canon::lookup(&book.name).ok_or_else(|| format!("unknown book '{}'", book.name))?;
+            for chapter in &book.chapters {
+                let sections: Vec<SectionHeading> = chapter
+                    .sections
+                    .iter()
+                    .map(|s| SectionHeading {
+                        heading: s.heading.trim().nfc().collect(),
+                    })
+                    .filter(|s| !s.heading.is_empty())
+                    .collect();
+                if !sections.is_empty() {
+                    headings.insert((info.name, chapter.chapter_number), sections);
+                }
+            }
+        }
+
+        let mut chapters = 0;
+        let books: Vec<Book> = base
+            .iter()
+            .map(|book| Book {
+                name: book.name.clone(),
+                testament: book.testament.clone(),
+                chapters: book
+                    .chapters
+                    .iter()
+                    .map(|chapter| {
+                        let sections =
+                            match headings.remove(&(book.name.as_str(), chapter.chapter_number)) {
+                                Some(sections) => {
+                                    chapters += 1;
+                                    sections
+                                }
+                                None => vec![SectionHeading {
+                                    heading: PLACEHOLDER_HEADING.to_string(),
+                                }],
+                            };
+                        Chapter {
+                            chapter_number: chapter.chapter_number,
+                            sections,
+                        }
+                    })
+                    .collect(),
+            })
+            .collect();
+        if let Some((book, chapter)) = headings.keys().next() {
+            return Err(format!("{} has no chapter {}", book, chapter));
+        }
+
+        let language = file.language.trim().to_string();
+        let search = SearchIndex::build(&books, analyzer.clone());
+        let body = Precomputed::new(&books);
+        Ok(HeadingSet {
+            info: SetInfo {
+                id: file.id,
+                name: file.name,
+                direction: file
+                    .direction
+                    .unwrap_or_else(|| Direction::for_language(&language)),
+                language,
+            },
+            books,
+            search,
+            chapters,
+            body,
+        })
+    }
+
+    /// Loads every `*.json` set in `dir`; none when it does not exist.
+    pub fn load_all(base: &[Book], dir: &DataPath, analyzer: &Analyzer) -> Vec<HeadingSet> {
+        let Some(entries) = dir.read_dir("heading sets") else {
+            return Vec::new();
+        };
+
+        let mut paths: Vec<_> = entries
+            .filter_map(|entry| entry.ok().map(|e| e.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "json"))
+            .collect();
+        paths.sort();
+
+        let mut sets: Vec<HeadingSet> = Vec::new();
+        for path in paths {
+            let parsed = std::fs::read_to_string(&path)
+                .map_err(|err| err.to_string())
+                .and_then(|source| HeadingSet::parse(&source, base, analyzer));
+            match parsed {
+                Ok(set) if sets.iter().any(|s| s.info.id == set.info.id) => {
+                    log::warn!(
+                        "Skipping heading set {}: duplicate id '{}'",
+                        path.display(),
+                        set.info.id
+                    );
+                }
+                Ok(set) => sets.push(set),
+                Err(err) => log::warn!("Skipping heading set {}: {}", path.display(), err),
+            }
+        }
+        sets
+    }
+}
+
+/// The heading set a request asks for: `?headings=`, then the `headings`
+/// cookie. `None` means the base English headings.
+pub fn requested<'a>(req: &HttpRequest, sets: &'a [HeadingSet]) -> Option<&'a HeadingSet> {
+    let id = query_value(req, "headings")
+        .or_else(|| req.cookie(HEADINGS_COOKIE).map(|c| c.value().to_string()))?;
+    sets.iter().find(|set| set.info.id == id)
+}
+
+fn query_value(req: &HttpRequest, key: &str) -> Option<String> {
+    web::Query::<HashMap<String, String>>::from_query(req.query_string())
+        .ok()?
+        .get(key)
+        .cloned()
+}
+
+/// Remembers an explicit `?headings=` choice; choosing the base set forgets it.
+pub fn remember(req: &HttpRequest, set: Option<&HeadingSet>, response: &mut HttpResponse) {
+    let Some(id) = query_value(req, "headings") else {
+        return;
+    };
+    let mut cookie = Cookie::build(HEADINGS_COOKIE, id)
+        .path("/")
+        .same_site(SameSite::Lax)
+        .max_age(Duration::days(HEADINGS_COOKIE_DAYS))
+        .finish();
+    if set.is_none() {
+        cookie.make_removal();
+    }
+    // A freshly built cookie always forms a valid header value.
+    let _ = response.add_cookie(&cookie);
+}
+
+/// Links switching between heading sets; empty when only English is loaded.
+pub fn set_links_html(sets: &[HeadingSet], current: &SetInfo) -> String {
+    if sets.is_empty() {
+        return String::new();
+    }
+    let base = SetInfo::base();
+    let mut html = String::from(
+        r#"
+            <nav class="language-links heading-sets" aria-label="Headings">"#,
+    );
+    for info in std::iter::once(&base).chain(sets.iter().map(|s| &s.info)) {
+        html.push_str(&format!(
+            r#"
+                <a href="?headings={}" lang="{}" dir="{}"{}>{}</a>"#,
+            info.id,
+            info.language,
+            info.direction.as_str(),
+            if info.id == current.id {
+                r#" aria-current="true""#
+            } else {
+                ""
+            },
+            crate::layout::escape(&info.name)
+        ));
+    }
+    html.push_str(
+        r#"
+            </nav>"#,
+    );
+    html
+}
+
+#[derive(Serialize, ToSchema)]
+struct SetSummary<'a> {
+    #[serde(flatten)]
+    info: &'a SetInfo,
+    /// Chapters with headings in this set.
+    chapters: usize,
+}
+
+/// `/api/translations`: the available heading sets and their metadata.
+#[utoipa::path(
+    get,
+    path = "/api/translations",
+    tag = "books",
+    responses((status = 200, description = "Heading sets, English first", body = [SetSummary]))
+)]
+pub async fn api_translations(data: web::Data<AppState>) -> Result<HttpResponse> {
+    let base = SetInfo::base();
+    let base_chapters = data
+        .books
+        .iter()
+        .flat_map(|book| &book.chapters)
+        .filter(|chapter| {
+            chapter
+                .sections
+                .iter()
+                .any(|s| s.heading != PLACEHOLDER_HEADING)
+        })
+        .count();
+    let mut sets = vec![SetSummary {
+        info: &base,
+        chapters: base_chapters,
+    }];
+    sets.extend(data.translations.iter().map(|set| SetSummary {
+        info: &set.info,
+        chapters: set.chapters,
+    }));
+    Ok(HttpResponse::Ok().json(sets))
+}
@@ -0,0 +1,478 @@
+//! `bible-headlines tui`: a terminal browser for the headings. It behaves like
+//! the web page — books collapse and expand as with `toggleBook`, and the
+//! search box and testament toggle hide books and chapters the way
+//! `filterContent` does — and is driven entirely from the keyboard.
+
+use std::collections::HashSet;
+use std::io;
+
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Position};
+use ratatui::style::{Color, Modifier, Style, Stylize};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, List, ListItem, ListState, Paragraph, Tabs};
+use ratatui::{DefaultTerminal, Frame};
+
+use crate::search::fold;
+use crate::{Book, Testament, PLACEHOLDER_HEADING};
+
+const HELP: &str = "↑↓ move  ←→ collapse/expand  Enter toggle  / search  t testament  q quit";
+/// Rows moved by Page Up and Page Down.
+const PAGE: usize = 10;
+
+/// The testament toggle: the page's "All", "Old Testament" and "New
+/// Testament" buttons.
+#[derive(Clone, Copy, PartialEq)]
+enum Filter {
+    All,
+    Old,
+    New,
+}
+
+impl Filter {
+    const TITLES: [&'static str; 3] = ["All", "Old Testament", "New Testament"];
+
+    fn next(self) -> Filter {
+        match self {
+            Filter::All => Filter::Old,
+            Filter::Old => Filter::New,
+            Filter::New => Filter::All,
+        }
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+
+    fn matches(self, testament: &Testament) -> bool {
+        match self {
+            Filter::All => true,
+            Filter::Old => *testament == Testament::Old,
+            Filter::New => *testament == Testament::New,
+        }
+    }
+}
+
+/// A line of the tree, by book, chapter and section position.
+#[derive(Clone, Copy, PartialEq)]
+enum Row {
+    Book(usize),
+    Chapter(usize, usize),
+    Heading(usize, usize, usize),
+}
+
+struct App {
+    books: Vec<Book>,
+    /// Folded book names and chapter heading text, like the page's
+    /// `data-book-name` and `data-search-text` attributes.
+    book_text: Vec<String>,
+    chapter_text: Vec<Vec<String>>,
+    query: String,
+    /// Whether keys go to the search box rather than the tree.
+    searching: bool,
+    filter: Filter,
+    expanded_books: HashSet<usize>,
+    expanded_chapters: HashSet<(usize, usize)>,
+    /// The visible rows, rebuilt whenever the filter or expansion changes.
+    rows: Vec<Row>,
+    list: ListState,
+    visible_books: usize,
+    visible_chapters: usize,
+    quit: bool,
+}
+
+impl App {
+    fn new(books: Vec<Book>) -> Self {
+        let book_text = books.iter().map(|book| fold(&book.name)).collect();
+        let chapter_text = books
+            .iter()
+            .map(|book| {
+                book.chapters
+                    .iter()
+                    .map(|chapter| {
+                        let headings: Vec<&str> = chapter
+                            .sections
+                            .iter()
+                            .map(|s| s.heading.as_str())
+                            .collect();
+                        fold(&headings.join(" "))
+                    })
+                    .collect()
+            })
+            .collect();
+        let mut app = App {
+            books,
+            book_text,
+            chapter_text,
+            query: String::new(),
+            searching: false,
+            filter: Filter::All,
+            expanded_books: HashSet::new(),
+            expanded_chapters: HashSet::new(),
+            rows: Vec::new(),
+            list: ListState::default().with_selected(Some(0)),
+            visible_books: 0,
+            visible_chapters: 0,
+            quit: false,
+        };
+        app.refresh();
+        app
+    }
+
+    /// Rebuilds the visible rows: a book shows when it is in the selected
+    /// testament and its name or any of its chapters matches the search; a
+    /// chapter shows when the search is empty, its book's name matches or its
+    /// headings do. Matching books and chapters open while searching.
+    fn refresh(&mut self) {
+        let term = fold(&self.query);
+        let selected = self.selected_row();
+        self.rows.clear();
+        self.visible_books = 0;
+        self.visible_chapters = 0;
+
+        for (b, book) in self.books.iter().enumerate() {
+            if !self.filter.matches(&book.testament) {
+                continue;
+            }
+            let book_matches = self.book_text[b].contains(&term);
+            let chapters: Vec<usize> = (0..book.chapters.len())
+                .filter(|&c| {
+                    term.is_empty() || book_matches || self.chapter_text[b][c].contains(&term)
+                })
+                .collect();
+            if !book_matches && chapters.is_empty() {
+                continue;
+            }
+
+            self.visible_books += 1;
+            self.visible_chapters += chapters.len();
+            self.rows.push(Row::Book(b));
+            if !term.is_empty() || self.expanded_books.contains(&b) {
+                for c in chapters {
+                    self.rows.push(Row::Chapter(b, c));
+                    let open = self.expanded_chapters.contains(&(b, c))
+                        || (!term.is_empty() && self.chapter_text[b][c].contains(&term));
+                    if open {
+                        let sections = book.chapters[c].sections.len();
+                        self.rows
+                            .extend((0..sections).map(|s| Row::Heading(b, c, s)));
+                    }
+                }
+            }
+        }
+
+        // Stay on the same row when it is still visible.
+        let index = selected
+            .and_then(|row| self.rows.iter().position(|r| *r == row))
+            .unwrap_or(0);
+        self.list
+            .select(Some(index.min(self.rows.len().saturating_sub(1))));
+    }
+
+    fn selected_row(&self) -> Option<Row> {
+        self.rows.get(self.list.selected()?).copied()
+    }
+
+    fn select(&mut self, row: Row) {
+        if let Some(index) = self.rows.iter().position(|r| *r == row) {
+            self.list.select(Some(index));
+        }
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        if self.rows.is_empty() {
+            return;
+        }
+        let current = self.list.selected().unwrap_or(0) as isize;
+        let last = self.rows.len() as isize - 1;
+        self.list
+            .select(Some((current + delta).clamp(0, last) as usize));
+    }
+
+    fn toggle(&mut self) {
+        match self.selected_row() {
+            Some(Row::Book(b)) => {
+                if !self.expanded_books.remove(&b) {
+                    self.expanded_books.insert(b);
+                }
+            }
+            Some(Row::Chapter(b, c)) => {
+                if !self.expanded_chapters.remove(&(b, c)) {
+                    self.expanded_chapters.insert((b, c));
+                }
+            }
+            Some(Row::Heading(..)) | None => {}
+        }
+        self.refresh();
+    }
+
+    fn expand(&mut self) {
+        match self.selected_row() {
+            Some(Row::Book(b)) => {
+                self.expanded_books.insert(b);
+            }
+            Some(Row::Chapter(b, c)) => {
+                self.expanded_chapters.insert((b, c));
+            }
+            Some(Row::Heading(..)) | None => {}
+        }
+        self.refresh();
+    }
+
+    /// Closes the selected book or chapter, or moves from a child to its parent.
+    fn collapse(&mut self) {
+        match self.selected_row() {
+            Some(Row::Book(b)) => {
+                self.expanded_books.remove(&b);
+            }
+            Some(Row::Chapter(b, c)) => {
+                let was_open = self.expanded_chapters.remove(&(b, c));
+                if !was_open {
+                    self.select(Row::Book(b));
+                }
+            }
+            Some(Row::Heading(b, c, _)) => self.select(Row::Chapter(b, c)),
+            None => {}
+        }
+        self.refresh();
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            self.quit = true;
+            return;
+        }
+        match key.code {
+            KeyCode::Up => self.move_by(-1),
+            KeyCode::Down => self.move_by(1),
+            KeyCode::PageUp => self.move_by(-(PAGE as isize)),
+            KeyCode::PageDown => self.move_by(PAGE as isize),
+            KeyCode::Tab => {
+                self.filter = self.filter.next();
+                self.refresh();
+            }
+            _ if self.searching => self.handle_search_key(key),
+            _ => self.handle_tree_key(key),
+        }
+    }
+
+    fn handle_search_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Esc | KeyCode::Enter => self.searching = false,
+            KeyCode::Backspace => {
+                self.query.pop();
+                self.refresh();
+            }
+            KeyCode::Char('u') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.query.clear();
+                self.refresh();
+            }
+            KeyCode::Char(c) => {
+                self.query.push(c);
+                self.list.select(Some(0));
+                self.refresh();
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_tree_key(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Char('q') => self.quit = true,
+            KeyCode::Char('/') => self.searching = true,
+            KeyCode::Esc => {
+                self.query.clear();
+                self.refresh();
+            }
+            KeyCode::Char('t') => {
+                self.filter = self.filter.next();
+                self.refresh();
+            }
+            KeyCode::Char('k') => self.move_by(-1),
+            KeyCode::Char('j') => self.move_by(1),
+            KeyCode::Char('g') | KeyCode::Home => self.list.select(Some(0)),
+            KeyCode::Char('G') | KeyCode::End => {
+                self.list.select(Some(self.rows.len().saturating_sub(1)))
+            }
+            KeyCode::Enter | KeyCode::Char(' ') => self.toggle(),
+            KeyCode::Right | KeyCode::Char('l') => self.expand(),
+            KeyCode::Left | KeyCode::Char('h') => self.collapse(),
+            _ => {}
+        }
+    }
+
+    fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            terminal.draw(|frame| self.draw(frame))?;
+            if let Event::Key(key) = event::read()? {
+                if key.kind == KeyEventKind::Press {
+                    self.handle_key(key);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [top, body, bottom] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+        let [search_area, tabs_area] =
+            Layout::horizontal([Constraint::Fill(1), Constraint::Length(40)]).areas(top);
+
+        let accent = Style::new().fg(Color::Yellow);
+        let search_block = Block::bordered()
+            .title(" Search (/) ")
+            .border_style(if self.searching { accent } else { Style::new() });
+        let search_text = if self.query.is_empty() && !self.searching {
+            Line::from("Search books and headings…".dark_gray())
+        } else {
+            Line::from(self.query.as_str())
+        };
+        frame.render_widget(Paragraph::new(search_text).block(search_block), search_area);
+        if self.searching {
+            let x = search_area.x + 1 + self.query.chars().count() as u16;
+            frame.set_cursor_position(Position::new(
+                x.min(search_area.right().saturating_sub(2)),
+                search_area.y + 1,
+            ));
+        }
+
+        let tabs = Tabs::new(Filter::TITLES)
+            .select(self.filter.index())
+            .highlight_style(accent.add_modifier(Modifier::BOLD))
+            .block(Block::bordered().title(" Testament (t) "));
+        frame.render_widget(tabs, tabs_area);
+
+        let term = fold(&self.query);
+        let items: Vec<ListItem> = if self.rows.is_empty() {
+            vec![ListItem::new("No books or chapters match".dark_gray())]
+        } else {
+            self.rows
+                .iter()
+                .map(|row| self.row_item(*row, &term))
+                .collect()
+        };
+        let list = List::new(items)
+            .block(Block::bordered())
+            .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+            .highlight_symbol("› ");
+        // The items borrow the books, so render with a copy of the list state.
+        let mut state = self.list.clone();
+        frame.render_stateful_widget(list, body, &mut state);
+        *self.list.offset_mut() = state.offset();
+
+        let stats = format!(
+            " {} · {}   ",
+            count(self.visible_books, "book"),
+            count(self.visible_chapters, "chapter")
+        );
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![stats.bold(), HELP.dark_gray()])),
+            bottom,
+        );
+    }
+
+    fn row_item(&self, row: Row, term: &str) -> ListItem<'_> {
+        let line = match row {
+            Row::Book(b) => {
+                let book = &self.books[b];
+                let open = !term.is_empty() || self.expanded_books.contains(&b);
+                let mut spans = vec![Span::raw(if open { "▼ " } else { "▶ " })];
+                spans.extend(highlight(
+                    &book.name,
+                    term,
+                    Style::new().bold().fg(Color::Cyan),
+                ));
+                spans.push(format!("  {}", count(book.chapters.len(), "chapter")).dark_gray());
+                Line::from(spans)
+            }
+            Row::Chapter(b, c) => {
+                let chapter = &self.books[b].chapters[c];
+                let open = self.expanded_chapters.contains(&(b, c))
+                    || (!term.is_empty() && self.chapter_text[b][c].contains(term));
+                let mut spans = vec![
+                    Span::raw(if open { "    ▾ " } else { "    ▸ " }),
+                    format!("Chapter {}", chapter.chapter_number).yellow(),
+                ];
+                // A closed chapter previews its headings on the same line.
+                if !open {
+                    let headings: Vec<&str> = chapter
+                        .sections
+                        .iter()
+                        .map(|s| s.heading.as_str())
+                        .collect();
+                    spans.push(format!("  {}", headings.join(" · ")).dark_gray());
+                }
+                Line::from(spans)
+            }
+            Row::Heading(b, c, s) => {
+                let heading = &self.books[b].chapters[c].sections[s].heading;
+                let mut spans = vec![Span::raw("        • ")];
+                if heading == PLACEHOLDER_HEADING {
+                    spans.push(heading.as_str().dark_gray().italic());
+                } else {
+                    spans.extend(highlight(heading, term, Style::new()));
+                }
+                Line::from(spans)
+            }
+        };
+        ListItem::new(line)
+    }
+}
+
+/// "1 book", "2 books".
+fn count(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
+
+/// Splits `text` into spans with the search term marked, matching folded
+/// text character by character so accented headings highlight correctly.
+fn highlight<'a>(text: &'a str, term: &str, style: Style) -> Vec<Span<'a>> {
+    if term.chars().count() < 2 {
+        return vec![Span::styled(text, style)];
+    }
+    // For each byte of the folded text, where its character starts in `text`.
+    let mut folded = String::new();
+    let mut origin = Vec::new();
+    for (start, c) in text.char_indices() {
+        let piece = fold(c.encode_utf8(&mut [0; 4]));
+        origin.extend(std::iter::repeat_n(start, piece.len()));
+        folded.push_str(&piece);
+    }
+    origin.push(text.len());
+
+    let mut spans = Vec::new();
+    let mut done = 0;
+    for (index, matched) in folded.match_indices(term) {
+        let start = origin[index];
+        let end = origin[index + matched.len()];
+        if start < done || start == end {
+            continue;
+        }
+        spans.push(Span::styled(&text[done..start], style));
+        spans.push(Span::styled(
+            &text[start..end],
+            style.bg(Color::Yellow).fg(Color::Black),
+        ));
+        done = end;
+    }
+    spans.push(Span::styled(&text[done..], style));
+    spans
+}
+
+/// Runs the browser until the user quits, restoring the terminal afterwards.
+pub fn run(books: Vec<Book>) -> io::Result<()> {
+    let app = App::new(books);
+    let mut terminal = ratatui::init();
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+    result
+}
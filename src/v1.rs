@@ -0,0 +1,379 @@
+//! The versioned API under `/api/v1/`. Responses are built from the types
+//! below rather than serialized from the internal `Book`/`Chapter` structs, so
+//! the data model can change without changing what clients see. Books and
+//! chapters are identified by OSIS ids ("Gen", "Gen.2"), enum values are
+//! snake_case, and each response type has a JSON Schema at
+//! `/api/v1/schemas/{name}`.
+
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+
+use actix_web::{web, HttpRequest, HttpResponse, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use utoipa::{IntoParams, OpenApi, ToSchema};
+
+use crate::canon::{self, Genre};
+use crate::search::SearchHit;
+use crate::{negotiate, translations, AppState, PLACEHOLDER_HEADING};
+
+const JSON_SCHEMA_DIALECT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+#[derive(Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+#[schema(as = v1::Testament)]
+pub enum Testament {
+    Old,
+    New,
+}
+
+impl From<&crate::Testament> for Testament {
+    fn from(testament: &crate::Testament) -> Self {
+        match testament {
+            crate::Testament::Old => Testament::Old,
+            crate::Testament::New => Testament::New,
+        }
+    }
+}
+
+/// A book without its chapters.
+#[derive(Serialize, ToSchema)]
+#[schema(as = v1::BookSummary)]
+pub struct BookSummary {
+    /// OSIS book id, e.g. "1Cor".
+    id: String,
+    name: String,
+    testament: Testament,
+    /// Null for books outside the 66-book canon.
+    genre: Option<Genre>,
+    chapter_count: usize,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = v1::BookList)]
+pub struct BookList {
+    books: Vec<BookSummary>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = v1::Book)]
+pub struct Book {
+    /// OSIS book id, e.g. "1Cor".
+    id: String,
+    name: String,
+    testament: Testament,
+    genre: Option<Genre>,
+    chapters: Vec<Chapter>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = v1::Chapter)]
+pub struct Chapter {
+    /// OSIS chapter id, e.g. "Gen.2".
+    id: String,
+    /// OSIS id of the book.
+    book: String,
+    number: u32,
+    sections: Vec<Section>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = v1::Section)]
+pub struct Section {
+    /// One-based position within the chapter.
+    position: usize,
+    heading: String,
+    /// True for chapters whose headings have not been written yet.
+    placeholder: bool,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = v1::SearchResults)]
+pub struct SearchResults {
+    query: String,
+    total: usize,
+    results: Vec<SearchResult>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[schema(as = v1::SearchResult)]
+pub struct SearchResult {
+    /// OSIS id of the chapter holding the heading.
+    chapter: String,
+    book: String,
+    testament: Testament,
+    heading: String,
+    score: f64,
+}
+
+/// The OSIS id for a book, falling back to its name without spaces for
+/// books `canon` does not know.
+pub fn book_id(name: &str) -> String {
+    canon::info(name).map_or_else(|| name.replace(' ', ""), |info| info.osis.to_string())
+}
+
+pub fn summary(book: &crate::Book) -> BookSummary {
+    BookSummary {
+        id: book_id(&book.name),
+        name: book.name.clone(),
+        testament: Testament::from(&book.testament),
+        genre: canon::info(&book.name).map(|info| info.genre()),
+        chapter_count: book.chapters.len(),
+    }
+}
+
+pub fn chapter(book: &crate::Book, chapter: &crate::Chapter) -> Chapter {
+    let book = book_id(&book.name);
+    Chapter {
+        id: format!("{}.{}", book, chapter.chapter_number),
+        book,
+        number: chapter.chapter_number,
+        sections: chapter
+            .sections
+            .iter()
+            .enumerate()
+            .map(|(index, section)| Section {
+                position: index + 1,
+                heading: section.heading.clone(),
+                placeholder: section.heading == PLACEHOLDER_HEADING,
+            })
+            .collect(),
+    }
+}
+
+pub fn book_list<'a>(books: impl IntoIterator<Item = &'a crate::Book>) -> BookList {
+    BookList {
+        books: books.into_iter().map(summary).collect(),
+    }
+}
+
+impl SearchResults {
+    /// The first `limit` of `hits`, which were found for `query`.
+    pub fn new(query: &str, hits: Vec<SearchHit>, limit: usize) -> Self {
+        SearchResults {
+            query: query.to_string(),
+            total: hits.len(),
+            results: hits
+                .into_iter()
+                .take(limit)
+                .map(|hit| SearchResult {
+                    chapter: format!("{}.{}", book_id(&hit.book), hit.chapter),
+                    testament: Testament::from(&hit.testament),
+                    book: book_id(&hit.book),
+                    heading: hit.heading,
+                    score: hit.score,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// The books to answer from: the base headings or the `?headings=` set.
+fn books<'a>(req: &HttpRequest, data: &'a AppState) -> &'a [crate::Book] {
+    translations::requested(req, &data.translations).map_or(&data.books[..], |set| &set.books[..])
+}
+
+/// Finds a book by OSIS id, name or abbreviation.
+fn find_book<'a>(books: &'a [crate::Book], id: &str) -> Option<&'a crate::Book> {
+    let name = canon::lookup(id).map_or(id, |info| info.name);
+    books
+        .iter()
+        .find(|book| book.name == name || book_id(&book.name) == id)
+}
+
+fn not_found(message: String) -> HttpResponse {
+    HttpResponse::NotFound().json(json!({ "error": message }))
+}
+
+/// Every book, in canonical order, without chapters.
+#[utoipa::path(
+    get,
+    path = "/api/v1/books",
+    tag = "v1",
+    params(("headings" = Option<String>, Query, description = "Heading set id from `/api/translations`")),
+    responses(
+        (status = 200, description = "Every book", body = BookList),
+        (status = 406, description = "No supported format is acceptable", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_books(req: HttpRequest, data: web::Data<AppState>) -> Result<HttpResponse> {
+    Ok(negotiate::respond(&req, &book_list(books(&req, &data))))
+}
+
+/// One book with all of its chapters and headings.
+#[utoipa::path(
+    get,
+    path = "/api/v1/books/{id}",
+    tag = "v1",
+    params(
+        ("id" = String, Path, description = "OSIS book id, e.g. `Gen`; names and abbreviations also work"),
+        ("headings" = Option<String>, Query, description = "Heading set id from `/api/translations`"),
+    ),
+    responses(
+        (status = 200, description = "The book", body = Book),
+        (status = 404, description = "Unknown book", body = crate::openapi::ErrorBody),
+        (status = 406, description = "No supported format is acceptable", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_book(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    id: web::Path<String>,
+) -> Result<HttpResponse> {
+    let Some(book) = find_book(books(&req, &data), &id) else {
+        return Ok(not_found(format!("Unknown book '{}'", id)));
+    };
+    let summary = summary(book);
+    Ok(negotiate::respond(
+        &req,
+        &Book {
+            id: summary.id,
+            name: summary.name,
+            testament: summary.testament,
+            genre: summary.genre,
+            chapters: book.chapters.iter().map(|c| chapter(book, c)).collect(),
+        },
+    ))
+}
+
+/// One chapter's headings.
+#[utoipa::path(
+    get,
+    path = "/api/v1/chapters/{id}",
+    tag = "v1",
+    params(
+        ("id" = String, Path, description = "OSIS chapter id, e.g. `Gen.2`"),
+        ("headings" = Option<String>, Query, description = "Heading set id from `/api/translations`"),
+    ),
+    responses(
+        (status = 200, description = "The chapter", body = Chapter),
+        (status = 404, description = "Unknown chapter", body = crate::openapi::ErrorBody),
+        (status = 406, description = "No supported format is acceptable", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_chapter(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    id: web::Path<String>,
+) -> Result<HttpResponse> {
+    let found = id.rsplit_once('.').and_then(|(book, number)| {
+        let book = find_book(books(&req, &data), book)?;
+        let number: u32 = number.parse().ok()?;
+        let chapter = book.chapters.iter().find(|c| c.chapter_number == number)?;
+        Some((book, chapter))
+    });
+    match found {
+        Some((book, found)) => Ok(negotiate::respond(&req, &chapter(book, found))),
+        None => Ok(not_found(format!("Unknown chapter '{}'", id))),
+    }
+}
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SearchParams {
+    /// Words to search for.
+    q: String,
+    /// Most results returned; 50 by default.
+    limit: Option<usize>,
+}
+
+/// Ranked section headings matching every search term.
+#[utoipa::path(
+    get,
+    path = "/api/v1/search",
+    tag = "v1",
+    params(SearchParams, ("headings" = Option<String>, Query, description = "Heading set to search")),
+    responses(
+        (status = 200, description = "Matching headings, best first", body = SearchResults),
+        (status = 406, description = "No supported format is acceptable", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_search(
+    req: HttpRequest,
+    data: web::Data<AppState>,
+    params: web::Query<SearchParams>,
+) -> Result<HttpResponse> {
+    let index =
+        translations::requested(&req, &data.translations).map_or(&*data.search, |set| &set.search);
+    let results = SearchResults::new(
+        &params.q,
+        index.search(&params.q),
+        params.limit.unwrap_or(50),
+    );
+    Ok(negotiate::respond(&req, &results))
+}
+
+/// The response types, whose schemas are published as JSON Schema.
+#[derive(OpenApi)]
+#[openapi(components(schemas(BookList, Book, Chapter, SearchResults, crate::openapi::ErrorBody)))]
+struct Schemas;
+
+/// Each response type as a standalone JSON Schema document, keyed by the
+/// name in its URL ("Book" for `v1.Book`).
+fn schemas() -> &'static BTreeMap<String, Value> {
+    static SCHEMAS: OnceLock<BTreeMap<String, Value>> = OnceLock::new();
+    SCHEMAS.get_or_init(|| {
+        let components = Schemas::openapi().components.unwrap_or_default().schemas;
+        // OpenAPI 3.1 schemas are JSON Schema; only the reference paths change.
+        let defs: Value = serde_json::from_str(
+            &serde_json::to_string(&components)
+                .expect("serializable schemas")
+                .replace("#/components/schemas/", "#/$defs/"),
+        )
+        .expect("valid JSON");
+        [
+            "v1.BookList",
+            "v1.Book",
+            "v1.Chapter",
+            "v1.SearchResults",
+            "ErrorBody",
+        ]
+        .into_iter()
+        .map(|full| {
+            let name = full.trim_start_matches("v1.");
+            let mut schema = defs[full].clone();
+            schema["$schema"] = json!(JSON_SCHEMA_DIALECT);
+            schema["$id"] = json!(format!("/api/v1/schemas/{}", name));
+            schema["title"] = json!(name);
+            schema["$defs"] = defs.clone();
+            (name.to_string(), schema)
+        })
+        .collect()
+    })
+}
+
+/// The names of the published schemas and where to find them.
+#[utoipa::path(
+    get,
+    path = "/api/v1/schemas",
+    tag = "v1",
+    responses((status = 200, description = "Schema names mapped to their URLs", body = BTreeMap<String, String>))
+)]
+pub async fn api_schemas() -> Result<HttpResponse> {
+    let index: BTreeMap<&str, String> = schemas()
+        .keys()
+        .map(|name| (name.as_str(), format!("/api/v1/schemas/{}", name)))
+        .collect();
+    Ok(HttpResponse::Ok().json(index))
+}
+
+/// The JSON Schema for one response type.
+#[utoipa::path(
+    get,
+    path = "/api/v1/schemas/{name}",
+    tag = "v1",
+    params(("name" = String, Path, description = "Schema name from `/api/v1/schemas`, e.g. `Book`")),
+    responses(
+        (status = 200, description = "A JSON Schema (draft 2020-12) document", content_type = "application/schema+json"),
+        (status = 404, description = "Unknown schema", body = crate::openapi::ErrorBody),
+    )
+)]
+pub async fn api_schema(name: web::Path<String>) -> Result<HttpResponse> {
+    let name = name.trim_end_matches(".json");
+    match schemas().get(name) {
+        Some(schema) => Ok(HttpResponse::Ok()
+            .content_type("application/schema+json")
+            .json(schema)),
+        None => Ok(not_found(format!("Unknown schema '{}'", name))),
+    }
+}
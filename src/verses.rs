@@ -0,0 +1,506 @@
+//! Optional full-text verse display.
+//!
+//! The site deliberately shows headings only. Building with
+//! `--features verses` and setting `VERSE_TRANSLATION` (e.g. `kjv-sample`,
+//! or `kjv` once imported) loads public-domain verse text from
+//! `<translation>.tsv` in the data directory's `verses` folder (or
+//! `VERSES_DIR`) and lets readers expand each chapter card to read the verses
+//! under its section headings. Without both the feature and the switch the
+//! page is rendered exactly as before.
+//!
+//! Verse files are tab-separated: `book<TAB>chapter<TAB>verse<TAB>text`,
+//! where `book` is a name, OSIS id or abbreviation (`Genesis`, `Gen`, `1John`).
+//! Only a short KJV sample is bundled; `bible-headlines import-verses` converts a
+//! complete translation downloaded from eBible.org (see [`import_vpl`]).
+
+use crate::canon;
+use crate::i18n::Locale;
+
+/// Button and container appended to a chapter card when verses are enabled.
+pub fn toggle_html(book: &str, chapter: u32, locale: &Locale) -> String {
+    format!(
+        r#"
+                    <button class="verses-toggle" onclick="toggleVerses(this)" data-book="{}" data-chapter="{}" aria-expanded="false">{}</button>
+                    <div class="verses" hidden></div>"#,
+        book,
+        chapter,
+        locale.text("verses.show")
+    )
+}
+
+/// Paratext book codes, as used by eBible.org's verse-per-line downloads.
+const USFM_BOOKS: &[(&str, &str)] = &[
+    ("GEN", "Genesis"),
+    ("EXO", "Exodus"),
+    ("LEV", "Leviticus"),
+    ("NUM", "Numbers"),
+    ("DEU", "Deuteronomy"),
+    ("JOS", "Joshua"),
+    ("JDG", "Judges"),
+    ("RUT", "Ruth"),
+    ("1SA", "1 Samuel"),
+    ("2SA", "2 Samuel"),
+    ("1KI", "1 Kings"),
+    ("2KI", "2 Kings"),
+    ("1CH", "1 Chronicles"),
+    ("2CH", "2 Chronicles"),
+    ("EZR", "Ezra"),
+    ("NEH", "Nehemiah"),
+    ("EST", "Esther"),
+    ("JOB", "Job"),
+    ("PSA", "Psalms"),
+    ("PRO", "Proverbs"),
+    ("ECC", "Ecclesiastes"),
+    ("SNG", "Song of Solomon"),
+    ("ISA", "Isaiah"),
+    ("JER", "Jeremiah"),
+    ("LAM", "Lamentations"),
+    ("EZK", "Ezekiel"),
+    ("DAN", "Daniel"),
+    ("HOS", "Hosea"),
+    ("JOL", "Joel"),
+    ("AMO", "Amos"),
+    ("OBA", "Obadiah"),
+    ("JON", "Jonah"),
+    ("MIC", "Micah"),
+    ("NAM", "Nahum"),
+    ("HAB", "Habakkuk"),
+    ("ZEP", "Zephaniah"),
+    ("HAG", "Haggai"),
+    ("ZEC", "Zechariah"),
+    ("MAL", "Malachi"),
+    ("MAT", "Matthew"),
+    ("MRK", "Mark"),
+    ("LUK", "Luke"),
+    ("JHN", "John"),
+    ("ACT", "Acts"),
+    ("ROM", "Romans"),
+    ("1CO", "1 Corinthians"),
+    ("2CO", "2 Corinthians"),
+    ("GAL", "Galatians"),
+    ("EPH", "Ephesians"),
+    ("PHP", "Philippians"),
+    ("COL", "Colossians"),
+    ("1TH", "1 Thessalonians"),
+    ("2TH", "2 Thessalonians"),
+    ("1TI", "1 Timothy"),
+    ("2TI", "2 Timothy"),
+    ("TIT", "Titus"),
+    ("PHM", "Philemon"),
+    ("HEB", "Hebrews"),
+    ("JAS", "James"),
+    ("1PE", "1 Peter"),
+    ("2PE", "2 Peter"),
+    ("1JN", "1 John"),
+    ("2JN", "2 John"),
+    ("3JN", "3 John"),
+    ("JUD", "Jude"),
+    ("REV", "Revelation"),
+];
+
+/// A translation converted by [`import_vpl`].
+pub struct Imported {
+    /// The verses in the tab-separated verse file format.
+    pub tsv: String,
+    pub verses: usize,
+    /// Book codes outside the 66 books, such as the deuterocanon, left out.
+    pub skipped: Vec<String>,
+}
+
+/// Converts an eBible.org verse-per-line file (`GEN 1:1 In the beginning…`)
+/// into the tab-separated verse format. Books outside the 66 are skipped, as
+/// are verses a translation leaves empty.
+pub fn import_vpl(source: &str) -> Result<Imported, String> {
+    let mut imported = Imported {
+        tsv: String::new(),
+        verses: 0,
+        skipped: Vec::new(),
+    };
+
+    for (index, line) in source.lines().enumerate() {
+        let line = line.trim_start_matches('\u{feff}').trim();
+        if line.is_empty() {
+            continue;
+        }
+        let (code, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let (point, text) = rest.split_once(' ').unwrap_or((rest, ""));
+        let (chapter, verse) = point
+            .split_once(':')
+            .and_then(|(c, v)| Some((c.parse::<u32>().ok()?, v.parse::<u32>().ok()?)))
+            .ok_or_else(|| format!("line {}: expected 'BOOK chapter:verse text'", index + 1))?;
+
+        let book = USFM_BOOKS
+            .iter()
+            .find(|(usfm, _)| usfm.eq_ignore_ascii_case(code))
+            .map(|(_, name)| *name)
+            .or_else(|| canon::lookup(code).map(|info| info.name));
+        let Some(book) = book else {
+            if !imported.skipped.iter().any(|skipped| skipped == code) {
+                imported.skipped.push(code.to_string());
+            }
+            continue;
+        };
+        let text = text.trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        imported
+            .tsv
+            .push_str(&format!("{}\t{}\t{}\t{}\n", book, chapter, verse, text));
+        imported.verses += 1;
+    }
+
+    Ok(imported)
+}
+
+pub const STYLES: &str = r#"
+        .verses-toggle {
+            margin-top: 1rem;
+            padding: 0.4rem 0.9rem;
+            border: 1px solid #e2e8f0;
+            border-radius: 8px;
+            background: white;
+            color: #667eea;
+            font-family: 'Inter', sans-serif;
+            font-size: 0.85rem;
+            font-weight: 500;
+            cursor: pointer;
+        }
+
+        .verses-toggle:hover {
+            border-color: #667eea;
+        }
+
+        .verses {
+            margin-top: 1rem;
+            display: flex;
+            flex-direction: column;
+            gap: 0.75rem;
+        }
+
+        .verses[hidden] {
+            display: none;
+        }
+
+        .verse-section-title {
+            font-family: 'Cormorant Garamond', serif;
+            font-size: 1.1rem;
+            font-weight: 600;
+            color: #667eea;
+        }
+
+        .verse-text {
+            font-size: 0.9rem;
+            line-height: 1.7;
+            color: #2d3748;
+        }
+
+        .verse-number {
+            font-size: 0.7rem;
+            font-weight: 600;
+            color: #764ba2;
+            vertical-align: super;
+            margin-inline-end: 0.15rem;
+        }
+
+        .verse-translation {
+            font-size: 0.75rem;
+            color: #4a5568;
+            text-transform: uppercase;
+            letter-spacing: 0.5px;
+        }
+"#;
+
+pub const SCRIPT: &str = r#"
+        function renderVerseGroup(container, title, verses) {
+            if (verses.length === 0) {
+                return;
+            }
+            if (title) {
+                const heading = document.createElement('div');
+                heading.className = 'verse-section-title';
+                heading.textContent = title;
+                container.appendChild(heading);
+            }
+            const paragraph = document.createElement('p');
+            paragraph.className = 'verse-text';
+            verses.forEach(verse => {
+                const number = document.createElement('span');
+                number.className = 'verse-number';
+                number.textContent = verse.verse;
+                paragraph.append(number, verse.text + ' ');
+            });
+            container.appendChild(paragraph);
+        }
+
+        function toggleVerses(button) {
+            const container = button.nextElementSibling;
+            const expanded = button.getAttribute('aria-expanded') === 'true';
+            button.setAttribute('aria-expanded', expanded ? 'false' : 'true');
+            button.textContent = MESSAGES[expanded ? 'verses.show' : 'verses.hide'];
+            container.hidden = expanded;
+            if (expanded || container.dataset.loaded) {
+                return;
+            }
+
+            const url = '/api/verses/' + encodeURIComponent(button.dataset.book) + '/' + button.dataset.chapter;
+            fetch(url)
+                .then(response => response.ok ? response.json() : Promise.reject())
+                .then(chapter => {
+                    container.dataset.loaded = 'true';
+                    chapter.sections.forEach(section => {
+                        renderVerseGroup(container, section.heading, section.verses);
+                    });
+                    renderVerseGroup(container, chapter.sections.length > 0 ? MESSAGES['verses.chapter_text'] : '', chapter.unassigned);
+                    const translation = document.createElement('div');
+                    translation.className = 'verse-translation';
+                    translation.textContent = chapter.translation;
+                    container.appendChild(translation);
+                })
+                .catch(() => {
+                    container.textContent = MESSAGES['verses.unavailable'];
+                });
+        }
+"#;
+
+#[cfg(feature = "verses")]
+pub use loaded::*;
+
+#[cfg(feature = "verses")]
+mod loaded {
+    use actix_web::{web, HttpResponse, Result};
+    use serde::Serialize;
+    use serde_json::json;
+    use std::collections::HashMap;
+    use std::io;
+    use utoipa::ToSchema;
+
+    use crate::config::DataPath;
+    use crate::reference::heading_range;
+    use crate::{canon, AppState};
+
+    const SAMPLE_NAME: &str = "kjv-sample";
+    const SAMPLE: &str = include_str!("../data/verses/kjv-sample.tsv");
+
+    #[derive(Debug, Clone, Serialize, ToSchema)]
+    pub struct Verse {
+        pub verse: u32,
+        pub text: String,
+    }
+
+    /// Verse text for one translation, keyed by canonical book name and chapter.
+    pub struct VerseText {
+        pub translation: String,
+        chapters: HashMap<(String, u32), Vec<Verse>>,
+    }
+
+    impl VerseText {
+        pub fn parse(translation: &str, source: &str) -> std::result::Result<Self, String> {
+            let mut chapters: HashMap<(String, u32), Vec<Verse>> = HashMap::new();
+
+            for (index, line) in source.lines().enumerate() {
+                if line.trim().is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                let fields: Vec<&str> = line.splitn(4, '\t').collect();
+                let [book, chapter, verse, text] = fields[..] else {
+                    return Err(format!(
+                        "line {}: expected 4 tab-separated fields",
+                        index + 1
+                    ));
+                };
+                let book = canon::lookup(book)
+                    .ok_or_else(|| format!("line {}: unknown book '{}'", index + 1, book))?;
+                let chapter: u32 = chapter
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("line {}: invalid chapter '{}'", index + 1, chapter))?;
+                let verse: u32 = verse
+                    .trim()
+                    .parse()
+                    .map_err(|_| format!("line {}: invalid verse '{}'", index + 1, verse))?;
+
+                chapters
+                    .entry((book.name.to_string(), chapter))
+                    .or_default()
+                    .push(Verse {
+                        verse,
+                        text: text.trim().to_string(),
+                    });
+            }
+
+            for verses in chapters.values_mut() {
+                verses.sort_by_key(|v| v.verse);
+            }
+
+            Ok(VerseText {
+                translation: translation.to_uppercase(),
+                chapters,
+            })
+        }
+
+        /// Loads the configured translation, if there is one, from `dir`.
+        /// The sample is bundled, so it loads even when `dir` lacks it.
+        pub fn load(translation: Option<&str>, dir: &DataPath) -> Option<Self> {
+            let translation = translation?;
+            let path = dir.join(&format!("{}.tsv", translation)).path;
+
+            let source = match std::fs::read_to_string(&path) {
+                Err(err) if err.kind() == io::ErrorKind::NotFound && translation == SAMPLE_NAME => {
+                    Ok(SAMPLE.to_string())
+                }
+                source => source.map_err(|err| err.to_string()),
+            };
+            match source.and_then(|source| VerseText::parse(translation, &source)) {
+                Ok(text) => {
+                    log::info!(
+                        "Verse text enabled: {} ({} chapters)",
+                        text.translation,
+                        text.chapters.len()
+                    );
+                    Some(text)
+                }
+                Err(err) => {
+                    log::warn!("Could not load verses from {}: {}", path.display(), err);
+                    None
+                }
+            }
+        }
+
+        pub fn chapter(&self, book: &str, chapter: u32) -> Option<&[Verse]> {
+            self.chapters
+                .get(&(book.to_string(), chapter))
+                .map(Vec::as_slice)
+        }
+    }
+
+    #[derive(Serialize, ToSchema)]
+    struct SectionVerses<'a> {
+        heading: &'a str,
+        verses: Vec<Verse>,
+    }
+
+    #[derive(Serialize, ToSchema)]
+    struct ChapterVerses<'a> {
+        translation: &'a str,
+        book: &'a str,
+        chapter: u32,
+        sections: Vec<SectionVerses<'a>>,
+        /// Verses not covered by any section's range.
+        unassigned: Vec<Verse>,
+    }
+
+    /// A chapter's verse text grouped by section heading.
+    #[utoipa::path(
+        get,
+        path = "/api/verses/{book}/{chapter}",
+        tag = "references",
+        params(
+            ("book" = String, Path, description = "Book name or abbreviation"),
+            ("chapter" = u32, Path, description = "Chapter number"),
+        ),
+        responses(
+            (status = 200, description = "The chapter's verses grouped under its headings", body = ChapterVerses),
+            (status = 404, description = "Verse text is disabled or missing", body = crate::openapi::ErrorBody),
+        )
+    )]
+    pub async fn api_chapter_verses(
+        data: web::Data<AppState>,
+        path: web::Path<(String, u32)>,
+    ) -> Result<HttpResponse> {
+        let (book_name, chapter_number) = path.into_inner();
+
+        let Some(text) = data.verses.as_deref() else {
+            return Ok(HttpResponse::NotFound().json(json!({ "error": "Verse text is disabled" })));
+        };
+        let name = canon::lookup(&book_name).map_or(book_name.as_str(), |info| info.name);
+        let chapter = data
+            .books
+            .iter()
+            .find(|book| book.name == name)
+            .and_then(|book| {
+                book.chapters
+                    .iter()
+                    .find(|c| c.chapter_number == chapter_number)
+            });
+        let (Some(chapter), Some(verses)) = (chapter, text.chapter(name, chapter_number)) else {
+            return Ok(HttpResponse::NotFound().json(json!({
+                "error": format!("No verse text for {} {}", book_name, chapter_number)
+            })));
+        };
+
+        let ranges: Vec<Option<(u32, u32)>> = chapter
+            .sections
+            .iter()
+            .map(|section| heading_range(&section.heading, chapter_number))
+            .collect();
+        // A lone heading without a range covers the whole chapter.
+        let whole_chapter = chapter.sections.len() == 1 && ranges[0].is_none();
+
+        let sections: Vec<SectionVerses> = chapter
+            .sections
+            .iter()
+            .zip(&ranges)
+            .map(|(section, range)| SectionVerses {
+                heading: &section.heading,
+                verses: verses
+                    .iter()
+                    .filter(|v| {
+                        whole_chapter || range.is_some_and(|(s, e)| (s..=e).contains(&v.verse))
+                    })
+                    .cloned()
+                    .collect(),
+            })
+            .collect();
+
+        let unassigned = verses
+            .iter()
+            .filter(|v| {
+                !whole_chapter
+                    && !ranges
+                        .iter()
+                        .flatten()
+                        .any(|&(s, e)| (s..=e).contains(&v.verse))
+            })
+            .cloned()
+            .collect();
+
+        Ok(HttpResponse::Ok().json(ChapterVerses {
+            translation: &text.translation,
+            book: name,
+            chapter: chapter_number,
+            sections,
+            unassigned,
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn vpl_lines_become_verse_rows() {
+        let source = "\u{feff}GEN 1:1 In the beginning God created the heaven and the earth.\n\
+                      1JN 3:16 Hereby perceive we the love of God.\n\
+                      TOB 1:1 The book of the words of Tobit.\n\
+                      PSA 119:176 \n";
+        let imported = import_vpl(source).unwrap();
+        assert_eq!(
+            imported.tsv,
+            "Genesis\t1\t1\tIn the beginning God created the heaven and the earth.\n\
+             1 John\t3\t16\tHereby perceive we the love of God.\n"
+        );
+        assert_eq!(imported.verses, 2);
+        assert_eq!(imported.skipped, ["TOB"]);
+    }
+
+    #[test]
+    fn malformed_vpl_lines_are_reported() {
+        let err = import_vpl("GEN 1:1 Fine.\nGEN one:2 Broken.\n")
+            .err()
+            .unwrap();
+        assert_eq!(err, "line 2: expected 'BOOK chapter:verse text'");
+    }
+}
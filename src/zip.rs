@@ -0,0 +1,180 @@
+//! A minimal ZIP archive writer for the packaged exports (EPUB, Anki).
+//! Entries are written in order, either stored or deflated, with a fixed
+//! timestamp so the same files always produce the same archive.
+
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::io::Write;
+
+/// 1980-01-01 00:00, the earliest MS-DOS timestamp.
+const DOS_DATE: u16 = (1 << 5) | 1;
+const DOS_TIME: u16 = 0;
+/// General purpose flag bit 11: file names are UTF-8.
+const UTF8_NAMES: u16 = 1 << 11;
+
+const STORED: u16 = 0;
+const DEFLATED: u16 = 8;
+
+struct Entry {
+    name: String,
+    method: u16,
+    crc: u32,
+    compressed_size: u32,
+    size: u32,
+    offset: u32,
+}
+
+#[derive(Default)]
+pub struct ZipWriter {
+    data: Vec<u8>,
+    entries: Vec<Entry>,
+}
+
+impl ZipWriter {
+    pub fn new() -> Self {
+        ZipWriter::default()
+    }
+
+    /// Adds an uncompressed entry (required for an EPUB's `mimetype`).
+    pub fn stored(&mut self, name: &str, contents: &[u8]) {
+        self.add(name, STORED, contents, contents);
+    }
+
+    pub fn deflated(&mut self, name: &str, contents: &[u8]) {
+        let mut encoder = DeflateEncoder::new(Vec::new(), Compression::default());
+        // Writing to a Vec cannot fail.
+        encoder.write_all(contents).expect("in-memory write");
+        let compressed = encoder.finish().expect("in-memory write");
+        self.add(name, DEFLATED, contents, &compressed);
+    }
+
+    fn add(&mut self, name: &str, method: u16, contents: &[u8], body: &[u8]) {
+        let mut crc = Crc::new();
+        crc.update(contents);
+        let entry = Entry {
+            name: name.to_string(),
+            method,
+            crc: crc.sum(),
+            compressed_size: body.len() as u32,
+            size: contents.len() as u32,
+            offset: self.data.len() as u32,
+        };
+
+        let out = &mut self.data;
+        out.extend_from_slice(&0x0403_4b50u32.to_le_bytes());
+        out.extend_from_slice(&20u16.to_le_bytes()); // version needed to extract
+        out.extend_from_slice(&UTF8_NAMES.to_le_bytes());
+        out.extend_from_slice(&entry.method.to_le_bytes());
+        out.extend_from_slice(&DOS_TIME.to_le_bytes());
+        out.extend_from_slice(&DOS_DATE.to_le_bytes());
+        out.extend_from_slice(&entry.crc.to_le_bytes());
+        out.extend_from_slice(&entry.compressed_size.to_le_bytes());
+        out.extend_from_slice(&entry.size.to_le_bytes());
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(body);
+
+        self.entries.push(entry);
+    }
+
+    /// Writes the central directory and returns the finished archive.
+    pub fn finish(mut self) -> Vec<u8> {
+        let directory_offset = self.data.len() as u32;
+        let out = &mut self.data;
+        for entry in &self.entries {
+            out.extend_from_slice(&0x0201_4b50u32.to_le_bytes());
+            out.extend_from_slice(&20u16.to_le_bytes()); // version made by
+            out.extend_from_slice(&20u16.to_le_bytes()); // version needed to extract
+            out.extend_from_slice(&UTF8_NAMES.to_le_bytes());
+            out.extend_from_slice(&entry.method.to_le_bytes());
+            out.extend_from_slice(&DOS_TIME.to_le_bytes());
+            out.extend_from_slice(&DOS_DATE.to_le_bytes());
+            out.extend_from_slice(&entry.crc.to_le_bytes());
+            out.extend_from_slice(&entry.compressed_size.to_le_bytes());
+            out.extend_from_slice(&entry.size.to_le_bytes());
+            out.extend_from_slice(&(entry.name.len() as u16).to_le_bytes());
+            out.extend_from_slice(&0u16.to_le_bytes()); // extra field length
+            out.extend_from_slice(&0u16.to_le_bytes()); // comment length
+            out.extend_from_slice(&0u16.to_le_bytes()); // disk number
+            out.extend_from_slice(&0u16.to_le_bytes()); // internal attributes
+            out.extend_from_slice(&0u32.to_le_bytes()); // external attributes
+            out.extend_from_slice(&entry.offset.to_le_bytes());
+            out.extend_from_slice(entry.name.as_bytes());
+        }
+        let directory_size = out.len() as u32 - directory_offset;
+
+        let count = self.entries.len() as u16;
+        out.extend_from_slice(&0x0605_4b50u32.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // this disk
+        out.extend_from_slice(&0u16.to_le_bytes()); // disk with the directory
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&count.to_le_bytes());
+        out.extend_from_slice(&directory_size.to_le_bytes());
+        out.extend_from_slice(&directory_offset.to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes()); // comment length
+        self.data
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::DeflateDecoder;
+    use std::io::Read;
+
+    fn u16_at(data: &[u8], at: usize) -> u16 {
+        u16::from_le_bytes([data[at], data[at + 1]])
+    }
+
+    fn u32_at(data: &[u8], at: usize) -> u32 {
+        u32::from_le_bytes(data[at..at + 4].try_into().unwrap())
+    }
+
+    #[test]
+    fn entries_round_trip() {
+        let text = "In the beginning ".repeat(20);
+        let mut zip = ZipWriter::new();
+        zip.stored("mimetype", b"hello");
+        zip.deflated("ch/1.txt", text.as_bytes());
+        let archive = zip.finish();
+
+        // The stored entry comes first, uncompressed, with the CRC-32 of "hello".
+        assert_eq!(u32_at(&archive, 0), 0x0403_4b50);
+        assert_eq!(u16_at(&archive, 8), STORED);
+        assert_eq!(u32_at(&archive, 14), 0x3610_a686);
+        assert_eq!(&archive[30..38], b"mimetype");
+        assert_eq!(&archive[38..43], b"hello");
+
+        // The second entry inflates back to its contents.
+        let header = 43;
+        assert_eq!(u32_at(&archive, header), 0x0403_4b50);
+        assert_eq!(u16_at(&archive, header + 8), DEFLATED);
+        let compressed = u32_at(&archive, header + 18) as usize;
+        assert_eq!(u32_at(&archive, header + 22) as usize, text.len());
+        let body = header + 30 + "ch/1.txt".len();
+        let mut inflated = String::new();
+        DeflateDecoder::new(&archive[body..body + compressed])
+            .read_to_string(&mut inflated)
+            .unwrap();
+        assert_eq!(inflated, text);
+
+        // The end record counts both entries and points at the directory.
+        let end = archive.len() - 22;
+        assert_eq!(u32_at(&archive, end), 0x0605_4b50);
+        assert_eq!(u16_at(&archive, end + 10), 2);
+        let directory = u32_at(&archive, end + 16) as usize;
+        assert_eq!(directory, body + compressed);
+        assert_eq!(u32_at(&archive, directory), 0x0201_4b50);
+    }
+
+    #[test]
+    fn archives_are_reproducible() {
+        let build = || {
+            let mut zip = ZipWriter::new();
+            zip.deflated("a.txt", b"same");
+            zip.finish()
+        };
+        assert_eq!(build(), build());
+    }
+}